@@ -8,9 +8,7 @@
 
 ## Note on Server Implementation
 
-I don't have any plans on implementing the server-side of this any time soon. I ended up implementing the client-side because I couldn't find any good libraries that were compatible with hyper >= 0.11.
-
-Please feel free to submit a pull request, I would gladly review it!
+The `server` module contains a parser for multipart/form-data bodies that works with any `http_body::Body`, including `hyper::body::Incoming`. See `examples/server.rs` for a server that prints the parts of the requests it receives.
 
 ## Alternatives
 
@@ -9,29 +9,53 @@
 use std::error::Error;
 
 use bytes::Bytes;
-use http_body_util::BodyExt;
-use hyper::body::{Body, Incoming};
+use http_body_util::Full;
+use hyper::body::Incoming;
 use hyper::server::conn::http1::Builder;
-use hyper::{service::service_fn, Request, Response};
+use hyper::{service::service_fn, Request, Response, StatusCode};
+use hyper_multipart_rfc7578::server::multipart::Multipart;
 use hyper_util::rt::TokioIo;
 use tokio::net::TcpListener;
 
-async fn index(
-    req: Request<Incoming>,
-) -> Result<Response<impl Body<Data = Bytes, Error = hyper::Error> + Send + Unpin>, hyper::Error> {
-    let body = req.into_body().map_frame(|frame| {
-        if let Some(data) = frame.data_ref() {
-            print!("{}", String::from_utf8_lossy(data));
+async fn index(req: Request<Incoming>) -> Result<Response<Full<Bytes>>, hyper::Error> {
+    let mut multipart = match Multipart::from_request(req) {
+        Ok(multipart) => multipart,
+        Err(e) => {
+            let mut res = Response::new(Full::from(e.to_string()));
+
+            *res.status_mut() = StatusCode::UNSUPPORTED_MEDIA_TYPE;
+
+            return Ok(res);
         }
+    };
+
+    loop {
+        match multipart.next_part().await {
+            Ok(Some(part)) => {
+                println!(
+                    "name: {:?}, filename: {:?}, content-type: {}",
+                    part.name(),
+                    part.filename(),
+                    part.content_type()
+                );
+                println!("{}", String::from_utf8_lossy(part.data()));
+            }
+            Ok(None) => break,
+            Err(e) => {
+                let mut res = Response::new(Full::from(e.to_string()));
 
-        frame
-    });
+                *res.status_mut() = StatusCode::BAD_REQUEST;
+
+                return Ok(res);
+            }
+        }
+    }
 
-    Ok(Response::new(body))
+    Ok(Response::new(Full::default()))
 }
 
-/// This example runs a server that prints requests as it receives them.
-/// It is useful for debugging.
+/// This example runs a server that prints the parts of multipart requests
+/// as it receives them. It is useful for debugging.
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn Error>> {
     let addr = "127.0.0.1:9001";
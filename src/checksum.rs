@@ -0,0 +1,157 @@
+// Copyright 2017 rust-hyper-multipart-rfc7578 Developers
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+//
+
+use std::{
+    fmt::Write as _,
+    io::{self, Write},
+    sync::{Arc, Mutex},
+};
+
+use md5::Md5;
+use sha2::{Digest, Sha256};
+
+/// An algorithm used to compute the checksum of a part.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ChecksumAlgorithm {
+    Md5,
+    Sha256,
+}
+
+/// A handle to the checksum of a part, computed while the part is written.
+///
+/// The digest is available once the content of the part was written in
+/// full. It covers the content as it is sent, after any encodings.
+#[derive(Clone, Debug)]
+pub struct Checksum {
+    algorithm: ChecksumAlgorithm,
+
+    digest: Arc<Mutex<Option<Vec<u8>>>>,
+}
+
+impl Checksum {
+    pub(crate) fn new(algorithm: ChecksumAlgorithm) -> Checksum {
+        Checksum {
+            algorithm,
+            digest: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// Returns the algorithm the checksum is computed with.
+    #[inline]
+    pub fn algorithm(&self) -> ChecksumAlgorithm {
+        self.algorithm
+    }
+
+    /// Returns the digest of the content of the part, or `None` if the
+    /// content wasn't written in full yet.
+    pub fn digest(&self) -> Option<Vec<u8>> {
+        self.digest
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    /// Returns the digest as a lowercase hexadecimal string.
+    pub fn hex(&self) -> Option<String> {
+        self.digest().map(|digest| {
+            digest.iter().fold(String::new(), |mut hex, byte| {
+                let _ = write!(hex, "{:02x}", byte);
+                hex
+            })
+        })
+    }
+
+    /// Starts computing a new digest for the handle.
+    pub(crate) fn hasher(&self) -> Hasher {
+        let state = match self.algorithm {
+            ChecksumAlgorithm::Md5 => State::Md5(Md5::new()),
+            ChecksumAlgorithm::Sha256 => State::Sha256(Sha256::new()),
+        };
+
+        Hasher {
+            state,
+            checksum: self.clone(),
+        }
+    }
+}
+
+enum State {
+    Md5(Md5),
+    Sha256(Sha256),
+}
+
+/// Computes a digest, and stores it in its handle once finished.
+pub(crate) struct Hasher {
+    state: State,
+
+    checksum: Checksum,
+}
+
+impl Hasher {
+    pub(crate) fn update(&mut self, data: &[u8]) {
+        match self.state {
+            State::Md5(ref mut hasher) => hasher.update(data),
+            State::Sha256(ref mut hasher) => hasher.update(data),
+        }
+    }
+
+    pub(crate) fn finish(self) {
+        let digest = match self.state {
+            State::Md5(hasher) => hasher.finalize().to_vec(),
+            State::Sha256(hasher) => hasher.finalize().to_vec(),
+        };
+
+        *self
+            .checksum
+            .digest
+            .lock()
+            .unwrap_or_else(|e| e.into_inner()) = Some(digest);
+    }
+}
+
+/// A writer that computes the checksum of the data written through it.
+pub(crate) struct HashWriter<'a, W: ?Sized> {
+    write: &'a mut W,
+
+    hasher: Option<Hasher>,
+}
+
+impl<'a, W> HashWriter<'a, W>
+where
+    W: Write + ?Sized,
+{
+    pub(crate) fn new(write: &'a mut W, hasher: Option<Hasher>) -> HashWriter<'a, W> {
+        HashWriter { write, hasher }
+    }
+
+    /// Stores the checksum of the data written so far in its handle.
+    pub(crate) fn finish(&mut self) {
+        if let Some(hasher) = self.hasher.take() {
+            hasher.finish();
+        }
+    }
+}
+
+impl<W> Write for HashWriter<'_, W>
+where
+    W: Write + ?Sized,
+{
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let num = self.write.write(buf)?;
+
+        if let Some(ref mut hasher) = self.hasher {
+            hasher.update(&buf[..num]);
+        }
+
+        Ok(num)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.write.flush()
+    }
+}
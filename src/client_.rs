@@ -7,34 +7,83 @@
 //
 
 use std::{
-    mem::MaybeUninit,
+    marker::PhantomData,
+    mem,
     pin::Pin,
     task::{Context, Poll},
 };
 
-use bytes::{BufMut, Bytes, BytesMut};
-use futures::stream::Stream;
+#[cfg(feature = "checksum")]
+use base64::{engine::general_purpose::STANDARD, Engine};
+use bytes::{Buf, BufMut, Bytes, BytesMut};
+use futures::{
+    channel::mpsc,
+    io::{AsyncRead, AsyncWrite, AsyncWriteExt},
+    sink::{Sink, SinkExt},
+    stream::{Stream, StreamExt, TryStreamExt},
+};
+#[cfg(feature = "http")]
 use http::{
     self,
-    header::CONTENT_TYPE,
+    header::{HeaderMap, HeaderName, HeaderValue, CONTENT_LENGTH, CONTENT_TYPE, TRAILER},
     request::{Builder, Request},
+    Uri,
 };
+#[cfg(feature = "http")]
 use http_body::Frame;
+#[cfg(feature = "http")]
 use http_body_util::StreamBody;
 use mime::{self, Mime};
-use rand::{distributions::Alphanumeric, Rng};
-use std::borrow::Borrow;
+use std::borrow::Cow;
+#[cfg(feature = "fs")]
 use std::{
-    fmt::Display,
-    fs::File,
-    io::{self, Cursor, Read, Write},
-    iter::{FromIterator, Peekable},
-    path::Path,
+    collections::VecDeque,
+    fs::{self, File},
+    io::Cursor,
+    path::{Path, PathBuf},
     str::FromStr,
+};
+use std::{
+    collections::{HashMap, HashSet},
+    ffi::OsStr,
+    fmt::{self, Display},
+    io::{self, Read, Seek, SeekFrom, Write},
+    iter::{FromIterator, Peekable},
+    sync::{Arc, Mutex, MutexGuard},
     vec::IntoIter,
 };
 
-use crate::error::Error;
+#[cfg(feature = "checksum")]
+use crate::checksum::{Checksum, ChecksumAlgorithm, HashWriter, Hasher};
+#[cfg(feature = "deadline")]
+use crate::deadline::Deadline;
+#[cfg(feature = "gzip")]
+use crate::encoding::ContentEncoding;
+#[cfg(feature = "throttle")]
+use crate::throttle::RateLimit;
+use crate::{
+    encoding::{Base64Reader, TransferEncoding},
+    entropy::{self, DefaultEntropy, Entropy},
+    error::Error,
+    spawner::{BlockingReader, Spawner},
+    transform::{Transform, Transformed, Transforms},
+};
+#[cfg(feature = "serde")]
+use crate::{error::SerializeError, ser::FormSerializer};
+#[cfg(feature = "throttle")]
+use std::num::NonZeroU64;
+#[cfg(feature = "deadline")]
+use std::time::Duration;
+
+use self::serializer::{HeaderOptions, PartSerializer, Serializer};
+
+#[cfg(feature = "http")]
+pub(crate) mod batch;
+pub mod blocking;
+#[cfg(feature = "fs")]
+pub(crate) mod directory;
+pub(crate) mod local;
+pub(crate) mod serializer;
 
 /// Writes a CLRF.
 fn write_crlf<W>(write: &mut W) -> io::Result<()>
@@ -44,496 +93,5995 @@ where
     write.write_all(b"\r\n")
 }
 
-/// Multipart body that is compatible with Hyper.
-pub struct Body {
-    /// The amount of data to write with each chunk.
-    buf_size: usize,
-
-    /// The active reader.
-    current: Option<Box<dyn Read + Send + 'static>>,
+/// Implements section 4.1.
+///
+/// [See](https://tools.ietf.org/html/rfc7578#section-4.1).
+fn write_boundary<W>(write: &mut W, boundary: &str, crlf: bool) -> io::Result<()>
+where
+    W: Write,
+{
+    if crlf {
+        write_crlf(write)?;
+    }
 
-    /// The parts as an iterator. When the iterator stops
-    /// yielding, the body is fully written.
-    parts: Peekable<IntoIter<Part>>,
+    write.write_all(b"--")?;
+    write.write_all(boundary.as_bytes())
+}
 
-    /// The multipart boundary.
-    boundary: String,
+/// Writes the last form boundary.
+///
+/// [See](https://tools.ietf.org/html/rfc2046#section-5.1).
+fn write_final_boundary<W>(write: &mut W, boundary: &str) -> io::Result<()>
+where
+    W: Write,
+{
+    write_boundary(write, boundary, true)?;
+    write.write_all(b"--")
 }
 
-impl Body {
-    /// Implements section 4.1.
-    ///
-    /// [See](https://tools.ietf.org/html/rfc7578#section-4.1).
-    fn write_boundary<W>(&self, write: &mut W) -> io::Result<()>
-    where
-        W: Write,
-    {
+/// Writes the Content-Disposition, and Content-Type headers.
+///
+/// Which headers are written depends on the multipart subtype:
+///
+///   * multipart/form-data parts always have a Content-Disposition header
+///     with the field name.
+///
+///   * multipart/mixed parts with a filename are sent as attachments.
+///
+///   * multipart/digest parts default to message/rfc822, so the
+///     Content-Type header is only written if it was set explicitly.
+fn write_headers<W>(
+    write: &mut W,
+    part: &Part,
+    subtype: Subtype,
+    encoding: NameEncoding,
+    non_utf8: NonUtf8Filename,
+    content_length: bool,
+) -> io::Result<()>
+where
+    W: Write,
+{
+    write_crlf(write)?;
+    if subtype != Subtype::Digest || part.mime.is_some() || part.charset.is_some() {
+        let mime = part.content_type();
+
+        match part.charset {
+            Some(ref charset) => {
+                write!(write, "Content-Type: {}", mime.essence_str())?;
+                for (name, value) in mime.params().filter(|&(name, _)| name != mime::CHARSET) {
+                    write!(write, "; {}={}", name, value)?;
+                }
+                write!(write, "; charset={}", charset)?;
+            }
+            None => write!(write, "Content-Type: {}", mime)?,
+        }
         write_crlf(write)?;
-        write.write_all(b"--")?;
-        write.write_all(self.boundary.as_bytes())
     }
+    match subtype {
+        Subtype::FormData => {
+            // `name` disposition parameter is required. It should correspond to the
+            // name of a form field.
+            //
+            // [See 4.2](https://tools.ietf.org/html/rfc7578#section-4.2)
+            //
+            write!(
+                write,
+                "Content-Disposition: form-data; name=\"{}\"",
+                encoding.encode(&part.name)
+            )?;
 
-    /// Writes the last form boundary.
-    ///
-    /// [See](https://tools.ietf.org/html/rfc2046#section-5.1).
-    fn write_final_boundary<W>(&self, write: &mut W) -> io::Result<()>
-    where
-        W: Write,
-    {
-        self.write_boundary(write)?;
-        write.write_all(b"--")
+            // `filename` can be supplied for files, but is totally optional.
+            //
+            // [See 4.2](https://tools.ietf.org/html/rfc7578#section-4.2)
+            //
+            if let Some(ref filename) = part.filename {
+                write_filename(
+                    write,
+                    filename,
+                    part.raw_filename.as_deref(),
+                    encoding,
+                    non_utf8,
+                )?;
+            }
+            write_disposition_params(write, part)?;
+            write_crlf(write)?;
+        }
+        Subtype::Mixed => {
+            if let Some(ref filename) = part.filename {
+                write!(write, "Content-Disposition: attachment")?;
+                write_filename(
+                    write,
+                    filename,
+                    part.raw_filename.as_deref(),
+                    encoding,
+                    non_utf8,
+                )?;
+                write_disposition_params(write, part)?;
+                write_crlf(write)?;
+            }
+        }
+        Subtype::Related | Subtype::Alternative | Subtype::Digest => (),
     }
-
-    /// Writes the Content-Disposition, and Content-Type headers.
-    fn write_headers<W>(&self, write: &mut W, part: &Part) -> io::Result<()>
-    where
-        W: Write,
-    {
+    if let Some(ref content_id) = part.content_id {
+        write!(write, "Content-ID: <{}>", content_id)?;
+        write_crlf(write)?;
+    }
+    #[cfg(feature = "gzip")]
+    if let Some(encoding) = part.content_encoding {
+        write!(write, "Content-Encoding: {}", encoding)?;
         write_crlf(write)?;
-        write.write_all(format!("Content-Type: {}", part.content_type).as_bytes())?;
+    }
+    if let Some(encoding) = part.transfer_encoding {
+        write!(write, "Content-Transfer-Encoding: {}", encoding)?;
         write_crlf(write)?;
-        write.write_all(format!("Content-Disposition: {}", part.content_disposition).as_bytes())?;
+    }
+    if let Some(len) = part.content_length().filter(|_| content_length) {
+        write!(write, "Content-Length: {}", len)?;
         write_crlf(write)?;
-        write_crlf(write)
     }
+    write_crlf(write)
 }
 
-impl Stream for Body {
-    type Item = Result<Frame<Bytes>, Error>;
-
-    /// Iterate over each form part, and write it out.
-    #[allow(clippy::only_used_in_recursion)]
-    fn poll_next(mut self: Pin<&mut Self>, ctx: &mut Context) -> Poll<Option<Self::Item>> {
-        let bytes = BytesMut::with_capacity(self.buf_size);
-        let mut writer = bytes.writer();
-
-        if self.current.is_none() {
-            if let Some(part) = self.parts.next() {
-                self.write_boundary(&mut writer)
-                    .map_err(Error::BoundaryWrite)?;
-                self.write_headers(&mut writer, &part)
-                    .map_err(Error::HeaderWrite)?;
-
-                let read = match part.inner {
-                    Inner::Read(read) => read,
-                    Inner::Text(s) => Box::new(Cursor::new(s.into_bytes())),
-                };
+/// Writes the filename parameter of a Content-Disposition header. `raw` is
+/// the original bytes of a filename that isn't valid UTF-8, which are
+/// written depending on `non_utf8`.
+fn write_filename<W>(
+    write: &mut W,
+    filename: &str,
+    raw: Option<&[u8]>,
+    encoding: NameEncoding,
+    non_utf8: NonUtf8Filename,
+) -> io::Result<()>
+where
+    W: Write,
+{
+    match (raw, non_utf8) {
+        (Some(raw), NonUtf8Filename::PercentEncode) => {
+            let keep = |b: u8| b == b' ' || b.is_ascii_graphic() && !b"\"%\\".contains(&b);
 
-                self.current = Some(read);
-            } else {
-                // No current part, and no parts left means there is nothing
-                // left to write.
-                //
-                return Poll::Ready(None);
-            }
+            write!(write, "; filename=\"{}\"", percent_encode(raw, keep))
         }
+        (Some(raw), NonUtf8Filename::Extended) => {
+            // The attr-chars of RFC 5987 are written as they are.
+            //
+            // [See](https://tools.ietf.org/html/rfc5987#section-3.2.1).
+            //
+            let keep = |b: u8| b.is_ascii_alphanumeric() || b"!#$&+-.^_`|~".contains(&b);
 
-        let num = if let Some(ref mut read) = self.current {
-            let buf = writer.get_mut();
-            let chunk = buf.chunk_mut();
-            unsafe { chunk.as_uninit_slice_mut() }.fill(MaybeUninit::zeroed());
-
-            let num = {
-                let data =
-                    unsafe { std::slice::from_raw_parts_mut(chunk.as_mut_ptr(), chunk.len()) };
-                read.read(data).map_err(Error::ContentRead)?
-            };
-
-            unsafe { buf.advance_mut(num) };
+            write!(
+                write,
+                "; filename=\"{}\"; filename*=UTF-8''{}",
+                encoding.encode(filename),
+                percent_encode(raw, keep)
+            )
+        }
+        _ => write!(write, "; filename=\"{}\"", encoding.encode(filename)),
+    }
+}
 
-            num
+/// Percent-encodes every byte that isn't kept as it is.
+fn percent_encode(bytes: &[u8], keep: fn(u8) -> bool) -> String {
+    bytes.iter().fold(String::new(), |mut encoded, &b| {
+        if keep(b) {
+            encoded.push(char::from(b));
         } else {
-            0
-        };
-
-        if num == 0 {
-            // Wrote 0 bytes from the reader, so we reached the EOF for the
-            // current item.
-            //
-            self.current = None;
+            encoded.push_str(&format!("%{:02X}", b));
+        }
+        encoded
+    })
+}
 
-            // Peek to check if there are are any parts not yet written.
-            // If there is nothing, the final boundary can be written.
-            //
-            if self.parts.peek().is_none() {
-                self.write_final_boundary(&mut writer)
-                    .map_err(Error::BoundaryWrite)?;
+/// Returns the bytes of a filename that isn't valid UTF-8, or `None` if it
+/// can be written as it is. On Unix these are the original bytes.
+fn non_utf8_bytes(filename: &OsStr) -> Option<Vec<u8>> {
+    match filename.to_str() {
+        Some(_) => None,
+        None => Some(filename.as_encoded_bytes().to_vec()),
+    }
+}
 
-                Poll::Ready(Some(Ok(Frame::data(writer.into_inner().freeze()))))
-            } else {
-                self.poll_next(ctx)
-            }
+/// Writes the extra Content-Disposition parameters of a part. Values that
+/// aren't tokens are written as quoted strings.
+///
+/// [See](https://tools.ietf.org/html/rfc2183#section-2).
+fn write_disposition_params<W>(write: &mut W, part: &Part) -> io::Result<()>
+where
+    W: Write,
+{
+    for (name, value) in &part.disposition_params {
+        if is_token(value) {
+            write!(write, "; {}={}", name, value)?;
         } else {
-            Poll::Ready(Some(Ok(Frame::data(writer.into_inner().freeze()))))
+            write!(
+                write,
+                "; {}=\"{}\"",
+                name,
+                value.replace('\\', "\\\\").replace('"', "\\\"")
+            )?;
         }
     }
+    Ok(())
+}
 
-    fn size_hint(&self) -> (usize, Option<usize>) {
-        (0, None)
-    }
+/// Returns whether a value is a non-empty token, as defined by RFC 2045.
+///
+/// [See](https://tools.ietf.org/html/rfc2045#section-5.1).
+fn is_token(value: &str) -> bool {
+    !value.is_empty()
+        && value
+            .bytes()
+            .all(|b| b.is_ascii_graphic() && !b"()<>@,;:\\\"/[]?=".contains(&b))
 }
 
-/// Implements the multipart/form-data media type as described by
-/// RFC 7578.
+/// Returns whether a boundary is 1 to 70 characters allowed by RFC 2046,
+/// and doesn't end with a space.
 ///
-/// [See](https://tools.ietf.org/html/rfc7578#section-1).
-pub struct Form {
-    parts: Vec<Part>,
+/// [See](https://tools.ietf.org/html/rfc2046#section-5.1.1).
+fn is_valid_boundary(boundary: &str) -> bool {
+    (1..=70).contains(&boundary.len())
+        && !boundary.ends_with(' ')
+        && boundary
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || b"'()+_,-./:=? ".contains(&b))
+}
 
-    /// The auto-generated boundary as described by 4.1.
-    ///
-    /// [See](https://tools.ietf.org/html/rfc7578#section-4.1).
-    boundary: String,
+/// Returns whether a length exceeds an optional limit.
+#[inline]
+fn exceeds(len: u64, max: Option<u64>) -> bool {
+    max.is_some_and(|max| len > max)
 }
 
-impl Default for Form {
-    /// Creates a new form with the default boundary generator.
-    #[inline]
-    fn default() -> Form {
-        Form::new::<RandomAsciiGenerator>()
+/// Guesses the mime type of a file from the extension of its path. Files
+/// with an unknown extension, or none, are left to the default of file
+/// parts.
+#[cfg(feature = "fs")]
+fn mime_from_path(path: &Path) -> Option<Mime> {
+    let ext = path.extension()?.to_str()?.to_ascii_lowercase();
+    let mime = match &ext[..] {
+        "txt" | "text" => "text/plain",
+        "htm" | "html" => "text/html",
+        "css" => "text/css",
+        "csv" => "text/csv",
+        "md" | "markdown" => "text/markdown",
+        "xml" => "text/xml",
+        "js" | "mjs" => "text/javascript",
+        "json" => "application/json",
+        "pdf" => "application/pdf",
+        "zip" => "application/zip",
+        "gz" => "application/gzip",
+        "tar" => "application/x-tar",
+        "wasm" => "application/wasm",
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "svg" => "image/svg+xml",
+        "bmp" => "image/bmp",
+        "ico" => "image/x-icon",
+        "mp3" => "audio/mpeg",
+        "wav" => "audio/wav",
+        "ogg" => "audio/ogg",
+        "mp4" => "video/mp4",
+        "webm" => "video/webm",
+        _ => return None,
+    };
+
+    Some(Mime::from_str(mime).expect("a valid mime type"))
+}
+
+/// Adds the path that failed to an error.
+#[cfg(feature = "fs")]
+fn path_error(path: &Path, e: io::Error) -> io::Error {
+    io::Error::new(e.kind(), format!("{}: {}", path.display(), e))
+}
+
+/// Renders the boundary and headers of every part, and the final boundary.
+///
+/// All of them are rendered into a single buffer, which is split into one
+/// slice per part, followed by the final boundary.
+fn render_heads(form: &Form) -> (Vec<Bytes>, Bytes) {
+    let serializer = &*form.serializer.0;
+    let mut buf = vec![];
+    let mut lens = Vec::with_capacity(form.parts.len());
+
+    for (i, part) in form.parts.iter().enumerate() {
+        let start = buf.len();
+
+        let options = HeaderOptions {
+            index: i,
+            subtype: form.subtype,
+            name_encoding: form.name_encoding,
+            non_utf8_filename: form.non_utf8_filename,
+            content_length: form.part_content_length,
+        };
+
+        // Writing to a `Vec` can't fail.
+        //
+        serializer
+            .write_boundary(&mut buf, &form.boundary, i > 0 || form.leading_crlf)
+            .and_then(|_| serializer.write_headers(&mut buf, part, &options))
+            .expect("writing to a buffer");
+
+        lens.push(buf.len() - start);
     }
+
+    serializer
+        .write_final_boundary(&mut buf, &form.boundary)
+        .expect("writing to a buffer");
+
+    let mut buf = Bytes::from(buf);
+    let heads = lens.into_iter().map(|len| buf.split_to(len)).collect();
+
+    (heads, buf)
 }
 
-impl Form {
-    /// Creates a new form with the specified boundary generator function.
+/// Multipart body that is compatible with Hyper.
+pub struct Body {
+    /// The amount of data to write with each chunk, unless a part
+    /// overrides it.
+    buf_size: usize,
+
+    /// The amount of data to write with each chunk of the active part.
+    part_buf_size: usize,
+
+    /// An initialized buffer that part content is read into.
+    scratch: Vec<u8>,
+
+    /// The buffer frames are written into, and split off from.
+    chunk: BytesMut,
+
+    /// Runs reads of blocking readers on a blocking pool, if set.
+    spawner: Option<Spawner>,
+
+    /// The maximum size of the content of a part.
+    max_part_size: Option<u64>,
+
+    /// The maximum size of the whole body.
+    max_body_size: Option<u64>,
+
+    /// The amount of content of the active part written so far.
+    part_len: u64,
+
+    /// The amount of the body written so far.
+    body_len: u64,
+
+    /// The number of frames written before yielding to other tasks.
+    yield_after: usize,
+
+    /// The number of frames left to write before yielding.
+    budget: usize,
+
+    /// Computes the checksum of the active part, if requested.
+    #[cfg(feature = "checksum")]
+    hasher: Option<Hasher>,
+
+    /// An error returned before anything is written, or once the deadline
+    /// expired.
+    error: Option<Error>,
+
+    /// The content of the active part.
+    current: Option<Content>,
+
+    /// The parts as an iterator, along with their pre-rendered boundary
+    /// and headers. When the iterator stops yielding, the body is fully
+    /// written.
+    parts: Peekable<IntoIter<(Bytes, Part)>>,
+
+    /// Receives the parts added to a dynamic form after the other parts,
+    /// until the sender is dropped.
+    receiver: Option<mpsc::Receiver<(Bytes, Part)>>,
+
+    /// The pre-rendered final boundary. It is taken once it is written.
+    final_boundary: Bytes,
+
+    /// The trailers sent after the final boundary. They are taken once
+    /// they are sent.
+    #[cfg(feature = "http")]
+    trailers: Vec<Trailer>,
+
+    /// The name of the active part.
+    part_name: Option<String>,
+
+    /// The index of the active part, or of the next part if no part is
+    /// active.
+    part_index: usize,
+
+    /// The span covering the lifetime of the body.
+    #[cfg(feature = "tracing")]
+    span: tracing::Span,
+
+    /// An optional limit on the number of bytes written per second.
+    #[cfg(feature = "throttle")]
+    rate_limit: Option<RateLimit>,
+
+    /// An optional time the whole body has to be written in.
+    #[cfg(feature = "deadline")]
+    deadline: Option<Deadline>,
+
+    /// Called once the body was written in full. They are taken once they
+    /// are called.
+    on_complete: Vec<Hook>,
+
+    /// Called if the body fails, or is dropped before it was written in
+    /// full. They are taken once they are called, and dropped once the body
+    /// is complete.
+    on_abort: Vec<Hook>,
+
+    /// Whether the body returned an error. It ends right after it.
+    failed: bool,
+
+    /// The number of bytes left to write, if the length of every part is
+    /// known up front (see [`Form::content_length`]).
+    remaining: Option<u64>,
+}
+
+impl Body {
+    /// Limits the rate the body is written at, in bytes per second. When
+    /// the limit is reached, the body waits on a timer before yielding the
+    /// next frame. This requires the Tokio runtime with the time driver
+    /// enabled.
     ///
     /// # Examples
     ///
     /// ```
-    /// # use hyper_multipart_rfc7578::client::multipart;
-    /// # use hyper_multipart_rfc7578::client::multipart::BoundaryGenerator;
-    /// #
-    /// struct TestGenerator;
+    /// use http_body_util::StreamBody;
+    /// use hyper::Request;
+    /// use hyper_multipart_rfc7578::client::multipart;
+    /// use std::num::NonZeroU64;
     ///
-    /// impl BoundaryGenerator for TestGenerator {
-    ///     fn generate_boundary() -> String {
-    ///         "test".to_string()
-    ///     }
-    /// }
+    /// let mut form = multipart::Form::default();
     ///
-    /// let form = multipart::Form::new::<TestGenerator>();
+    /// form.add_text("text", "Hello World!");
+    ///
+    /// let body = multipart::Body::from(form)
+    ///     .with_rate_limit(NonZeroU64::new(64 * 1024).unwrap());
+    /// let req = Request::post("http://localhost/upload")
+    ///     .body(StreamBody::new(body))
+    ///     .unwrap();
     /// ```
-    #[inline]
-    pub fn new<G>() -> Form
-    where
-        G: BoundaryGenerator,
-    {
-        Form {
-            parts: vec![],
-            boundary: G::generate_boundary(),
-        }
+    #[cfg(feature = "throttle")]
+    pub fn with_rate_limit(mut self, bytes_per_second: NonZeroU64) -> Body {
+        self.rate_limit = Some(RateLimit::new(bytes_per_second));
+        self
     }
 
-    /// Updates a request instance with the multipart Content-Type header
-    /// and the payload data.
+    /// Sets the time the whole body has to be written in, counted from the
+    /// first time it is polled. Once it expires, the body yields
+    /// `Error::DeadlineExceeded`, and then ends without writing anything
+    /// else, even if it is waiting on a reader or a rate limit. This
+    /// requires the Tokio runtime with the time driver enabled.
     ///
     /// # Examples
     ///
     /// ```
-    /// use hyper::{Method, Request, Uri};
     /// use hyper_multipart_rfc7578::client::multipart;
+    /// use std::time::Duration;
     ///
-    /// # fn main() {
-    /// let url: Uri = "http://localhost:80/upload".parse().unwrap();
-    /// let mut req_builder = Request::post(url);
     /// let mut form = multipart::Form::default();
     ///
     /// form.add_text("text", "Hello World!");
-    /// let req = form.set_body(req_builder).unwrap();
-    /// # }
+    ///
+    /// let body = multipart::Body::from(form).with_deadline(Duration::from_secs(30));
     /// ```
-    pub fn set_body(self, req: Builder) -> Result<Request<StreamBody<Body>>, http::Error> {
-        let header = format!("multipart/form-data; boundary=\"{}\"", &self.boundary);
-
-        let header: &str = header.as_ref();
+    #[cfg(feature = "deadline")]
+    pub fn with_deadline(mut self, after: Duration) -> Body {
+        self.deadline = Some(Deadline::new(after));
+        self
+    }
 
-        req.header(CONTENT_TYPE, header)
-            .body(StreamBody::new(Body::from(self)))
+    /// Drops whatever is left to write, once the body failed.
+    fn abandon(&mut self) {
+        self.current = None;
+        self.parts = Vec::new().into_iter().peekable();
+        self.receiver = None;
+        self.final_boundary = Bytes::new();
+        self.chunk.clear();
+        #[cfg(feature = "http")]
+        self.trailers.clear();
     }
+}
 
-    /// Adds a text part to the Form.
+impl Body {
+    /// Writes the whole body into an asynchronous writer, such as a file or
+    /// a socket, and returns the number of bytes written. The writer is
+    /// flushed once the body is written.
     ///
     /// # Examples
     ///
     /// ```
     /// use hyper_multipart_rfc7578::client::multipart;
     ///
+    /// # #[tokio::main]
+    /// # async fn main() {
     /// let mut form = multipart::Form::default();
     ///
     /// form.add_text("text", "Hello World!");
-    /// form.add_text("more", String::from("Hello Universe!"));
+    ///
+    /// let mut out = futures::io::Cursor::new(vec![]);
+    /// let len = multipart::Body::from(form).write_into(&mut out).await.unwrap();
+    ///
+    /// assert_eq!(len, out.get_ref().len() as u64);
+    /// # }
     /// ```
-    pub fn add_text<N, T>(&mut self, name: N, text: T)
+    pub async fn write_into<W>(self, mut write: W) -> Result<u64, Error>
     where
-        N: Display,
-        T: Into<String>,
+        W: AsyncWrite + Unpin,
     {
-        self.parts.push(Part::new::<_, String>(
-            Inner::Text(text.into()),
-            name,
-            None,
-            None,
-        ))
+        let mut stream = self.into_data_stream();
+        let mut len = 0;
+
+        while let Some(data) = stream.try_next().await? {
+            write.write_all(&data).await.map_err(Error::BodyWrite)?;
+            len += data.len() as u64;
+        }
+
+        write.flush().await.map_err(Error::BodyWrite)?;
+
+        Ok(len)
     }
 
-    /// Adds a readable part to the Form.
+    /// Collects the whole body into memory. This is meant for small forms
+    /// and tests, since the content of every part is buffered.
     ///
     /// # Examples
     ///
     /// ```
     /// use hyper_multipart_rfc7578::client::multipart;
-    /// use std::io::Cursor;
     ///
-    /// let bytes = Cursor::new("Hello World!");
+    /// # #[tokio::main]
+    /// # async fn main() {
     /// let mut form = multipart::Form::default();
     ///
-    /// form.add_reader("input", bytes);
+    /// form.add_text("text", "Hello World!");
+    ///
+    /// let body = multipart::Body::from(form).collect_bytes().await.unwrap();
+    /// # }
     /// ```
-    pub fn add_reader<F, R>(&mut self, name: F, read: R)
-    where
-        F: Display,
-        R: 'static + Read + Send,
-    {
-        let read = Box::new(read);
+    pub async fn collect_bytes(self) -> Result<Bytes, Error> {
+        let mut stream = self.into_data_stream();
+        let mut buf = BytesMut::new();
 
-        self.parts
-            .push(Part::new::<_, String>(Inner::Read(read), name, None, None));
+        while let Some(data) = stream.try_next().await? {
+            buf.extend_from_slice(&data);
+        }
+
+        Ok(buf.freeze())
     }
 
-    /// Adds a file, and attempts to derive the mime type.
+    /// Turns the body into a stream of the encoded bytes. Unlike the body
+    /// itself, the stream doesn't depend on the http types, so it can be
+    /// sent by any client.
     ///
     /// # Examples
     ///
     /// ```
+    /// use futures::TryStreamExt;
     /// use hyper_multipart_rfc7578::client::multipart;
     ///
+    /// # #[tokio::main]
+    /// # async fn main() {
     /// let mut form = multipart::Form::default();
     ///
-    /// form.add_file("file", file!()).expect("file to exist");
+    /// form.add_text("text", "Hello World!");
+    ///
+    /// let chunks: Vec<_> = multipart::Body::from(form)
+    ///     .into_data_stream()
+    ///     .try_collect()
+    ///     .await
+    ///     .unwrap();
+    /// # }
     /// ```
     #[inline]
-    pub fn add_file<P, F>(&mut self, name: F, path: P) -> io::Result<()>
-    where
-        P: AsRef<Path>,
-        F: Display,
-    {
-        self._add_file(name, path, None)
+    pub fn into_data_stream(self) -> DataStream {
+        DataStream { body: self }
     }
 
-    /// Adds a readable part to the Form as a file.
+    /// Divides the encoded bytes of the body into segments of `size` bytes,
+    /// in order, for chunked upload protocols such as S3 multipart uploads.
+    /// Every segment but the last is exactly `size` bytes long, and each
+    /// one can be sent as its own request body.
+    ///
+    /// One segment is buffered in memory at a time.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `size` is zero.
     ///
     /// # Examples
     ///
     /// ```
+    /// use futures::TryStreamExt;
     /// use hyper_multipart_rfc7578::client::multipart;
-    /// use std::io::Cursor;
     ///
-    /// let bytes = Cursor::new("Hello World!");
+    /// # #[tokio::main]
+    /// # async fn main() {
     /// let mut form = multipart::Form::default();
     ///
-    /// form.add_reader_file("input", bytes, "filename.txt");
+    /// form.add_text("text", "Hello World!");
+    ///
+    /// let mut segments = multipart::Body::from(form).into_chunks(16);
+    ///
+    /// while let Some(segment) = segments.try_next().await.unwrap() {
+    ///     assert!(segment.len() <= 16);
+    /// }
+    /// # }
     /// ```
-    pub fn add_reader_file<F, G, R>(&mut self, name: F, read: R, filename: G)
-    where
-        F: Display,
+    pub fn into_chunks(self, size: usize) -> BodyChunks {
+        assert!(size > 0, "segment size must be greater than zero");
+
+        BodyChunks {
+            body: self,
+            size,
+            buf: BytesMut::new(),
+            done: false,
+        }
+    }
+
+    /// Turns the body into a reader of its encoded bytes, for transports
+    /// that consume readers rather than streams of frames. The reader
+    /// implements the futures `AsyncRead` trait, and Tokio's with the
+    /// `tokio` feature.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use futures::AsyncReadExt;
+    /// use hyper_multipart_rfc7578::client::multipart;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let mut form = multipart::Form::with_generator(&|| "boundary".to_string());
+    ///
+    /// form.add_text("text", "Hello World!");
+    ///
+    /// let mut body = vec![];
+    ///
+    /// multipart::Body::from(form)
+    ///     .into_async_read()
+    ///     .read_to_end(&mut body)
+    ///     .await
+    ///     .unwrap();
+    ///
+    /// assert!(body.ends_with(b"Hello World!\r\n--boundary--"));
+    /// # }
+    /// ```
+    #[inline]
+    pub fn into_async_read(self) -> BodyReader {
+        BodyReader {
+            body: self,
+            buf: Bytes::new(),
+        }
+    }
+
+    /// Iterate over each form part, and write it out.
+    ///
+    /// The boundary and headers of a part are sent in the same frame as the
+    /// first chunk of its content, and the end of a part is sent in the same
+    /// frame as the following boundary, so small parts don't produce frames
+    /// of their own.
+    fn poll_body(&mut self, ctx: &mut Context) -> Poll<Option<Result<Bytes, Error>>> {
+        if let Some(e) = self.error.take() {
+            return Poll::Ready(Some(Err(e)));
+        }
+
+        // The chunk buffer is reused across polls. Once the frames split
+        // off it were dropped, reserving reclaims the same allocation.
+        //
+        self.chunk.reserve(self.buf_size);
+
+        loop {
+            if self.current.is_none() {
+                let next = match (self.parts.next(), &mut self.receiver) {
+                    (None, Some(receiver)) => match receiver.poll_next_unpin(ctx) {
+                        Poll::Ready(next) => next,
+
+                        // Whatever was written so far is sent while waiting
+                        // for the next part.
+                        //
+                        Poll::Pending if self.chunk.is_empty() => return Poll::Pending,
+                        Poll::Pending => return Poll::Ready(Some(Ok(self.chunk.split().freeze()))),
+                    },
+                    (next, _) => next,
+                };
+
+                if let Some((head, part)) = next {
+                    #[cfg(feature = "tracing")]
+                    tracing::debug!(part = %part.name, "writing part");
+
+                    self.part_name = Some(part.name.clone());
+
+                    self.chunk.extend_from_slice(&head);
+
+                    self.part_buf_size = part.chunk_size.unwrap_or(self.buf_size);
+                    self.part_len = 0;
+
+                    #[cfg(feature = "checksum")]
+                    {
+                        self.hasher = part.checksum.as_ref().map(Checksum::hasher);
+                    }
+
+                    let blocking = part.inner.is_blocking();
+                    let content = part.into_content().map_err(|e| self.content_error(e, 0))?;
+
+                    self.current = Some(match (content, &self.spawner) {
+                        (Content::Read(read), Some(spawner)) if blocking => {
+                            Content::AsyncRead(Box::pin(BlockingReader::new(read, spawner.clone())))
+                        }
+                        (content, _) => content,
+                    });
+                } else {
+                    // No current part, and no parts left means there is nothing
+                    // left to write, except for the final boundary of a dynamic
+                    // form once its sender is dropped.
+                    //
+                    self.receiver = None;
+
+                    if self.part_index > 0 {
+                        self.chunk
+                            .extend_from_slice(&mem::take(&mut self.final_boundary));
+                    }
+
+                    if self.chunk.is_empty() {
+                        return Poll::Ready(None);
+                    }
+
+                    return Poll::Ready(Some(Ok(self.chunk.split().freeze())));
+                }
+            }
+
+            match self.current {
+                Some(Content::Bytes(ref mut bytes)) => {
+                    // In-memory content of at least a chunk is sent as its own
+                    // frame, without copying it. Any boundary or headers written
+                    // for the part are sent first. Smaller content is copied
+                    // into the frame, so a small part doesn't cost a frame of
+                    // its own.
+                    //
+                    let large = bytes.len() >= self.part_buf_size;
+
+                    if large && !self.chunk.is_empty() {
+                        return Poll::Ready(Some(Ok(self.chunk.split().freeze())));
+                    }
+
+                    let bytes = mem::take(bytes);
+
+                    self.part_len += bytes.len() as u64;
+
+                    if exceeds(self.part_len, self.max_part_size) {
+                        return Poll::Ready(Some(Err(self.part_too_large())));
+                    }
+
+                    #[cfg(feature = "checksum")]
+                    if let Some(ref mut hasher) = self.hasher {
+                        hasher.update(&bytes);
+                    }
+
+                    if large {
+                        return Poll::Ready(Some(Ok(bytes)));
+                    }
+
+                    self.chunk.extend_from_slice(&bytes);
+                }
+                Some(Content::Read(ref mut read)) => {
+                    // The scratch buffer is only zeroed when it grows, so reading
+                    // doesn't require initializing memory on every poll.
+                    //
+                    if self.scratch.len() < self.part_buf_size {
+                        self.scratch.resize(self.part_buf_size, 0);
+                    }
+
+                    let data = &mut self.scratch[..self.part_buf_size];
+
+                    // A single read is made per poll, so a slow reader such as
+                    // a pipe or a socket sends whatever it has right away. Only
+                    // a read of 0 bytes ends the part.
+                    //
+                    let num = loop {
+                        match read.read(data) {
+                            Ok(n) => break n,
+                            Err(ref e) if e.kind() == io::ErrorKind::Interrupted => (),
+                            Err(e) => return Poll::Ready(Some(Err(self.content_error(e, 0)))),
+                        }
+                    };
+
+                    self.part_len += num as u64;
+
+                    if exceeds(self.part_len, self.max_part_size) {
+                        return Poll::Ready(Some(Err(self.part_too_large())));
+                    }
+
+                    #[cfg(feature = "checksum")]
+                    if let Some(ref mut hasher) = self.hasher {
+                        hasher.update(&data[..num]);
+                    }
+
+                    if num > 0 {
+                        self.chunk.extend_from_slice(&data[..num]);
+
+                        return Poll::Ready(Some(Ok(self.chunk.split().freeze())));
+                    }
+                }
+                Some(Content::AsyncRead(ref mut read)) => {
+                    if self.scratch.len() < self.part_buf_size {
+                        self.scratch.resize(self.part_buf_size, 0);
+                    }
+
+                    let data = &mut self.scratch[..self.part_buf_size];
+                    let mut num = 0;
+                    let mut eof = false;
+
+                    while num < data.len() {
+                        match read.as_mut().poll_read(ctx, &mut data[num..]) {
+                            Poll::Ready(Ok(0)) => {
+                                eof = true;
+                                break;
+                            }
+                            Poll::Ready(Ok(n)) => num += n,
+                            Poll::Ready(Err(ref e)) if e.kind() == io::ErrorKind::Interrupted => (),
+                            Poll::Ready(Err(e)) => {
+                                return Poll::Ready(Some(Err(self.content_error(e, num))))
+                            }
+                            Poll::Pending => break,
+                        }
+                    }
+
+                    self.part_len += num as u64;
+
+                    if exceeds(self.part_len, self.max_part_size) {
+                        return Poll::Ready(Some(Err(self.part_too_large())));
+                    }
+
+                    #[cfg(feature = "checksum")]
+                    if let Some(ref mut hasher) = self.hasher {
+                        hasher.update(&data[..num]);
+                    }
+
+                    self.chunk.extend_from_slice(&data[..num]);
+
+                    // If the chunk is full, or the reader isn't ready, whatever
+                    // was written so far is sent, and reading resumes with the
+                    // next poll.
+                    //
+                    if !eof {
+                        if self.chunk.is_empty() {
+                            return Poll::Pending;
+                        }
+
+                        return Poll::Ready(Some(Ok(self.chunk.split().freeze())));
+                    }
+                }
+                None => (),
+            }
+
+            // Wrote 0 bytes from the reader, so we reached the EOF for the
+            // current item.
+            //
+            self.current = None;
+
+            #[cfg(feature = "checksum")]
+            if let Some(hasher) = self.hasher.take() {
+                hasher.finish();
+            }
+
+            #[cfg_attr(not(feature = "tracing"), allow(unused_variables))]
+            if let Some(name) = self.part_name.take() {
+                #[cfg(feature = "tracing")]
+                tracing::debug!(part = %name, "finished part");
+
+                self.part_index += 1;
+            }
+
+            // Peek to check if there are are any parts not yet written.
+            // If there is nothing, the final boundary can be written.
+            //
+            if self.parts.peek().is_none() && self.receiver.is_none() {
+                self.chunk
+                    .extend_from_slice(&mem::take(&mut self.final_boundary));
+
+                #[cfg(feature = "tracing")]
+                tracing::debug!("wrote final boundary");
+
+                return Poll::Ready(Some(Ok(self.chunk.split().freeze())));
+            }
+
+            // Start the next part in the same frame, unless the frame is
+            // already large enough.
+            //
+            if self.chunk.len() >= self.buf_size {
+                return Poll::Ready(Some(Ok(self.chunk.split().freeze())));
+            }
+        }
+    }
+}
+
+impl Body {
+    /// Returns an error for a failed read of the active part, after `read`
+    /// bytes of the current chunk.
+    fn content_error(&self, source: io::Error, read: usize) -> Error {
+        Error::ContentRead {
+            index: self.part_index,
+            name: self.part_name.clone().unwrap_or_default(),
+            offset: self.part_len + read as u64,
+            source,
+        }
+    }
+
+    /// Returns an error for the active part exceeding the maximum size.
+    fn part_too_large(&self) -> Error {
+        Error::PartTooLarge {
+            index: self.part_index,
+            name: self.part_name.clone().unwrap_or_default(),
+            max: self.max_part_size.unwrap_or_default(),
+        }
+    }
+
+    /// Writes the next chunk of the body, applying the rate limit and
+    /// recording it.
+    #[cfg_attr(not(feature = "throttle"), allow(unused_variables))]
+    fn poll_data(&mut self, ctx: &mut Context) -> Poll<Option<Result<Bytes, Error>>> {
+        #[cfg(feature = "tracing")]
+        let span = self.span.clone();
+        #[cfg(feature = "tracing")]
+        let _enter = span.enter();
+
+        if self.failed {
+            return Poll::Ready(None);
+        }
+
+        // A reader that always has data ready would otherwise keep the task
+        // busy until the whole body is written, starving other tasks and
+        // streams on the same connection.
+        //
+        if self.budget == 0 {
+            self.budget = self.yield_after;
+            ctx.waker().wake_by_ref();
+
+            return Poll::Pending;
+        }
+
+        #[cfg(feature = "deadline")]
+        if let Some(ref mut deadline) = self.deadline {
+            if deadline.poll_expired(ctx).is_ready() {
+                let after = deadline.after();
+
+                self.deadline = None;
+                self.abandon();
+                self.error = Some(Error::DeadlineExceeded { after });
+            }
+        }
+
+        // An error is returned right away, without waiting for the rate
+        // limit.
+        //
+        #[cfg(feature = "throttle")]
+        if let (Some(rate_limit), None) = (self.rate_limit.as_mut(), &self.error) {
+            futures::ready!(rate_limit.poll_ready(ctx));
+        }
+
+        let mut res = self.poll_body(ctx);
+
+        match res {
+            Poll::Ready(Some(Ok(_))) => self.budget -= 1,
+            Poll::Pending => self.budget = self.yield_after,
+            _ => (),
+        }
+
+        if let Poll::Ready(Some(Ok(ref data))) = res {
+            self.body_len += data.len() as u64;
+            self.remaining = self
+                .remaining
+                .map(|remaining| remaining.saturating_sub(data.len() as u64));
+
+            if exceeds(self.body_len, self.max_body_size) {
+                res = Poll::Ready(Some(Err(Error::BodyTooLarge {
+                    max: self.max_body_size.unwrap_or_default(),
+                })));
+            }
+        }
+
+        // A body that failed can't be complete anymore. Whatever is left to
+        // write is dropped, so the error is the last item of the body, and a
+        // part that failed can't be read again. The body ends with the
+        // error, so it is aborted right away, rather than once it is
+        // dropped.
+        //
+        match res {
+            Poll::Ready(Some(Err(_))) => {
+                self.on_complete.clear();
+                self.failed = true;
+                self.remaining = Some(0);
+                self.abandon();
+
+                for hook in mem::take(&mut self.on_abort) {
+                    (hook.0)();
+                }
+            }
+            Poll::Ready(None) => {
+                self.on_abort.clear();
+
+                for hook in mem::take(&mut self.on_complete) {
+                    (hook.0)();
+                }
+            }
+            _ => (),
+        }
+
+        #[cfg(feature = "throttle")]
+        if let (Some(rate_limit), Poll::Ready(Some(Ok(data)))) = (self.rate_limit.as_mut(), &res) {
+            rate_limit.consume(data.len());
+        }
+
+        #[cfg(feature = "tracing")]
+        match res {
+            Poll::Ready(Some(Ok(ref data))) => {
+                tracing::trace!(bytes = data.len(), "emitting frame");
+            }
+            Poll::Ready(Some(Err(ref e))) => {
+                tracing::warn!(part = ?self.part_name, error = %e, "error writing body");
+            }
+            Poll::Ready(None) => tracing::debug!("finished body"),
+            Poll::Pending => (),
+        }
+
+        res
+    }
+
+    /// Returns the bounds on the number of chunks left, from the number of
+    /// bytes left, since every chunk has at least one byte.
+    fn chunks_hint(&self) -> (usize, Option<usize>) {
+        match self.remaining {
+            Some(remaining) => (usize::from(remaining > 0), usize::try_from(remaining).ok()),
+            None => (0, None),
+        }
+    }
+}
+
+#[cfg(feature = "http")]
+impl Stream for Body {
+    type Item = Result<Frame<Bytes>, Error>;
+
+    fn poll_next(mut self: Pin<&mut Self>, ctx: &mut Context) -> Poll<Option<Self::Item>> {
+        match self.poll_data(ctx) {
+            // The trailers are computed once the whole body was written, so
+            // they can depend on its content.
+            //
+            Poll::Ready(None) if !self.trailers.is_empty() => {
+                let trailers = mem::take(&mut self.trailers)
+                    .into_iter()
+                    .map(|trailer| (trailer.name, (trailer.value)()))
+                    .collect::<HeaderMap>();
+
+                Poll::Ready(Some(Ok(Frame::trailers(trailers))))
+            }
+            res => res.map(|data| data.map(|data| data.map(Frame::data))),
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let (lower, upper) = self.chunks_hint();
+        let trailers = usize::from(!self.trailers.is_empty());
+
+        (
+            lower + trailers,
+            upper.and_then(|upper| upper.checked_add(trailers)),
+        )
+    }
+}
+
+/// The body can also be sent as it is, rather than through a `StreamBody`,
+/// so the length of a body with parts of known length is reported to the
+/// client, which can then send a Content-Length header.
+#[cfg(feature = "http")]
+impl http_body::Body for Body {
+    type Data = Bytes;
+    type Error = Error;
+
+    #[inline]
+    fn poll_frame(
+        self: Pin<&mut Self>,
+        ctx: &mut Context,
+    ) -> Poll<Option<Result<Frame<Bytes>, Error>>> {
+        Stream::poll_next(self, ctx)
+    }
+
+    fn size_hint(&self) -> http_body::SizeHint {
+        match self.remaining {
+            Some(remaining) => http_body::SizeHint::with_exact(remaining),
+            None => http_body::SizeHint::default(),
+        }
+    }
+}
+
+/// A stream of the encoded bytes of a multipart body, which doesn't depend
+/// on the http types.
+///
+/// A stream is created with [`Body::into_data_stream`].
+pub struct DataStream {
+    body: Body,
+}
+
+impl Stream for DataStream {
+    type Item = Result<Bytes, Error>;
+
+    fn poll_next(mut self: Pin<&mut Self>, ctx: &mut Context) -> Poll<Option<Self::Item>> {
+        self.body.poll_data(ctx)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.body.chunks_hint()
+    }
+}
+
+/// The encoded bytes of a multipart body, divided into segments of a fixed
+/// size.
+///
+/// The segments are created with
+/// [`Body::into_chunks`](/hyper_multipart_rfc7578/client/multipart/struct.Body.html#method.into_chunks).
+pub struct BodyChunks {
+    body: Body,
+
+    size: usize,
+
+    /// The part of the next segment read so far.
+    buf: BytesMut,
+
+    /// Whether the body was fully read, or failed.
+    done: bool,
+}
+
+impl Stream for BodyChunks {
+    type Item = Result<Bytes, Error>;
+
+    fn poll_next(mut self: Pin<&mut Self>, ctx: &mut Context) -> Poll<Option<Self::Item>> {
+        loop {
+            if self.buf.len() >= self.size {
+                let size = self.size;
+
+                return Poll::Ready(Some(Ok(self.buf.split_to(size).freeze())));
+            }
+
+            if self.done {
+                if self.buf.is_empty() {
+                    return Poll::Ready(None);
+                }
+
+                return Poll::Ready(Some(Ok(self.buf.split().freeze())));
+            }
+
+            match futures::ready!(self.body.poll_data(ctx)) {
+                Some(Ok(data)) => self.buf.extend_from_slice(&data),
+                Some(Err(e)) => {
+                    self.done = true;
+                    self.buf.clear();
+
+                    return Poll::Ready(Some(Err(e)));
+                }
+                None => self.done = true,
+            }
+        }
+    }
+}
+
+/// A reader of the encoded bytes of a multipart body.
+///
+/// A reader is created with
+/// [`Body::into_async_read`](/hyper_multipart_rfc7578/client/multipart/struct.Body.html#method.into_async_read).
+pub struct BodyReader {
+    body: Body,
+
+    /// Data of the current frame that wasn't read yet.
+    buf: Bytes,
+}
+
+impl BodyReader {
+    /// Writes the next frame of the body, unless the previous one wasn't
+    /// read in full. The buffer is empty once the body was written.
+    fn poll_fill(&mut self, ctx: &mut Context) -> Poll<io::Result<()>> {
+        while self.buf.is_empty() {
+            match futures::ready!(self.body.poll_data(ctx)) {
+                Some(data) => self.buf = data?,
+                None => break,
+            }
+        }
+
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl AsyncRead for BodyReader {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        ctx: &mut Context,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        if buf.is_empty() {
+            return Poll::Ready(Ok(0));
+        }
+
+        futures::ready!(self.poll_fill(ctx))?;
+
+        let n = buf.len().min(self.buf.len());
+
+        buf[..n].copy_from_slice(&self.buf[..n]);
+        self.buf.advance(n);
+
+        Poll::Ready(Ok(n))
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl tokio::io::AsyncRead for BodyReader {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        ctx: &mut Context,
+        buf: &mut tokio::io::ReadBuf,
+    ) -> Poll<io::Result<()>> {
+        if buf.remaining() == 0 {
+            return Poll::Ready(Ok(()));
+        }
+
+        futures::ready!(self.poll_fill(ctx))?;
+
+        let n = buf.remaining().min(self.buf.len());
+
+        buf.put_slice(&self.buf.split_to(n));
+
+        Poll::Ready(Ok(()))
+    }
+}
+
+/// Implements the multipart/form-data media type as described by
+/// RFC 7578.
+///
+/// [See](https://tools.ietf.org/html/rfc7578#section-1).
+#[derive(Debug)]
+pub struct Form {
+    parts: Vec<Part>,
+
+    /// The auto-generated boundary as described by 4.1.
+    ///
+    /// [See](https://tools.ietf.org/html/rfc7578#section-4.1).
+    boundary: String,
+
+    /// Generates a new boundary if the content of a part contains the
+    /// boundary. Only known for forms created with `Form::new`.
+    generate_boundary: Option<fn() -> String>,
+
+    /// Whether the boundary parameter in the Content-Type header is quoted.
+    quote_boundary: bool,
+
+    /// Whether the body starts with a CRLF before the first boundary.
+    leading_crlf: bool,
+
+    /// Whether parts of a known length have a Content-Length header.
+    part_content_length: bool,
+
+    /// How names and filenames are written in the headers of each part.
+    name_encoding: NameEncoding,
+
+    /// How filenames that aren't valid UTF-8 are written.
+    non_utf8_filename: NonUtf8Filename,
+
+    /// Whether parts can share a name.
+    duplicate_names: DuplicateNames,
+
+    /// How filenames derived from paths are normalized.
+    #[cfg(feature = "fs")]
+    filename_normalization: FilenameNormalization,
+
+    /// Writes the boundaries and headers of the body.
+    serializer: Serializer,
+
+    /// The configuration of the body created from the form.
+    body_config: BodyConfig,
+
+    /// The multipart subtype of the body.
+    subtype: Subtype,
+
+    /// The Content-ID of the root part of a multipart/related body.
+    related_root: Option<String>,
+
+    /// The base64 encoded MD5 digest of the whole body, sent as the
+    /// Content-MD5 header, once computed.
+    #[cfg(feature = "checksum")]
+    content_md5: Option<String>,
+}
+
+/// The multipart subtype of a body.
+///
+/// [See](https://tools.ietf.org/html/rfc2046#section-5.1).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Subtype {
+    /// multipart/form-data, as described by RFC 7578.
+    FormData,
+
+    /// multipart/related, as described by RFC 2387. Parts don't have a
+    /// Content-Disposition header, and refer to each other by Content-ID.
+    Related,
+
+    /// multipart/mixed, for independent parts. Parts with a filename are
+    /// sent as attachments.
+    ///
+    /// [See](https://tools.ietf.org/html/rfc2046#section-5.1.3).
+    Mixed,
+
+    /// multipart/alternative, for alternative versions of the same
+    /// content, in increasing order of preference.
+    ///
+    /// [See](https://tools.ietf.org/html/rfc2046#section-5.1.4).
+    Alternative,
+
+    /// multipart/digest, where parts default to message/rfc822.
+    ///
+    /// [See](https://tools.ietf.org/html/rfc2046#section-5.1.5).
+    Digest,
+}
+
+/// How names and filenames are written in the Content-Disposition header
+/// of a part. Both are written as quoted strings, so quotes and line breaks
+/// can't be written as they are. Servers differ in what they accept.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum NameEncoding {
+    /// Percent-encodes quotes, CR, and LF, like browsers do. This is the
+    /// default.
+    ///
+    /// [See](https://html.spec.whatwg.org/multipage/form-control-infrastructure.html#multipart-form-data).
+    #[default]
+    Html5,
+
+    /// Writes names and filenames as they are, and fails the body with
+    /// `Error::InvalidName` or `Error::InvalidFilename` if they contain a
+    /// quote, CR, or LF.
+    ///
+    /// [See](https://tools.ietf.org/html/rfc7578#section-4.2).
+    Rfc7578Strict,
+
+    /// Writes names and filenames as they are, without checking them. A
+    /// quote or a line break in a name breaks the headers of the part.
+    Raw,
+}
+
+/// How a filename that isn't valid UTF-8 is written in the
+/// Content-Disposition header of a part. Filenames on Unix can be any
+/// bytes, which a header can't carry as they are.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum NonUtf8Filename {
+    /// Replaces invalid sequences with U+FFFD. This is the default.
+    #[default]
+    Lossy,
+
+    /// Percent-encodes the original bytes that aren't printable ASCII, as
+    /// well as `%`, so the server can decode the original bytes.
+    PercentEncode,
+
+    /// Writes the lossy filename, followed by a `filename*` parameter with
+    /// the percent-encoded original bytes. RFC 7578 discourages
+    /// `filename*`, but some servers prefer it.
+    ///
+    /// [See](https://tools.ietf.org/html/rfc5987).
+    Extended,
+
+    /// Fails the body with `Error::InvalidFilename`.
+    Reject,
+}
+
+/// Whether the parts of a form can share a name. Fields with multiple
+/// values, such as multiple files, are sent as parts with the same name, so
+/// duplicate names are allowed by default.
+///
+/// [See](https://tools.ietf.org/html/rfc7578#section-4.3).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DuplicateNames {
+    /// Allows any number of parts with the same name. This is the default.
+    #[default]
+    Allow,
+
+    /// Fails the body with `Error::DuplicateName`, for servers that only
+    /// keep one value per field.
+    Reject,
+}
+
+/// How filenames derived from the paths of files added to a form are
+/// normalized. Paths on Windows use backslashes, and can start with a drive
+/// letter or a UNC prefix, which many servers store as they are or reject.
+///
+/// By default, filenames are the paths as they were given.
+///
+/// # Examples
+///
+/// ```
+/// use hyper_multipart_rfc7578::client::multipart::FilenameNormalization;
+///
+/// let normalization = FilenameNormalization::new()
+///     .forward_slashes(true)
+///     .strip_prefix(true);
+///
+/// assert_eq!(normalization, FilenameNormalization::windows());
+/// ```
+#[cfg(feature = "fs")]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct FilenameNormalization {
+    forward_slashes: bool,
+
+    strip_prefix: bool,
+}
+
+#[cfg(feature = "fs")]
+impl FilenameNormalization {
+    /// Creates a normalization that leaves filenames as they are.
+    #[inline]
+    pub fn new() -> FilenameNormalization {
+        FilenameNormalization::default()
+    }
+
+    /// Creates a normalization with forward slashes, and without prefixes.
+    #[inline]
+    pub fn windows() -> FilenameNormalization {
+        FilenameNormalization::new()
+            .forward_slashes(true)
+            .strip_prefix(true)
+    }
+
+    /// Sets whether backslashes are replaced with forward slashes.
+    #[inline]
+    pub fn forward_slashes(mut self, forward_slashes: bool) -> FilenameNormalization {
+        self.forward_slashes = forward_slashes;
+        self
+    }
+
+    /// Sets whether drive letters (`C:`), UNC prefixes (`\\server\share`),
+    /// verbatim and device prefixes (`\\?\`, `\\.\`), and leading
+    /// separators are removed, so the filename is a relative path.
+    #[inline]
+    pub fn strip_prefix(mut self, strip_prefix: bool) -> FilenameNormalization {
+        self.strip_prefix = strip_prefix;
+        self
+    }
+
+    /// Normalizes the bytes of a filename. Only ASCII bytes are removed or
+    /// replaced, so a valid UTF-8 filename stays valid.
+    fn apply(self, filename: &[u8]) -> Vec<u8> {
+        let filename = if self.strip_prefix {
+            strip_path_prefix(filename)
+        } else {
+            filename
+        };
+
+        filename
+            .iter()
+            .map(|&b| match b {
+                b'\\' if self.forward_slashes => b'/',
+                b => b,
+            })
+            .collect()
+    }
+
+    /// Normalizes the filename of a part, and its original bytes if it
+    /// isn't valid UTF-8.
+    fn apply_to(self, mut part: Part) -> Part {
+        if let Some(ref mut filename) = part.filename {
+            *filename = String::from_utf8(self.apply(filename.as_bytes()))
+                .expect("normalized filename to be valid UTF-8");
+        }
+
+        if let Some(ref mut raw) = part.raw_filename {
+            *raw = self.apply(raw);
+        }
+
+        part
+    }
+}
+
+/// Returns a path without its Windows prefix and leading separators. Both
+/// kinds of slashes are separators.
+#[cfg(feature = "fs")]
+fn strip_path_prefix(path: &[u8]) -> &[u8] {
+    fn is_separator(b: u8) -> bool {
+        b == b'\\' || b == b'/'
+    }
+
+    // Skips the server and share of a UNC path.
+    //
+    fn skip_components(mut path: &[u8], n: usize) -> &[u8] {
+        for _ in 0..n {
+            path = match path.iter().position(|&b| is_separator(b)) {
+                Some(i) => &path[i + 1..],
+                None => &[],
+            };
+        }
+
+        path
+    }
+
+    let mut rest = path;
+
+    match rest {
+        [a, b, b'?' | b'.', c, tail @ ..]
+            if is_separator(*a) && is_separator(*b) && is_separator(*c) =>
+        {
+            rest = match tail {
+                [u, n, c, s, tail @ ..]
+                    if [*u, *n, *c].eq_ignore_ascii_case(b"UNC") && is_separator(*s) =>
+                {
+                    skip_components(tail, 2)
+                }
+                _ => tail,
+            };
+        }
+        [a, b, tail @ ..] if is_separator(*a) && is_separator(*b) => {
+            rest = skip_components(tail, 2);
+        }
+        _ => (),
+    }
+
+    if let [drive, b':', tail @ ..] = rest {
+        if drive.is_ascii_alphabetic() {
+            rest = tail;
+        }
+    }
+
+    while let [first, tail @ ..] = rest {
+        if !is_separator(*first) {
+            break;
+        }
+
+        rest = tail;
+    }
+
+    rest
+}
+
+impl NameEncoding {
+    /// Encodes a name or filename to write in a quoted string.
+    fn encode(self, value: &str) -> Cow<'_, str> {
+        match self {
+            NameEncoding::Html5 if value.contains(['"', '\r', '\n']) => Cow::Owned(
+                value
+                    .replace('"', "%22")
+                    .replace('\r', "%0D")
+                    .replace('\n', "%0A"),
+            ),
+            NameEncoding::Html5 | NameEncoding::Rfc7578Strict | NameEncoding::Raw => {
+                Cow::Borrowed(value)
+            }
+        }
+    }
+}
+
+impl Display for Subtype {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Subtype::FormData => write!(f, "form-data"),
+            Subtype::Related => write!(f, "related"),
+            Subtype::Mixed => write!(f, "mixed"),
+            Subtype::Alternative => write!(f, "alternative"),
+            Subtype::Digest => write!(f, "digest"),
+        }
+    }
+}
+
+impl Default for Form {
+    /// Creates a new form with the default boundary generator.
+    #[inline]
+    fn default() -> Form {
+        Form::new::<RandomBoundary>()
+    }
+}
+
+impl<N, T> Extend<(N, T)> for Form
+where
+    N: Display,
+    T: Into<String>,
+{
+    /// Adds a text part to the form for each name and value.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hyper_multipart_rfc7578::client::multipart;
+    ///
+    /// let mut form = multipart::Form::default();
+    ///
+    /// form.extend(vec![("text", "Hello World!"), ("more", "Hello Universe!")]);
+    /// ```
+    fn extend<I>(&mut self, iter: I)
+    where
+        I: IntoIterator<Item = (N, T)>,
+    {
+        for (name, text) in iter {
+            self.add_text(name, text);
+        }
+    }
+}
+
+impl<N, T> FromIterator<(N, T)> for Form
+where
+    N: Display,
+    T: Into<String>,
+{
+    /// Creates a new form with the default boundary generator, with a text
+    /// part for each name and value.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hyper_multipart_rfc7578::client::multipart;
+    ///
+    /// let form: multipart::Form = vec![("text", "Hello World!")].into_iter().collect();
+    /// ```
+    fn from_iter<I>(iter: I) -> Form
+    where
+        I: IntoIterator<Item = (N, T)>,
+    {
+        let mut form = Form::default();
+
+        form.extend(iter);
+        form
+    }
+}
+
+impl From<HashMap<String, String>> for Form {
+    /// Creates a new form with the default boundary generator, with a text
+    /// part for each entry of the map. The parts are in the iteration order
+    /// of the map.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hyper_multipart_rfc7578::client::multipart;
+    /// use std::collections::HashMap;
+    ///
+    /// let mut fields = HashMap::new();
+    ///
+    /// fields.insert("text".to_string(), "Hello World!".to_string());
+    ///
+    /// let form = multipart::Form::from(fields);
+    /// ```
+    fn from(fields: HashMap<String, String>) -> Form {
+        fields.into_iter().collect()
+    }
+}
+
+impl Form {
+    /// Creates a new form with the specified boundary generator function.
+    ///
+    /// If the content of a text or bytes part contains the boundary, a new
+    /// boundary is generated when the form is sent with `Form::set_body`,
+    /// `Form::write_to`, or the other methods consuming the form. Creating
+    /// a `Body` directly can't change the boundary, so it returns
+    /// `Error::BoundaryCollision` instead.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use hyper_multipart_rfc7578::client::multipart;
+    /// # use hyper_multipart_rfc7578::client::multipart::BoundaryGenerator;
+    /// #
+    /// struct TestGenerator;
+    ///
+    /// impl BoundaryGenerator for TestGenerator {
+    ///     fn generate_boundary() -> String {
+    ///         "test".to_string()
+    ///     }
+    /// }
+    ///
+    /// let form = multipart::Form::new::<TestGenerator>();
+    /// ```
+    #[inline]
+    pub fn new<G>() -> Form
+    where
+        G: BoundaryGenerator,
+    {
+        let mut form = Form::from_boundary(G::generate_boundary());
+
+        form.generate_boundary = Some(G::generate_boundary);
+        form
+    }
+
+    /// Creates a new form using a boundary generator instance.
+    ///
+    /// Unlike [`Form::new`], the generator can carry state, such as a seeded
+    /// random number generator for reproducible output. Closures returning a
+    /// `String` can be used directly.
+    ///
+    /// Since the generator isn't kept, a boundary contained in the content
+    /// of a part isn't regenerated, and sending the form fails with
+    /// `Error::BoundaryCollision`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use hyper_multipart_rfc7578::client::multipart;
+    /// #
+    /// let form = multipart::Form::with_generator(&|| "test".to_string());
+    /// ```
+    #[inline]
+    pub fn with_generator<G>(generator: &G) -> Form
+    where
+        G: BoundaryGeneratorExt + ?Sized,
+    {
+        Form::from_boundary(generator.generate_boundary())
+    }
+
+    /// Creates a new form with a fixed boundary, for example to reproduce
+    /// the exact body of another request.
+    ///
+    /// A boundary contained in the content of a part isn't regenerated, and
+    /// sending the form fails with `Error::BoundaryCollision`. An invalid
+    /// boundary fails with `Error::InvalidBoundary`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hyper_multipart_rfc7578::client::multipart;
+    ///
+    /// let form = multipart::Form::with_boundary("boundary");
+    ///
+    /// assert_eq!(form.content_type(), "multipart/form-data; boundary=\"boundary\"");
+    /// ```
+    #[inline]
+    pub fn with_boundary<S>(boundary: S) -> Form
+    where
+        S: Into<String>,
+    {
+        Form::from_boundary(boundary.into())
+    }
+
+    /// Returns the boundary of the form. It can change when a form created
+    /// with [`Form::new`] is sent, if the content of a part contains it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hyper_multipart_rfc7578::client::multipart;
+    ///
+    /// let form = multipart::Form::with_boundary("boundary");
+    ///
+    /// assert_eq!(form.boundary(), "boundary");
+    /// ```
+    #[inline]
+    pub fn boundary(&self) -> &str {
+        &self.boundary
+    }
+
+    /// Attempts to clone the form, so the same form can be sent more than
+    /// once. This fails if any part's content is an arbitrary reader. Files
+    /// are reopened when the clone is sent.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hyper::Request;
+    /// use hyper_multipart_rfc7578::client::multipart;
+    ///
+    /// let mut form = multipart::Form::default();
+    ///
+    /// form.add_text("text", "Hello World!");
+    /// form.add_bytes_file("file", &b"Hello World!"[..], "hello.txt");
+    ///
+    /// let retry = form.try_clone().expect("form to be clonable");
+    ///
+    /// let req = form.set_body(Request::post("http://localhost/upload")).unwrap();
+    /// let retry_req = retry.set_body(Request::post("http://localhost/upload")).unwrap();
+    /// ```
+    pub fn try_clone(&self) -> Option<Form> {
+        Some(Form {
+            parts: self
+                .parts
+                .iter()
+                .map(Part::try_clone)
+                .collect::<Option<_>>()?,
+            boundary: self.boundary.clone(),
+            generate_boundary: self.generate_boundary,
+            quote_boundary: self.quote_boundary,
+            leading_crlf: self.leading_crlf,
+            part_content_length: self.part_content_length,
+            name_encoding: self.name_encoding,
+            non_utf8_filename: self.non_utf8_filename,
+            body_config: self.body_config.clone(),
+            duplicate_names: self.duplicate_names,
+            #[cfg(feature = "fs")]
+            filename_normalization: self.filename_normalization,
+            serializer: self.serializer.clone(),
+            subtype: self.subtype,
+            related_root: self.related_root.clone(),
+            #[cfg(feature = "checksum")]
+            content_md5: self.content_md5.clone(),
+        })
+    }
+
+    /// Internal method for creating a form with a fixed boundary.
+    #[inline]
+    fn from_boundary(boundary: String) -> Form {
+        Form {
+            parts: vec![],
+            boundary,
+            generate_boundary: None,
+            quote_boundary: true,
+            leading_crlf: true,
+            part_content_length: false,
+            name_encoding: NameEncoding::default(),
+            non_utf8_filename: NonUtf8Filename::default(),
+            duplicate_names: DuplicateNames::default(),
+            #[cfg(feature = "fs")]
+            filename_normalization: FilenameNormalization::default(),
+            serializer: Serializer::default(),
+            body_config: BodyConfig::default(),
+            subtype: Subtype::FormData,
+            related_root: None,
+            #[cfg(feature = "checksum")]
+            content_md5: None,
+        }
+    }
+
+    /// Returns the index of the first in-memory part whose content contains
+    /// the boundary. Readers and files can't be checked before they are
+    /// sent.
+    fn colliding_part(&self) -> Option<usize> {
+        self.parts
+            .iter()
+            .position(|part| part.contains_boundary(&self.boundary))
+    }
+
+    /// Checks that the form can be encoded, before anything is written.
+    fn check(&self) -> Result<(), Error> {
+        if !is_valid_boundary(&self.boundary) {
+            return Err(Error::InvalidBoundary(self.boundary.clone()));
+        }
+
+        for (index, part) in self.parts.iter().enumerate() {
+            part.check_names(index, self.name_encoding, self.non_utf8_filename)?;
+        }
+
+        if let Some(index) = self.duplicate_parts().next() {
+            return Err(Error::DuplicateName {
+                index,
+                name: self.parts[index].name.clone(),
+            });
+        }
+
+        match self.colliding_part() {
+            Some(index) => Err(Error::BoundaryCollision {
+                index,
+                name: self.parts[index].name.clone(),
+            }),
+            None => Ok(()),
+        }
+    }
+
+    /// Returns the indices of the parts whose name is used by an earlier
+    /// part, if duplicate names aren't allowed.
+    fn duplicate_parts(&self) -> impl Iterator<Item = usize> + '_ {
+        let mut names = HashSet::new();
+
+        self.parts
+            .iter()
+            .enumerate()
+            .filter(move |(_, part)| {
+                self.duplicate_names == DuplicateNames::Reject && !names.insert(&part.name)
+            })
+            .map(|(index, _)| index)
+    }
+
+    /// Checks the whole form before it is sent, and returns every problem
+    /// found, in order: an invalid boundary, names and filenames that can't
+    /// be written, duplicate names if they aren't allowed, a boundary
+    /// contained in the content of a part that can't be regenerated, a form
+    /// without parts, and files that can't be opened.
+    ///
+    /// Problems with the content of readers can only be found when the body
+    /// is written.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hyper_multipart_rfc7578::client::{multipart, Error};
+    ///
+    /// let mut form = multipart::Form::default();
+    ///
+    /// assert!(matches!(form.validate().unwrap_err()[..], [Error::EmptyForm]));
+    ///
+    /// form.add_text("text", "Hello World!");
+    ///
+    /// assert!(form.validate().is_ok());
+    /// ```
+    pub fn validate(&self) -> Result<(), Vec<Error>> {
+        let mut problems = vec![];
+
+        if !is_valid_boundary(&self.boundary) {
+            problems.push(Error::InvalidBoundary(self.boundary.clone()));
+        }
+
+        for (index, part) in self.parts.iter().enumerate() {
+            if let Err(e) = part.check_names(index, self.name_encoding, self.non_utf8_filename) {
+                problems.push(e);
+            }
+        }
+
+        problems.extend(self.duplicate_parts().map(|index| Error::DuplicateName {
+            index,
+            name: self.parts[index].name.clone(),
+        }));
+
+        // A collision is resolved when the form is sent, if the form has a
+        // generator.
+        //
+        if self.generate_boundary.is_none() {
+            problems.extend(self.colliding_part().map(|index| Error::BoundaryCollision {
+                index,
+                name: self.parts[index].name.clone(),
+            }));
+        }
+
+        if self.parts.is_empty() {
+            problems.push(Error::EmptyForm);
+        }
+
+        #[cfg(feature = "fs")]
+        for (index, part) in self.parts.iter().enumerate() {
+            if let Inner::File {
+                ref path,
+                file: None,
+                ..
+            } = part.inner
+            {
+                let res = File::open(path)
+                    .and_then(|file| file.metadata())
+                    .and_then(|meta| {
+                        if meta.is_file() {
+                            Ok(())
+                        } else {
+                            Err(io::Error::new(
+                                io::ErrorKind::InvalidInput,
+                                "expected a file",
+                            ))
+                        }
+                    });
+
+                if let Err(source) = res {
+                    problems.push(Error::UnreadableFile {
+                        index,
+                        name: part.name.clone(),
+                        path: path.clone(),
+                        source,
+                    });
+                }
+            }
+        }
+
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(problems)
+        }
+    }
+
+    /// Generates a new boundary while the content of a part contains the
+    /// boundary, if the form has a generator.
+    fn resolve_boundary_collision(&mut self) {
+        // A generator can return the same boundary every time, so it is
+        // only retried a few times.
+        //
+        const ATTEMPTS: usize = 8;
+
+        if let Some(generate) = self.generate_boundary {
+            for _ in 0..ATTEMPTS {
+                if self.colliding_part().is_none() {
+                    break;
+                }
+
+                self.boundary = generate();
+            }
+        }
+    }
+
+    /// Sets whether the boundary parameter of the Content-Type header is
+    /// wrapped in quotes. Boundaries are quoted by default, but some legacy
+    /// servers fail to parse a quoted boundary.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hyper::Request;
+    /// use hyper_multipart_rfc7578::client::multipart;
+    ///
+    /// let mut form = multipart::Form::default();
+    ///
+    /// form.quote_boundary(false);
+    ///
+    /// let req = form.set_body(Request::post("http://localhost/upload")).unwrap();
+    /// let content_type = req.headers()["content-type"].to_str().unwrap();
+    ///
+    /// assert!(!content_type.contains('"'));
+    /// ```
+    #[inline]
+    pub fn quote_boundary(&mut self, quote: bool) {
+        self.quote_boundary = quote;
+    }
+
+    /// Sets whether a CRLF is written before the first boundary. By default
+    /// the body starts with a CRLF (an empty preamble), which a few strict
+    /// parsers reject. Boundaries after the first are always preceded by a
+    /// CRLF.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hyper_multipart_rfc7578::client::multipart;
+    ///
+    /// let mut form = multipart::Form::default();
+    ///
+    /// form.leading_crlf(false);
+    /// form.add_text("text", "Hello World!");
+    /// ```
+    #[inline]
+    pub fn leading_crlf(&mut self, leading_crlf: bool) {
+        self.leading_crlf = leading_crlf;
+        self.reset_content_md5();
+    }
+
+    /// Sets whether parts have a Content-Length header with the length of
+    /// their content. This is off by default, since RFC 7578 doesn't use
+    /// it, but some servers allocate buffers based on it. The header is
+    /// only written for parts with a length known up front, such as text,
+    /// bytes, and files (see [`Part::content_length`]).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hyper_multipart_rfc7578::client::multipart;
+    ///
+    /// let mut form = multipart::Form::with_generator(&|| "boundary".to_string());
+    ///
+    /// form.part_content_length(true);
+    /// form.add_text("text", "Hello World!");
+    ///
+    /// let (_, body) = form.into_bytes().unwrap();
+    ///
+    /// assert!(std::str::from_utf8(&body).unwrap().contains("Content-Length: 12\r\n"));
+    /// ```
+    #[inline]
+    pub fn part_content_length(&mut self, enabled: bool) {
+        self.part_content_length = enabled;
+        self.reset_content_md5();
+    }
+
+    /// Sets how names and filenames are written in the headers of each
+    /// part. By default, quotes and line breaks are percent-encoded, like
+    /// browsers do (see [`NameEncoding`]).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hyper_multipart_rfc7578::client::multipart::{self, NameEncoding};
+    ///
+    /// let mut form = multipart::Form::with_generator(&|| "boundary".to_string());
+    ///
+    /// form.name_encoding(NameEncoding::Html5);
+    /// form.add_bytes_file("file", &b"a,b,c"[..], "\"data\".csv");
+    ///
+    /// let (_, body) = form.into_bytes().unwrap();
+    ///
+    /// assert!(std::str::from_utf8(&body)
+    ///     .unwrap()
+    ///     .contains("filename=\"%22data%22.csv\""));
+    /// ```
+    #[inline]
+    pub fn name_encoding(&mut self, encoding: NameEncoding) {
+        self.name_encoding = encoding;
+        self.reset_content_md5();
+    }
+
+    /// Sets how filenames that aren't valid UTF-8, such as the names of
+    /// files added by path, are written. By default they are converted
+    /// lossily (see [`NonUtf8Filename`]).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hyper_multipart_rfc7578::client::multipart::{self, NonUtf8Filename};
+    ///
+    /// let mut form = multipart::Form::default();
+    ///
+    /// form.non_utf8_filenames(NonUtf8Filename::Reject);
+    /// ```
+    #[inline]
+    pub fn non_utf8_filenames(&mut self, strategy: NonUtf8Filename) {
+        self.non_utf8_filename = strategy;
+        self.reset_content_md5();
+    }
+
+    /// Sets whether parts can share a name. By default they can (see
+    /// [`DuplicateNames`]).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hyper_multipart_rfc7578::client::multipart::{self, DuplicateNames};
+    ///
+    /// let mut form = multipart::Form::default();
+    ///
+    /// form.duplicate_names(DuplicateNames::Reject);
+    /// ```
+    #[inline]
+    pub fn duplicate_names(&mut self, policy: DuplicateNames) {
+        self.duplicate_names = policy;
+    }
+
+    /// Sets how filenames derived from the paths of files are normalized.
+    /// It applies to files added after it is set. Explicit filenames are
+    /// never normalized.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hyper_multipart_rfc7578::client::multipart::{self, FilenameNormalization};
+    ///
+    /// let mut form = multipart::Form::default();
+    ///
+    /// form.normalize_filenames(FilenameNormalization::windows());
+    /// form.add_file_lazy("file", r"C:\Users\ferris\data.csv");
+    ///
+    /// assert_eq!(form.parts()[0].filename(), Some("Users/ferris/data.csv"));
+    /// ```
+    #[cfg(feature = "fs")]
+    #[inline]
+    pub fn normalize_filenames(&mut self, normalization: FilenameNormalization) {
+        self.filename_normalization = normalization;
+    }
+
+    /// Sets how the boundaries and headers of the body are written. By
+    /// default, they are written by [`DefaultSerializer`](serializer::DefaultSerializer).
+    /// See [`PartSerializer`].
+    #[inline]
+    pub fn part_serializer<S>(&mut self, serializer: S)
+    where
+        S: 'static + PartSerializer,
+    {
+        self.serializer = Serializer(Arc::new(serializer));
+        self.reset_content_md5();
+    }
+
+    /// Sets the multipart subtype of the body. This defaults to
+    /// multipart/form-data. The headers written for each part depend on the
+    /// subtype; see [`Subtype`] for details.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hyper_multipart_rfc7578::client::multipart::{self, Subtype};
+    ///
+    /// let mut form = multipart::Form::default();
+    ///
+    /// form.subtype(Subtype::Related);
+    /// ```
+    #[inline]
+    pub fn subtype(&mut self, subtype: Subtype) {
+        self.subtype = subtype;
+        self.reset_content_md5();
+    }
+
+    /// Limits the size of the content of each part. Once a part exceeds it,
+    /// the body returns `Error::PartTooLarge`. This is a shorthand for
+    /// [`BodyConfig::max_part_size`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hyper_multipart_rfc7578::client::multipart;
+    ///
+    /// let mut form = multipart::Form::default();
+    ///
+    /// form.max_part_size(10 * 1024 * 1024);
+    /// ```
+    #[inline]
+    pub fn max_part_size(&mut self, size: u64) {
+        self.body_config.max_part_size(size);
+    }
+
+    /// Limits the size of the whole body. Once the body exceeds it, the body
+    /// returns `Error::BodyTooLarge`. This is a shorthand for
+    /// [`BodyConfig::max_body_size`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hyper_multipart_rfc7578::client::multipart;
+    ///
+    /// let mut form = multipart::Form::default();
+    ///
+    /// form.max_body_size(100 * 1024 * 1024);
+    /// ```
+    #[inline]
+    pub fn max_body_size(&mut self, size: u64) {
+        self.body_config.max_body_size(size);
+    }
+
+    /// Sets the Content-ID of the root part of a multipart/related body,
+    /// which is sent as the `start` parameter. If it isn't set, the first
+    /// part is the root part.
+    ///
+    /// [See](https://tools.ietf.org/html/rfc2387#section-3.2).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hyper::Request;
+    /// use hyper_multipart_rfc7578::client::multipart::{self, Subtype};
+    ///
+    /// let mut form = multipart::Form::default();
+    ///
+    /// form.subtype(Subtype::Related);
+    /// form.related_root("root@example.com");
+    /// form.add_part(
+    ///     multipart::Part::bytes("image", &b"\x89PNG"[..], None::<String>)
+    ///         .mime(mime::IMAGE_PNG)
+    ///         .content_id("image@example.com"),
+    /// );
+    /// form.add_part(
+    ///     multipart::Part::text("metadata", "{}")
+    ///         .mime(mime::APPLICATION_JSON)
+    ///         .content_id("root@example.com"),
+    /// );
+    ///
+    /// let req = form.set_body(Request::post("http://localhost/upload")).unwrap();
+    /// let content_type = req.headers()["content-type"].to_str().unwrap();
+    ///
+    /// assert!(content_type.starts_with("multipart/related;"));
+    /// assert!(content_type.ends_with("; type=\"application/json\"; start=\"<root@example.com>\""));
+    /// ```
+    #[inline]
+    pub fn related_root<S>(&mut self, content_id: S)
+    where
+        S: Into<String>,
+    {
+        self.related_root = Some(content_id.into());
+    }
+
+    /// Returns the number of parts in the form.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.parts.len()
+    }
+
+    /// Returns whether the form has no parts.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.parts.is_empty()
+    }
+
+    /// Returns the length of the whole body, if the length of every part is
+    /// known up front (see [`Part::content_length`]). This can be sent as
+    /// the Content-Length header, for servers that refuse chunked requests.
+    ///
+    /// The length changes if a colliding boundary is replaced when the body
+    /// is created, so the boundary should be fixed for the length to be
+    /// exact.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hyper_multipart_rfc7578::client::multipart;
+    /// use std::io::Cursor;
+    ///
+    /// let mut form = multipart::Form::with_boundary("boundary");
+    ///
+    /// form.add_text("text", "Hello World!");
+    /// form.add_reader_sized("input", Cursor::new("Hello Universe!"), 15);
+    ///
+    /// let len = form.content_length().unwrap();
+    /// let (_, body) = form.into_bytes().unwrap();
+    ///
+    /// assert_eq!(len, body.len() as u64);
+    /// ```
+    pub fn content_length(&self) -> Option<u64> {
+        let content = self
+            .parts
+            .iter()
+            .map(Part::content_length)
+            .sum::<Option<u64>>()?;
+        let (heads, final_boundary) = render_heads(self);
+        let heads: usize = heads.iter().map(Bytes::len).sum();
+
+        Some(content + heads as u64 + final_boundary.len() as u64)
+    }
+
+    /// Returns the parts of the form, in the order they are sent.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hyper_multipart_rfc7578::client::multipart;
+    ///
+    /// let mut form = multipart::Form::default();
+    ///
+    /// form.add_text("text", "Hello World!");
+    /// form.add_bytes_file("file", &b"Hello World!"[..], "hello.txt");
+    ///
+    /// let names: Vec<_> = form.parts().iter().map(|part| part.name()).collect();
+    ///
+    /// assert_eq!(names, ["text", "file"]);
+    /// ```
+    #[inline]
+    pub fn parts(&self) -> &[Part] {
+        &self.parts
+    }
+
+    /// Returns the parts to change them. The digest of the body, if it
+    /// was computed, no longer covers it, so it is dropped.
+    fn parts_mut(&mut self) -> &mut Vec<Part> {
+        self.reset_content_md5();
+
+        &mut self.parts
+    }
+
+    /// Drops the digest of the body, once the way it is written changes.
+    #[inline]
+    fn reset_content_md5(&mut self) {
+        #[cfg(feature = "checksum")]
+        {
+            self.content_md5 = None;
+        }
+    }
+
+    /// Returns the configuration of the body created from the form, which
+    /// can be updated in place.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hyper_multipart_rfc7578::client::multipart;
+    ///
+    /// let mut form = multipart::Form::default();
+    ///
+    /// form.body_config().chunk_size(256 * 1024);
+    /// ```
+    #[inline]
+    pub fn body_config(&mut self) -> &mut BodyConfig {
+        &mut self.body_config
+    }
+
+    /// Updates a request instance with the multipart Content-Type header
+    /// and the payload data.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hyper::{Method, Request, Uri};
+    /// use hyper_multipart_rfc7578::client::multipart;
+    ///
+    /// # fn main() {
+    /// let url: Uri = "http://localhost:80/upload".parse().unwrap();
+    /// let mut req_builder = Request::post(url);
+    /// let mut form = multipart::Form::default();
+    ///
+    /// form.add_text("text", "Hello World!");
+    /// let req = form.set_body(req_builder).unwrap();
+    /// # }
+    /// ```
+    #[cfg(feature = "http")]
+    pub fn set_body(self, req: Builder) -> Result<Request<StreamBody<Body>>, http::Error> {
+        self.set_body_convert::<Body>(req)
+            .map(|req| req.map(StreamBody::new))
+    }
+
+    /// Updates a request instance with the multipart Content-Type header
+    /// and the payload data, converted into another body type. This is
+    /// useful for clients and services typed over their own body type.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hyper::Request;
+    /// use hyper_multipart_rfc7578::client::multipart;
+    ///
+    /// struct MyBody(multipart::Body);
+    ///
+    /// impl From<multipart::Body> for MyBody {
+    ///     fn from(body: multipart::Body) -> Self {
+    ///         MyBody(body)
+    ///     }
+    /// }
+    ///
+    /// let mut form = multipart::Form::default();
+    ///
+    /// form.add_text("text", "Hello World!");
+    ///
+    /// let req: Request<MyBody> = form
+    ///     .set_body_convert(Request::post("http://localhost/upload"))
+    ///     .unwrap();
+    /// ```
+    #[cfg(feature = "http")]
+    pub fn set_body_convert<B>(mut self, req: Builder) -> Result<Request<B>, http::Error>
+    where
+        B: From<Body>,
+    {
+        self.resolve_boundary_collision();
+
+        let header = self.content_type();
+
+        let header: &str = header.as_ref();
+
+        let req = self.set_content_md5_header(req.header(CONTENT_TYPE, header));
+
+        self.body_config
+            .set_trailer_header(req)
+            .body(B::from(Body::from(self)))
+    }
+
+    /// Sets the Content-MD5 header of a request, if the digest of the body
+    /// was computed.
+    #[cfg(feature = "http")]
+    fn set_content_md5_header(&self, req: Builder) -> Builder {
+        #[cfg(feature = "checksum")]
+        if let Some(ref md5) = self.content_md5 {
+            return req.header(HeaderName::from_static("content-md5"), md5.as_str());
+        }
+
+        req
+    }
+
+    /// Replaces the body of an existing request with the multipart body,
+    /// and sets its Content-Type header. Unlike [`Form::set_body`], this
+    /// works with requests built by another layer, such as a signing or
+    /// authentication middleware. Other headers are kept.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hyper::Request;
+    /// use hyper_multipart_rfc7578::client::multipart;
+    ///
+    /// let req = Request::post("http://localhost/upload")
+    ///     .header("authorization", "Bearer token")
+    ///     .body(())
+    ///     .unwrap();
+    /// let mut form = multipart::Form::default();
+    ///
+    /// form.add_text("text", "Hello World!");
+    ///
+    /// let req = form.set_body_on(req).unwrap();
+    ///
+    /// assert!(req.headers().contains_key("authorization"));
+    /// assert!(req.headers()["content-type"]
+    ///     .to_str()
+    ///     .unwrap()
+    ///     .starts_with("multipart/form-data"));
+    /// ```
+    #[cfg(feature = "http")]
+    pub fn set_body_on<T>(
+        mut self,
+        req: Request<T>,
+    ) -> Result<Request<StreamBody<Body>>, http::Error> {
+        self.resolve_boundary_collision();
+
+        let (mut parts, _) = req.into_parts();
+
+        parts
+            .headers
+            .insert(CONTENT_TYPE, HeaderValue::try_from(self.content_type())?);
+
+        if let Some(names) = self.body_config.trailer_header() {
+            parts.headers.insert(TRAILER, HeaderValue::try_from(names)?);
+        }
+
+        #[cfg(feature = "checksum")]
+        if let Some(ref md5) = self.content_md5 {
+            parts.headers.insert(
+                HeaderName::from_static("content-md5"),
+                HeaderValue::try_from(md5.as_str())?,
+            );
+        }
+
+        Ok(Request::from_parts(
+            parts,
+            StreamBody::new(Body::from(self)),
+        ))
+    }
+
+    /// Creates a POST request to `uri`, with the multipart Content-Type
+    /// header and the payload data.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hyper_multipart_rfc7578::client::multipart;
+    ///
+    /// let mut form = multipart::Form::default();
+    ///
+    /// form.add_text("text", "Hello World!");
+    ///
+    /// let req = form.post("http://localhost/upload").unwrap();
+    ///
+    /// assert_eq!(req.method(), "POST");
+    /// ```
+    #[cfg(feature = "http")]
+    pub fn post<U>(self, uri: U) -> Result<Request<StreamBody<Body>>, http::Error>
+    where
+        Uri: TryFrom<U>,
+        <Uri as TryFrom<U>>::Error: Into<http::Error>,
+    {
+        self.set_body(Request::post(uri))
+    }
+
+    /// Creates a PUT request to `uri`, with the multipart Content-Type
+    /// header and the payload data.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hyper_multipart_rfc7578::client::multipart;
+    ///
+    /// let mut form = multipart::Form::default();
+    ///
+    /// form.add_text("text", "Hello World!");
+    ///
+    /// let req = form.put("http://localhost/upload").unwrap();
+    ///
+    /// assert_eq!(req.method(), "PUT");
+    /// ```
+    #[cfg(feature = "http")]
+    pub fn put<U>(self, uri: U) -> Result<Request<StreamBody<Body>>, http::Error>
+    where
+        Uri: TryFrom<U>,
+        <Uri as TryFrom<U>>::Error: Into<http::Error>,
+    {
+        self.set_body(Request::put(uri))
+    }
+
+    /// Updates a reqwest request with the multipart Content-Type header and
+    /// the payload data.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hyper_multipart_rfc7578::client::multipart;
+    ///
+    /// let client = reqwest::Client::new();
+    /// let mut form = multipart::Form::default();
+    ///
+    /// form.add_text("text", "Hello World!");
+    ///
+    /// let req = form
+    ///     .set_reqwest_body(client.post("http://localhost/upload"))
+    ///     .build()
+    ///     .unwrap();
+    /// ```
+    #[cfg(feature = "reqwest")]
+    pub fn set_reqwest_body(mut self, req: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
+        self.resolve_boundary_collision();
+
+        let req = req.header(reqwest::header::CONTENT_TYPE, self.content_type());
+
+        #[cfg(feature = "checksum")]
+        let req = match self.content_md5 {
+            Some(ref md5) => req.header("content-md5", md5.as_str()),
+            None => req,
+        };
+
+        req.body(reqwest::Body::from(Body::from(self)))
+    }
+
+    /// Computes the MD5 digest of the whole body, and returns it base64
+    /// encoded, as the value of a Content-MD5 header. Some endpoints, such
+    /// as S3-compatible stores, require this header to cover the request
+    /// body. The header is then set by [`Form::set_body`], and the other
+    /// methods setting a request body.
+    ///
+    /// The body is encoded once to compute the digest, and again when it
+    /// is sent, so every part must be replayable: files are reopened, and
+    /// seekable readers are rewound. For a form with readers that can only
+    /// be read once, [`Form::spool`] computes the digest while the body is
+    /// written instead, and [`SpooledBody::set_body`] sends it. Changing
+    /// the parts of the form, or how they are written, drops the digest, so
+    /// it must be computed again.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hyper::Request;
+    /// use hyper_multipart_rfc7578::client::multipart;
+    ///
+    /// let mut form = multipart::Form::default();
+    ///
+    /// form.add_text("text", "Hello World!");
+    ///
+    /// let md5 = form.compute_content_md5().unwrap();
+    /// let req = form.set_body(Request::post("http://localhost/upload")).unwrap();
+    ///
+    /// assert_eq!(req.headers()["content-md5"], md5.as_str());
+    /// ```
+    #[cfg(feature = "checksum")]
+    pub fn compute_content_md5(&mut self) -> io::Result<String> {
+        // The boundary can't change once the digest covers it.
+        //
+        self.resolve_boundary_collision();
+
+        let mut form = self.try_clone().ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "the digest of a form with parts that can't be read twice can't be computed",
+            )
+        })?;
+
+        // Only the body that is sent completes, or is aborted.
+        //
+        form.body_config.on_complete.clear();
+        form.body_config.on_abort.clear();
+        let checksum = Checksum::new(ChecksumAlgorithm::Md5);
+        let mut sink = io::sink();
+        let mut write = HashWriter::new(&mut sink, Some(checksum.hasher()));
+
+        form.write_to(&mut write)?;
+        write.finish();
+
+        let md5 = STANDARD.encode(checksum.digest().expect("digest to be computed"));
+
+        self.content_md5 = Some(md5.clone());
+
+        Ok(md5)
+    }
+
+    /// Writes the whole multipart body to a writer synchronously, and
+    /// returns the number of bytes written. This produces the same bytes as
+    /// the streaming body, for clients that don't use hyper: the size limits
+    /// and the hooks of the [`BodyConfig`] apply the same way.
+    ///
+    /// Parts that can only be read asynchronously, such as the ones added
+    /// with [`Form::add_async_reader`], can't be written without a runtime,
+    /// so a form with any of them fails with
+    /// [`io::ErrorKind::InvalidInput`] before anything is written.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hyper_multipart_rfc7578::client::multipart;
+    ///
+    /// let mut form = multipart::Form::default();
+    ///
+    /// form.add_text("text", "Hello World!");
+    ///
+    /// let mut body = vec![];
+    /// let len = form.write_to(&mut body).unwrap();
+    ///
+    /// assert_eq!(len, body.len() as u64);
+    /// ```
+    pub fn write_to<W>(mut self, write: &mut W) -> io::Result<u64>
+    where
+        W: Write + ?Sized,
+    {
+        self.resolve_boundary_collision();
+
+        if self
+            .parts
+            .iter()
+            .any(|part| matches!(part.inner, Inner::AsyncRead(_)))
+        {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "asynchronous parts can't be written synchronously",
+            ));
+        }
+
+        // Every part is read on this thread, so a blocking read doesn't need
+        // to be moved off the executor.
+        //
+        self.body_config.spawner = None;
+
+        let mut body = Body::from(self);
+        let mut ctx = Context::from_waker(futures::task::noop_waker_ref());
+        let mut len = 0;
+
+        loop {
+            match body.poll_data(&mut ctx) {
+                Poll::Ready(Some(data)) => {
+                    let data = data?;
+
+                    write.write_all(&data)?;
+                    len += data.len() as u64;
+                }
+                Poll::Ready(None) => return Ok(len),
+                // Without asynchronous parts, the body is only pending when
+                // it yields after a number of chunks, so it can be polled
+                // again right away.
+                Poll::Pending => (),
+            }
+        }
+    }
+
+    /// Encodes the whole multipart body in memory. Returns the value of the
+    /// Content-Type header along with the body. This is meant for small
+    /// forms and tests, since the content of every part is buffered.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hyper_multipart_rfc7578::client::multipart;
+    ///
+    /// let mut form = multipart::Form::with_generator(&|| "boundary".to_string());
+    ///
+    /// form.add_text("text", "Hello World!");
+    ///
+    /// let (content_type, body) = form.into_bytes().unwrap();
+    ///
+    /// assert_eq!(content_type, "multipart/form-data; boundary=\"boundary\"");
+    /// assert!(body.ends_with(b"Hello World!\r\n--boundary--"));
+    /// ```
+    pub fn into_bytes(mut self) -> io::Result<(String, Bytes)> {
+        self.resolve_boundary_collision();
+
+        let content_type = self.content_type();
+        let mut writer = BytesMut::new().writer();
+
+        self.write_to(&mut writer)?;
+
+        Ok((content_type, writer.into_inner().freeze()))
+    }
+
+    /// Returns the value of the multipart Content-Type header.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hyper_multipart_rfc7578::client::multipart;
+    ///
+    /// let form = multipart::Form::with_generator(&|| "boundary".to_string());
+    ///
+    /// assert_eq!(form.content_type(), "multipart/form-data; boundary=\"boundary\"");
+    /// ```
+    pub fn content_type(&self) -> String {
+        let mut content_type = if self.quote_boundary {
+            format!(
+                "multipart/{}; boundary=\"{}\"",
+                self.subtype, &self.boundary
+            )
+        } else {
+            format!("multipart/{}; boundary={}", self.subtype, &self.boundary)
+        };
+
+        // The `type` parameter of multipart/related is required, and is the
+        // Content-Type of the root part. The root part is the first part,
+        // unless `start` names another part.
+        //
+        // [See](https://tools.ietf.org/html/rfc2387#section-3).
+        //
+        if self.subtype == Subtype::Related {
+            let root = match self.related_root {
+                Some(ref start) => self
+                    .parts
+                    .iter()
+                    .find(|part| part.content_id.as_ref() == Some(start)),
+                None => self.parts.first(),
+            };
+
+            if let Some(root) = root {
+                content_type.push_str(&format!("; type=\"{}\"", root.content_type()));
+            }
+
+            if let Some(ref start) = self.related_root {
+                content_type.push_str(&format!("; start=\"<{}>\"", start));
+            }
+        }
+
+        content_type
+    }
+
+    /// Turns the form into a factory that can create the same body more
+    /// than once, for example to retry a failed upload. Seekable readers
+    /// are rewound, and files are reopened, for every new body.
+    ///
+    /// If any part's content is an arbitrary reader, the form is returned
+    /// as an error.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hyper::Request;
+    /// use hyper_multipart_rfc7578::client::multipart;
+    /// use std::io::Cursor;
+    ///
+    /// let mut form = multipart::Form::default();
+    ///
+    /// form.add_text("text", "Hello World!");
+    /// form.add_seekable_reader("input", Cursor::new("Hello Universe!"));
+    ///
+    /// let factory = form.into_factory().ok().expect("form to be replayable");
+    ///
+    /// for _ in 0..3 {
+    ///     let req = factory.set_body(Request::post("http://localhost/upload")).unwrap();
+    /// }
+    /// ```
+    // The form is handed back as is, so it can still be sent once.
+    #[allow(clippy::result_large_err)]
+    pub fn into_factory(mut self) -> Result<BodyFactory, Form> {
+        self.resolve_boundary_collision();
+
+        if self
+            .parts
+            .iter()
+            .all(|part| part.inner.is_replayable() && part.transforms.is_empty())
+        {
+            Ok(BodyFactory { form: self })
+        } else {
+            Err(self)
+        }
+    }
+
+    /// Writes the whole body to a temporary file up front, so it can be
+    /// sent with a Content-Length header, and sent again for a retry. This
+    /// is useful for servers that refuse chunked requests, when parts come
+    /// from readers that can only be read once.
+    ///
+    /// The file is deleted once the spooled body and every body created
+    /// from it are dropped.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hyper::Request;
+    /// use hyper_multipart_rfc7578::client::multipart;
+    /// use std::io::Cursor;
+    ///
+    /// let mut form = multipart::Form::default();
+    ///
+    /// form.add_reader("input", Cursor::new("Hello World!"));
+    ///
+    /// let spooled = form.spool().unwrap();
+    /// let req = spooled.set_body(Request::post("http://localhost/upload")).unwrap();
+    ///
+    /// assert_eq!(req.headers()["content-length"], spooled.len().to_string());
+    /// ```
+    #[cfg(feature = "fs")]
+    pub fn spool(self) -> io::Result<SpooledBody> {
+        self.spool_to(tempfile::tempfile()?)
+    }
+
+    /// Writes the whole body to memory up front, like [`Form::spool`]. Up to
+    /// `max_memory` bytes are kept in memory, so small forms don't cause
+    /// any extra I/O. Larger bodies are moved to a temporary file once
+    /// they exceed it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hyper::Request;
+    /// use hyper_multipart_rfc7578::client::multipart;
+    /// use std::io::Cursor;
+    ///
+    /// let mut form = multipart::Form::default();
+    ///
+    /// form.add_reader("input", Cursor::new("Hello World!"));
+    ///
+    /// let buffered = form.buffered(1024 * 1024).unwrap();
+    /// let req = buffered.set_body(Request::post("http://localhost/upload")).unwrap();
+    /// ```
+    #[cfg(feature = "fs")]
+    pub fn buffered(self, max_memory: usize) -> io::Result<SpooledBody> {
+        self.spool_to(tempfile::spooled_tempfile(max_memory))
+    }
+
+    /// Writes the whole body to `storage` up front, such as a file or an
+    /// in-memory buffer, so it can be sent with a Content-Length header,
+    /// and sent again for a retry. The body is written from the current
+    /// position of `storage`, and read back from there to its end.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hyper_multipart_rfc7578::client::multipart;
+    /// use std::io::Cursor;
+    ///
+    /// let mut form = multipart::Form::default();
+    ///
+    /// form.add_reader("input", Cursor::new("Hello World!"));
+    ///
+    /// let spooled = form.spool_to(Cursor::new(vec![])).unwrap();
+    ///
+    /// assert!(spooled.len() > 0);
+    /// ```
+    pub fn spool_to<S>(mut self, mut storage: S) -> io::Result<SpooledBody>
+    where
+        S: 'static + Read + Write + Seek + Send,
+    {
+        self.resolve_boundary_collision();
+
+        let content_type = self.content_type();
+        let body_config = self.body_config.clone();
+        let start = storage.stream_position()?;
+
+        // The hooks are kept for the spooled body, which is the one sent.
+        //
+        self.body_config.on_complete.clear();
+        self.body_config.on_abort.clear();
+
+        // The digest is computed while the body is written, since its
+        // parts may not be readable again.
+        //
+        #[cfg(feature = "checksum")]
+        let (len, content_md5) = {
+            let checksum = Checksum::new(ChecksumAlgorithm::Md5);
+            let mut write = HashWriter::new(&mut storage, Some(checksum.hasher()));
+            let len = self.write_to(&mut write)?;
+
+            write.finish();
+
+            let md5 = STANDARD.encode(checksum.digest().expect("digest to be computed"));
+
+            (len, md5)
+        };
+        #[cfg(not(feature = "checksum"))]
+        let len = self.write_to(&mut storage)?;
+
+        storage.flush()?;
+        storage.seek(SeekFrom::Start(start))?;
+
+        Ok(SpooledBody {
+            content_type,
+            len,
+            storage: SharedReader::new(storage),
+            body_config,
+            #[cfg(feature = "checksum")]
+            content_md5,
+        })
+    }
+
+    /// Turns the form into a body that more parts can be added to while it
+    /// is written, for forms whose parts aren't all known up front. The
+    /// parts of the form are written first, followed by the parts added
+    /// with the returned [`Sender`]. The final boundary is written once the
+    /// sender is dropped.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hyper_multipart_rfc7578::client::multipart;
+    ///
+    /// # futures::executor::block_on(async {
+    /// let mut form = multipart::Form::with_boundary("boundary");
+    ///
+    /// form.add_text("first", "Hello World!");
+    ///
+    /// let (mut sender, body) = form.into_channel();
+    /// let content_type = sender.content_type().to_string();
+    ///
+    /// let send = async move {
+    ///     sender.add_text("second", "Hello Universe!").await.unwrap();
+    ///     sender.close();
+    /// };
+    /// let (_, body) = futures::join!(send, body.collect_bytes());
+    ///
+    /// assert!(body.unwrap().ends_with(b"Hello Universe!\r\n--boundary--"));
+    /// # });
+    /// ```
+    pub fn into_channel(mut self) -> (Sender, Body) {
+        self.resolve_boundary_collision();
+
+        let (tx, rx) = mpsc::channel(0);
+        let sender = Sender {
+            content_type: self.content_type(),
+            boundary: self.boundary.clone(),
+            subtype: self.subtype,
+            part_content_length: self.part_content_length,
+            name_encoding: self.name_encoding,
+            non_utf8_filename: self.non_utf8_filename,
+            crlf: !self.parts.is_empty() || self.leading_crlf,
+            index: self.parts.len(),
+            serializer: self.serializer.clone(),
+            sender: tx,
+        };
+        let mut body = Body::from(self);
+
+        if body.error.is_none() {
+            body.receiver = Some(rx);
+        }
+
+        // Parts sent later can't be counted up front.
+        //
+        body.remaining = None;
+
+        (sender, body)
+    }
+
+    /// Creates a new form with the default boundary generator, with a text
+    /// part for each field of a struct or entry of a map.
+    ///
+    /// Serde attributes control how fields are named and skipped. `None`
+    /// values are skipped, and sequences add a part for each of their
+    /// values, with the same name. Nested structs and maps are not supported.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hyper_multipart_rfc7578::client::multipart;
+    /// use serde::Serialize;
+    ///
+    /// #[derive(Serialize)]
+    /// struct Upload {
+    ///     title: String,
+    ///     #[serde(rename = "tag")]
+    ///     tags: Vec<String>,
+    ///     #[serde(skip_serializing_if = "Option::is_none")]
+    ///     description: Option<String>,
+    /// }
+    ///
+    /// let upload = Upload {
+    ///     title: "Hello World!".to_string(),
+    ///     tags: vec!["hello".to_string(), "world".to_string()],
+    ///     description: None,
+    /// };
+    ///
+    /// let form = multipart::Form::from_serialize(&upload).unwrap();
+    /// ```
+    #[cfg(feature = "serde")]
+    pub fn from_serialize<T>(value: &T) -> Result<Form, SerializeError>
+    where
+        T: ?Sized + serde::Serialize,
+    {
+        let mut form = Form::default();
+
+        value.serialize(FormSerializer { form: &mut form })?;
+
+        Ok(form)
+    }
+
+    /// Creates a new form with the default boundary generator, with a text
+    /// part for each field of `application/x-www-form-urlencoded` data, in
+    /// order. Names and values are percent-decoded, and `+` is decoded as a
+    /// space. Invalid UTF-8 is replaced.
+    ///
+    /// Pairs that were already decoded can be collected into a form (see
+    /// `FromIterator`).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hyper_multipart_rfc7578::client::multipart;
+    ///
+    /// let form = multipart::Form::from_urlencoded(b"title=Hello+World%21&tag=a&tag=b");
+    ///
+    /// assert_eq!(form.parts().len(), 3);
+    /// ```
+    #[cfg(feature = "urlencoded")]
+    pub fn from_urlencoded(input: &[u8]) -> Form {
+        form_urlencoded::parse(input).collect()
+    }
+
+    /// Adds a text part for each field of
+    /// `application/x-www-form-urlencoded` data. See
+    /// [`Form::from_urlencoded`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hyper_multipart_rfc7578::client::multipart;
+    ///
+    /// let mut form = multipart::Form::default();
+    ///
+    /// form.add_urlencoded(b"title=Hello+World%21");
+    /// ```
+    #[cfg(feature = "urlencoded")]
+    pub fn add_urlencoded(&mut self, input: &[u8]) {
+        self.extend(form_urlencoded::parse(input));
+    }
+
+    /// Adds the special `_charset_` field as the first part of the Form. It
+    /// tells the server which charset to assume for text parts that don't
+    /// carry a charset parameter of their own.
+    ///
+    /// [See](https://tools.ietf.org/html/rfc7578#section-4.6).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hyper_multipart_rfc7578::client::multipart;
+    ///
+    /// let mut form = multipart::Form::default();
+    ///
+    /// form.add_text("text", "Hello World!");
+    /// form.add_charset("iso-8859-1");
+    /// ```
+    pub fn add_charset<S>(&mut self, charset: S)
+    where
+        S: Into<String>,
+    {
+        self.parts_mut().insert(
+            0,
+            Part::new::<_, String>(
+                Inner::Text(Bytes::from(charset.into())),
+                "_charset_",
+                None,
+                None,
+            ),
+        )
+    }
+
+    /// Adds a text part to the Form.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hyper_multipart_rfc7578::client::multipart;
+    ///
+    /// let mut form = multipart::Form::default();
+    ///
+    /// form.add_text("text", "Hello World!");
+    /// form.add_text("more", String::from("Hello Universe!"));
+    /// ```
+    pub fn add_text<N, T>(&mut self, name: N, text: T)
+    where
+        N: Display,
+        T: Into<String>,
+    {
+        self.parts_mut().push(Part::new::<_, String>(
+            Inner::Text(Bytes::from(text.into())),
+            name,
+            None,
+            None,
+        ))
+    }
+
+    /// Adds multiple values for one field, as one text part per value with
+    /// the same name. This is how array-valued fields are sent.
+    ///
+    /// [See](https://tools.ietf.org/html/rfc7578#section-4.3).
+    ///
+    /// The parts are added in the order of the values, and are sent in that
+    /// order, right after each other.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hyper_multipart_rfc7578::client::multipart;
+    ///
+    /// let mut form = multipart::Form::default();
+    ///
+    /// form.add_texts("tags", ["rust", "http", "multipart"]);
+    ///
+    /// assert_eq!(form.len(), 3);
+    /// assert!(form.parts().iter().all(|part| part.name() == "tags"));
+    /// ```
+    pub fn add_texts<N, I, T>(&mut self, name: N, texts: I)
+    where
+        N: Display,
+        I: IntoIterator<Item = T>,
+        T: Into<String>,
+    {
+        for text in texts {
+            self.add_text(&name, text);
+        }
+    }
+
+    /// Adds a text part to the Form from borrowed or owned data. Static
+    /// strings are embedded without copying them.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hyper_multipart_rfc7578::client::multipart;
+    /// use std::borrow::Cow;
+    ///
+    /// const TEMPLATE: &str = "Hello World!";
+    ///
+    /// let mut form = multipart::Form::default();
+    ///
+    /// form.add_text_cow("text", TEMPLATE);
+    /// form.add_text_cow("more", Cow::Owned(String::from("Hello Universe!")));
+    /// ```
+    pub fn add_text_cow<N, T>(&mut self, name: N, text: T)
+    where
+        N: Display,
+        T: Into<Cow<'static, str>>,
+    {
+        let text = match text.into() {
+            Cow::Borrowed(text) => Bytes::from_static(text.as_bytes()),
+            Cow::Owned(text) => Bytes::from(text),
+        };
+
+        self.parts
+            .push(Part::new::<_, String>(Inner::Text(text), name, None, None))
+    }
+
+    /// Adds a part to the Form containing a value serialized as JSON, with
+    /// a Content-Type of application/json.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hyper_multipart_rfc7578::client::multipart;
+    /// use serde::Serialize;
+    ///
+    /// #[derive(Serialize)]
+    /// struct Metadata {
+    ///     title: String,
+    /// }
+    ///
+    /// let metadata = Metadata {
+    ///     title: "Hello World!".to_string(),
+    /// };
+    /// let mut form = multipart::Form::default();
+    ///
+    /// form.add_json("metadata", &metadata).unwrap();
+    /// form.add_bytes_file("file", &b"Hello World!"[..], "hello.txt");
+    /// ```
+    #[cfg(feature = "serde_json")]
+    pub fn add_json<N, T>(&mut self, name: N, value: &T) -> serde_json::Result<()>
+    where
+        N: Display,
+        T: ?Sized + serde::Serialize,
+    {
+        let json = serde_json::to_vec(value)?;
+
+        self.parts_mut().push(Part::new::<_, String>(
+            Inner::Text(Bytes::from(json)),
+            name,
+            Some(mime::APPLICATION_JSON),
+            None,
+        ));
+
+        Ok(())
+    }
+
+    /// Adds a part to the Form.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hyper_multipart_rfc7578::client::multipart;
+    ///
+    /// let mut form = multipart::Form::default();
+    ///
+    /// form.add_part(multipart::Part::text("data", "a,b,c").mime(mime::TEXT_CSV));
+    /// ```
+    #[inline]
+    pub fn add_part(&mut self, part: Part) {
+        self.parts_mut().push(part);
+    }
+
+    /// Adds a part to the Form, computing a checksum of its content while
+    /// it is written. The returned handle gives the digest once the part
+    /// was sent.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hyper_multipart_rfc7578::client::multipart::{self, ChecksumAlgorithm};
+    ///
+    /// let mut form = multipart::Form::default();
+    ///
+    /// let checksum = form.add_part_with_checksum(
+    ///     multipart::Part::bytes("file", &b"Hello World!"[..], Some("hello.txt")),
+    ///     ChecksumAlgorithm::Sha256,
+    /// );
+    ///
+    /// assert_eq!(checksum.digest(), None);
+    ///
+    /// form.into_bytes().unwrap();
+    ///
+    /// assert_eq!(
+    ///     checksum.hex().unwrap(),
+    ///     "7f83b1657ff1fc53b92dc18148a1d65dfc2d4b1fa3d677284addd200126d9069"
+    /// );
+    /// ```
+    #[cfg(feature = "checksum")]
+    pub fn add_part_with_checksum(
+        &mut self,
+        mut part: Part,
+        algorithm: ChecksumAlgorithm,
+    ) -> Checksum {
+        let checksum = Checksum::new(algorithm);
+
+        part.checksum = Some(checksum.clone());
+        self.parts_mut().push(part);
+
+        checksum
+    }
+
+    /// Adds a file, computing a checksum of its content while it is
+    /// written. The returned handle gives the digest once the file was
+    /// sent.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hyper_multipart_rfc7578::client::multipart::{self, ChecksumAlgorithm};
+    ///
+    /// let mut form = multipart::Form::default();
+    ///
+    /// let checksum = form
+    ///     .add_file_with_checksum("file", file!(), ChecksumAlgorithm::Md5)
+    ///     .expect("file to exist");
+    /// ```
+    #[cfg(all(feature = "checksum", feature = "fs"))]
+    pub fn add_file_with_checksum<P, F>(
+        &mut self,
+        name: F,
+        path: P,
+        algorithm: ChecksumAlgorithm,
+    ) -> io::Result<Checksum>
+    where
+        P: AsRef<Path>,
+        F: Display,
+    {
+        self._add_file(name, path, None, None, None)?;
+
+        let checksum = Checksum::new(algorithm);
+
+        if let Some(part) = self.parts_mut().last_mut() {
+            part.checksum = Some(checksum.clone());
+        }
+
+        Ok(checksum)
+    }
+
+    /// Inserts a part into the Form at a position, shifting the parts after
+    /// it. Parts are sent in order, and some servers require fields in a
+    /// specific order, such as the file last.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is greater than the number of parts.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hyper_multipart_rfc7578::client::multipart;
+    ///
+    /// let mut form = multipart::Form::default();
+    ///
+    /// form.add_text("key", "uploads/hello.txt");
+    /// form.add_bytes_file("file", &b"Hello World!"[..], "hello.txt");
+    /// form.insert_part(1, multipart::Part::text("policy", "eyJleHBpcmF0aW9uIjoi..."));
+    ///
+    /// let names: Vec<_> = form.parts().iter().map(|part| part.name()).collect();
+    ///
+    /// assert_eq!(names, ["key", "policy", "file"]);
+    /// ```
+    #[inline]
+    pub fn insert_part(&mut self, index: usize, part: Part) {
+        self.parts_mut().insert(index, part);
+    }
+
+    /// Removes and returns the part at a position, shifting the parts after
+    /// it. Together with [`Form::insert_part`], this moves a part to another
+    /// position.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of bounds.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hyper_multipart_rfc7578::client::multipart;
+    ///
+    /// let mut form = multipart::Form::default();
+    ///
+    /// form.add_bytes_file("file", &b"Hello World!"[..], "hello.txt");
+    /// form.add_text("key", "uploads/hello.txt");
+    ///
+    /// let file = form.remove_part(0);
+    ///
+    /// form.add_part(file);
+    /// ```
+    #[inline]
+    pub fn remove_part(&mut self, index: usize) -> Part {
+        self.parts_mut().remove(index)
+    }
+
+    /// Adds an in-memory bytes part to the Form. The bytes are sent as a
+    /// single frame, without copying them.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bytes::Bytes;
+    /// use hyper_multipart_rfc7578::client::multipart;
+    ///
+    /// let mut form = multipart::Form::default();
+    ///
+    /// form.add_bytes("input", Bytes::from_static(b"Hello World!"));
+    /// form.add_bytes("more", vec![0, 1, 2, 3]);
+    /// ```
+    pub fn add_bytes<F, B>(&mut self, name: F, bytes: B)
+    where
+        F: Display,
+        B: Into<Bytes>,
+    {
+        self.parts_mut().push(Part::new::<_, String>(
+            Inner::Bytes(bytes.into()),
+            name,
+            None,
+            None,
+        ));
+    }
+
+    /// Adds an in-memory bytes part to the Form from borrowed or owned data.
+    /// Static data is embedded without copying it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hyper_multipart_rfc7578::client::multipart;
+    /// use std::borrow::Cow;
+    ///
+    /// static BLOB: [u8; 4] = [0, 1, 2, 3];
+    ///
+    /// let mut form = multipart::Form::default();
+    ///
+    /// form.add_bytes_cow("blob", &BLOB[..]);
+    /// form.add_bytes_cow("more", Cow::Owned(vec![4, 5, 6, 7]));
+    /// ```
+    pub fn add_bytes_cow<F, B>(&mut self, name: F, bytes: B)
+    where
+        F: Display,
+        B: Into<Cow<'static, [u8]>>,
+    {
+        let bytes = match bytes.into() {
+            Cow::Borrowed(bytes) => Bytes::from_static(bytes),
+            Cow::Owned(bytes) => Bytes::from(bytes),
+        };
+
+        self.parts_mut().push(Part::new::<_, String>(
+            Inner::Bytes(bytes),
+            name,
+            None,
+            None,
+        ))
+    }
+
+    /// Adds an in-memory bytes part to the Form as a file.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hyper_multipart_rfc7578::client::multipart;
+    ///
+    /// let mut form = multipart::Form::default();
+    ///
+    /// form.add_bytes_file("input", &b"Hello World!"[..], "filename.txt");
+    /// ```
+    pub fn add_bytes_file<F, G, B>(&mut self, name: F, bytes: B, filename: G)
+    where
+        F: Display,
+        G: Into<String>,
+        B: Into<Bytes>,
+    {
+        self.parts_mut().push(Part::new(
+            Inner::Bytes(bytes.into()),
+            name,
+            None,
+            Some(filename.into()),
+        ));
+    }
+
+    /// Adds a readable part to the Form.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hyper_multipart_rfc7578::client::multipart;
+    /// use std::io::Cursor;
+    ///
+    /// let bytes = Cursor::new("Hello World!");
+    /// let mut form = multipart::Form::default();
+    ///
+    /// form.add_reader("input", bytes);
+    /// ```
+    pub fn add_reader<F, R>(&mut self, name: F, read: R)
+    where
+        F: Display,
+        R: 'static + Read + Send,
+    {
+        let read = Box::new(read);
+
+        self.parts
+            .push(Part::new::<_, String>(Inner::Read(read), name, None, None));
+    }
+
+    /// Adds a part whose reader is created by `open` only when the part
+    /// starts to be written. The reader isn't created at all if the body is
+    /// dropped, or fails, before the part is reached.
+    ///
+    /// An error returned by `open` fails the body like an error reading the
+    /// content of the part.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hyper_multipart_rfc7578::client::multipart;
+    /// use std::io::Cursor;
+    ///
+    /// let mut form = multipart::Form::default();
+    ///
+    /// form.add_lazy("input", || Ok(Cursor::new("Hello World!")));
+    /// ```
+    pub fn add_lazy<F, O, R>(&mut self, name: F, open: O)
+    where
+        F: Display,
+        O: 'static + FnOnce() -> io::Result<R> + Send,
+        R: 'static + Read + Send,
+    {
+        let open = Box::new(move || open().map(|read| Box::new(read) as Box<dyn Read + Send>));
+
+        self.parts
+            .push(Part::new::<_, String>(Inner::Lazy(open), name, None, None));
+    }
+
+    /// Adds a readable part with a known length to the Form, such as a
+    /// stream from an object store. Unlike other readers, the length of the
+    /// part is known up front (see [`Part::content_length`]).
+    ///
+    /// Exactly `len` bytes are sent. If the reader ends before, the body
+    /// fails, since a Content-Length computed from it would be wrong.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hyper_multipart_rfc7578::client::multipart;
+    /// use std::io::Cursor;
+    ///
+    /// let bytes = Cursor::new("Hello World!");
+    /// let mut form = multipart::Form::default();
+    ///
+    /// form.add_reader_sized("input", bytes, 12);
+    ///
+    /// assert_eq!(form.parts()[0].content_length(), Some(12));
+    /// ```
+    pub fn add_reader_sized<F, R>(&mut self, name: F, read: R, len: u64)
+    where
+        F: Display,
+        R: 'static + Read + Send,
+    {
+        self.parts_mut().push(Part::new::<_, String>(
+            Inner::Sized(Box::new(read), len),
+            name,
+            None,
+            None,
+        ));
+    }
+
+    /// Adds a seekable readable part to the Form. Unlike other readers, the
+    /// part can be sent more than once, because the reader is rewound to
+    /// where it started before it is sent again.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hyper_multipart_rfc7578::client::multipart;
+    /// use std::io::Cursor;
+    ///
+    /// let bytes = Cursor::new("Hello World!");
+    /// let mut form = multipart::Form::default();
+    ///
+    /// form.add_seekable_reader("input", bytes);
+    /// ```
+    pub fn add_seekable_reader<F, R>(&mut self, name: F, read: R)
+    where
+        F: Display,
+        R: 'static + Read + Seek + Send,
+    {
+        self.parts_mut().push(Part::new::<_, String>(
+            Inner::Seek(SharedReader::new(read)),
+            name,
+            None,
+            None,
+        ));
+    }
+
+    /// Adds a file, and attempts to derive the mime type.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hyper_multipart_rfc7578::client::multipart;
+    ///
+    /// let mut form = multipart::Form::default();
+    ///
+    /// form.add_file("file", file!()).expect("file to exist");
+    /// ```
+    #[cfg(feature = "fs")]
+    #[inline]
+    pub fn add_file<P, F>(&mut self, name: F, path: P) -> io::Result<()>
+    where
+        P: AsRef<Path>,
+        F: Display,
+    {
+        self._add_file(name, path, None, None, None)
+    }
+
+    /// Adds multiple files for one field, as one part per file with the
+    /// same name, and attempts to derive their mime types.
+    ///
+    /// [See](https://tools.ietf.org/html/rfc7578#section-4.3).
+    ///
+    /// If any file can't be added, none of them are, and the error names
+    /// the path that failed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hyper_multipart_rfc7578::client::multipart;
+    ///
+    /// let mut form = multipart::Form::default();
+    ///
+    /// form.add_files("files", &[file!(), "Cargo.toml"])
+    ///     .expect("files to exist");
+    ///
+    /// assert!(form.add_files("more", &["Cargo.toml", "missing.txt"]).is_err());
+    /// assert_eq!(form.len(), 2);
+    /// ```
+    #[cfg(feature = "fs")]
+    pub fn add_files<F, I, P>(&mut self, name: F, files: I) -> io::Result<()>
+    where
+        F: Display,
+        I: IntoIterator<Item = P>,
+        P: AsRef<Path>,
+    {
+        let len = self.parts.len();
+
+        for path in files {
+            let path = path.as_ref();
+
+            if let Err(e) = self._add_file(&name, path, None, None, None) {
+                self.parts_mut().truncate(len);
+
+                return Err(io::Error::new(
+                    e.kind(),
+                    format!("{}: {}", path.display(), e),
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Adds `len` bytes of a file, starting at `offset`, and attempts to
+    /// derive the mime type. This is useful for resumable uploads, where only
+    /// the remainder of a file is sent again. If the file ends before the
+    /// range does, the rest of the file is sent.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hyper_multipart_rfc7578::client::multipart;
+    ///
+    /// let mut form = multipart::Form::default();
+    ///
+    /// form.add_file_range("file", file!(), 128, 256).expect("file to exist");
+    /// ```
+    #[cfg(feature = "fs")]
+    #[inline]
+    pub fn add_file_range<P, F>(
+        &mut self,
+        name: F,
+        path: P,
+        offset: u64,
+        len: u64,
+    ) -> io::Result<()>
+    where
+        P: AsRef<Path>,
+        F: Display,
+    {
+        self._add_file(name, path, None, None, Some((offset, len)))
+    }
+
+    /// Adds a file without opening it, and attempts to derive the mime type.
+    /// The file is only opened when its part starts streaming, so no file
+    /// descriptor is held while the form is built. Errors opening or reading
+    /// the file are returned by the body.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hyper_multipart_rfc7578::client::multipart;
+    ///
+    /// let mut form = multipart::Form::default();
+    ///
+    /// for path in &[file!(), "Cargo.toml"] {
+    ///     form.add_file_lazy("files", path);
+    /// }
+    /// ```
+    #[cfg(feature = "fs")]
+    pub fn add_file_lazy<P, F>(&mut self, name: F, path: P)
+    where
+        P: AsRef<Path>,
+        F: Display,
+    {
+        let path = path.as_ref();
+        let mime = mime_from_path(path);
+
+        let part = Part::new::<_, String>(
+            Inner::File {
+                path: path.to_path_buf(),
+                file: None,
+                range: None,
+            },
+            name,
+            mime,
+            None,
+        )
+        .filename_os(path);
+        let part = self.filename_normalization.apply_to(part);
+
+        self.parts_mut().push(part);
+    }
+
+    /// Adds a readable part to the Form as a file.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hyper_multipart_rfc7578::client::multipart;
+    /// use std::io::Cursor;
+    ///
+    /// let bytes = Cursor::new("Hello World!");
+    /// let mut form = multipart::Form::default();
+    ///
+    /// form.add_reader_file("input", bytes, "filename.txt");
+    /// ```
+    pub fn add_reader_file<F, G, R>(&mut self, name: F, read: R, filename: G)
+    where
+        F: Display,
+        G: Into<String>,
+        R: 'static + Read + Send,
+    {
+        let read = Box::new(read);
+
+        self.parts_mut().push(Part::new::<_, String>(
+            Inner::Read(read),
+            name,
+            None,
+            Some(filename.into()),
+        ));
+    }
+
+    /// Adds a readable part to the Form as a file with a specified mime.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hyper_multipart_rfc7578::client::multipart;
+    /// use std::io::Cursor;
+    ///
+    /// # fn main() {
+    /// let bytes = Cursor::new("Hello World!");
+    /// let mut form = multipart::Form::default();
+    ///
+    /// form.add_reader_file_with_mime("input", bytes, "filename.txt", mime::TEXT_PLAIN);
+    /// # }
+    /// ```
+    pub fn add_reader_file_with_mime<F, G, R>(&mut self, name: F, read: R, filename: G, mime: Mime)
+    where
+        F: Display,
+        G: Into<String>,
+        R: 'static + Read + Send,
+    {
+        let read = Box::new(read);
+
+        self.parts_mut().push(Part::new::<_, String>(
+            Inner::Read(read),
+            name,
+            Some(mime),
+            Some(filename.into()),
+        ));
+    }
+
+    /// Adds an asynchronous readable part to the Form. The reader is polled
+    /// by the body, so it doesn't block the task writing the body. Readers
+    /// from async-std or smol can be used directly.
+    ///
+    /// Content encodings aren't supported for asynchronous readers.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hyper_multipart_rfc7578::client::multipart;
+    ///
+    /// let bytes = futures::io::Cursor::new("Hello World!");
+    /// let mut form = multipart::Form::default();
+    ///
+    /// form.add_async_reader("input", bytes);
+    /// ```
+    pub fn add_async_reader<F, R>(&mut self, name: F, read: R)
+    where
+        F: Display,
+        R: 'static + AsyncRead + Send,
+    {
+        self.parts
+            .push(Part::async_reader::<_, String, _>(name, read, None));
+    }
+
+    /// Adds an asynchronous readable part to the Form as a file.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hyper_multipart_rfc7578::client::multipart;
+    ///
+    /// let bytes = futures::io::Cursor::new("Hello World!");
+    /// let mut form = multipart::Form::default();
+    ///
+    /// form.add_async_reader_file("input", bytes, "filename.txt");
+    /// ```
+    pub fn add_async_reader_file<F, G, R>(&mut self, name: F, read: R, filename: G)
+    where
+        F: Display,
+        G: Into<String>,
+        R: 'static + AsyncRead + Send,
+    {
+        self.parts
+            .push(Part::async_reader(name, read, Some(filename.into())));
+    }
+
+    /// Adds a part whose content is written through the returned writer,
+    /// for code that writes into an `AsyncWrite`, such as an archive or CSV
+    /// encoder. The part ends when the writer is closed or dropped.
+    ///
+    /// Writes wait until the body is ready for more data, so the writer
+    /// has to be written to concurrently with sending the body.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use futures::io::AsyncWriteExt;
+    /// use hyper_multipart_rfc7578::client::multipart;
+    ///
+    /// # futures::executor::block_on(async {
+    /// let mut form = multipart::Form::default();
+    /// let mut writer = form.add_writer("data", Some("data.csv"), Some(mime::TEXT_CSV));
+    ///
+    /// let write = async move {
+    ///     writer.write_all(b"a,b,c\n").await.unwrap();
+    ///     writer.close().await.unwrap();
+    /// };
+    /// let (_, body) = futures::join!(write, multipart::Body::from(form).collect_bytes());
+    /// # });
+    /// ```
+    pub fn add_writer<F, G>(
+        &mut self,
+        name: F,
+        filename: Option<G>,
+        mime: Option<Mime>,
+    ) -> PartWriter
+    where
+        F: Display,
+        G: Display,
+    {
+        PartWriter {
+            sender: self.add_channel_part(name, filename, mime),
+        }
+    }
+
+    /// Adds a part whose content is sent as chunks through the returned
+    /// channel, so a producer task can push the content while the body is
+    /// written. The part ends when every sender is dropped.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bytes::Bytes;
+    /// use futures::SinkExt;
+    /// use hyper_multipart_rfc7578::client::multipart;
+    ///
+    /// # futures::executor::block_on(async {
+    /// let mut form = multipart::Form::default();
+    /// let mut sender = form.add_channel("input");
+    ///
+    /// let send = async move {
+    ///     sender.send(Bytes::from("Hello ")).await.unwrap();
+    ///     sender.send(Bytes::from("World!")).await.unwrap();
+    /// };
+    /// let (_, body) = futures::join!(send, multipart::Body::from(form).collect_bytes());
+    ///
+    /// assert!(body.unwrap().windows(12).any(|w| w == b"Hello World!"));
+    /// # });
+    /// ```
+    pub fn add_channel<F>(&mut self, name: F) -> mpsc::Sender<Bytes>
+    where
+        F: Display,
+    {
+        self.add_channel_part::<_, String>(name, None, None)
+    }
+
+    /// Internal method for adding a part whose content is received from a
+    /// channel.
+    fn add_channel_part<F, G>(
+        &mut self,
+        name: F,
+        filename: Option<G>,
+        mime: Option<Mime>,
+    ) -> mpsc::Sender<Bytes>
+    where
+        F: Display,
+        G: Display,
+    {
+        let (sender, receiver) = mpsc::channel(0);
+        let read = receiver.map(Ok::<_, io::Error>).into_async_read();
+
+        self.parts_mut().push(Part::new(
+            Inner::AsyncRead(Box::pin(read)),
+            name,
+            mime,
+            filename,
+        ));
+
+        sender
+    }
+
+    /// Adds a Tokio asynchronous readable part to the Form.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hyper_multipart_rfc7578::client::multipart;
+    ///
+    /// let mut form = multipart::Form::default();
+    ///
+    /// form.add_tokio_reader("input", &b"Hello World!"[..]);
+    /// ```
+    #[cfg(feature = "tokio")]
+    pub fn add_tokio_reader<F, R>(&mut self, name: F, read: R)
+    where
+        F: Display,
+        R: 'static + tokio::io::AsyncRead + Send,
+    {
+        self.parts
+            .push(Part::tokio_reader::<_, String, _>(name, read, None));
+    }
+
+    /// Adds a Tokio asynchronous readable part to the Form as a file.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hyper_multipart_rfc7578::client::multipart;
+    ///
+    /// let mut form = multipart::Form::default();
+    ///
+    /// form.add_tokio_reader_file("input", &b"Hello World!"[..], "filename.txt");
+    /// ```
+    #[cfg(feature = "tokio")]
+    pub fn add_tokio_reader_file<F, G, R>(&mut self, name: F, read: R, filename: G)
+    where
+        F: Display,
+        G: Into<String>,
+        R: 'static + tokio::io::AsyncRead + Send,
+    {
+        self.parts
+            .push(Part::tokio_reader(name, read, Some(filename.into())));
+    }
+
+    /// Adds a file, read with io_uring on the tokio-uring runtime instead
+    /// of blocking reads, and attempts to derive its mime type. Once the
+    /// part starts streaming, the file is opened and read by a task spawned
+    /// on the runtime, which reads one chunk ahead of the body.
+    ///
+    /// Errors opening or reading the file are returned by the body.
+    ///
+    /// # Panics
+    ///
+    /// The body panics if it isn't polled on the tokio-uring runtime.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use hyper_multipart_rfc7578::client::multipart;
+    ///
+    /// tokio_uring::start(async {
+    ///     let mut form = multipart::Form::default();
+    ///
+    ///     form.add_uring_file("file", "Cargo.toml");
+    /// });
+    /// ```
+    #[cfg(all(feature = "tokio-uring", target_os = "linux"))]
+    pub fn add_uring_file<F, P>(&mut self, name: F, path: P)
+    where
+        F: Display,
+        P: AsRef<Path>,
+    {
+        let path = path.as_ref();
+        let read = UringFile {
+            path: path.to_path_buf(),
+            read: None,
+        };
+
+        let part = Part::new::<_, String>(
+            Inner::AsyncRead(Box::pin(read)),
+            name,
+            mime_from_path(path),
+            None,
+        )
+        .filename_os(path);
+        let part = self.filename_normalization.apply_to(part);
+
+        self.parts_mut().push(part);
+    }
+
+    /// Adds a file with the specified mime type to the form.
+    /// If the mime type isn't specified, a mime type will try to
+    /// be derived.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hyper_multipart_rfc7578::client::multipart;
+    ///
+    /// # fn main() {
+    /// let mut form = multipart::Form::default();
+    ///
+    /// form.add_file_with_mime("data", "test.csv", mime::TEXT_CSV);
+    /// # }
+    /// ```
+    #[cfg(feature = "fs")]
+    #[inline]
+    pub fn add_file_with_mime<P, F>(&mut self, name: F, path: P, mime: Mime) -> io::Result<()>
+    where
+        P: AsRef<Path>,
+        F: Display,
+    {
+        self._add_file(name, path, None, Some(mime), None)
+    }
+
+    /// Adds a file, sent with a filename other than its path. This is
+    /// useful to upload a temporary file under its original name.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hyper_multipart_rfc7578::client::multipart;
+    ///
+    /// let mut form = multipart::Form::default();
+    ///
+    /// form.add_file_with_filename("file", file!(), "report.rs")
+    ///     .expect("file to exist");
+    ///
+    /// assert_eq!(form.parts()[0].filename(), Some("report.rs"));
+    /// ```
+    #[cfg(feature = "fs")]
+    #[inline]
+    pub fn add_file_with_filename<P, F, G>(
+        &mut self,
+        name: F,
+        path: P,
+        filename: G,
+    ) -> io::Result<()>
+    where
+        P: AsRef<Path>,
+        F: Display,
+        G: Into<String>,
+    {
+        self._add_file(name, path, Some(filename.into()), None, None)
+    }
+
+    /// Adds a file with the specified mime type, sent with a filename other
+    /// than its path.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hyper_multipart_rfc7578::client::multipart;
+    ///
+    /// let mut form = multipart::Form::default();
+    ///
+    /// form.add_file_with_filename_and_mime("data", file!(), "data.csv", mime::TEXT_CSV)
+    ///     .expect("file to exist");
+    /// ```
+    #[cfg(feature = "fs")]
+    #[inline]
+    pub fn add_file_with_filename_and_mime<P, F, G>(
+        &mut self,
+        name: F,
+        path: P,
+        filename: G,
+        mime: Mime,
+    ) -> io::Result<()>
+    where
+        P: AsRef<Path>,
+        F: Display,
         G: Into<String>,
+    {
+        self._add_file(name, path, Some(filename.into()), Some(mime), None)
+    }
+
+    /// Adds multiple files for one field as a single part, wrapped in a
+    /// nested multipart/mixed body with its own boundary.
+    ///
+    /// This is how [RFC 2388](https://tools.ietf.org/html/rfc2388#section-4.2)
+    /// sends multiple files for one field. It is deprecated by
+    /// [RFC 7578](https://tools.ietf.org/html/rfc7578#section-4.3), but some
+    /// older servers still expect it.
+    ///
+    /// Each file is sent as an attachment, with the mime type derived from
+    /// its path. Filenames are written with the name encoding, filename
+    /// normalization and serializer of the form, like the headers of other
+    /// parts. The files are only opened when the part starts streaming, and
+    /// a file that can't be read fails the body with an error naming its
+    /// path.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hyper_multipart_rfc7578::client::multipart;
+    ///
+    /// let mut form = multipart::Form::default();
+    ///
+    /// form.add_files_mixed("files", &[file!(), "Cargo.toml"])
+    ///     .expect("filenames to be valid");
+    /// ```
+    #[cfg(feature = "fs")]
+    pub fn add_files_mixed<F, I, P>(&mut self, name: F, files: I) -> io::Result<()>
+    where
+        F: Display,
+        I: IntoIterator<Item = P>,
+        P: AsRef<Path>,
+    {
+        let serializer = &*self.serializer.0;
+        let boundary = <RandomBoundary>::generate_boundary();
+        let mut readers: Vec<Box<dyn Read + Send>> = vec![];
+
+        for (i, path) in files.into_iter().enumerate() {
+            let path = path.as_ref();
+            let part = self.filename_normalization.apply_to(
+                Part::new::<_, String>(
+                    Inner::File {
+                        path: path.to_path_buf(),
+                        file: None,
+                        range: None,
+                    },
+                    &name,
+                    mime_from_path(path),
+                    None,
+                )
+                .filename_os(path),
+            );
+
+            if part.raw_filename.is_some() && self.non_utf8_filename == NonUtf8Filename::Reject {
+                return Err(path_error(
+                    path,
+                    io::Error::new(io::ErrorKind::InvalidInput, "filename is not valid UTF-8"),
+                ));
+            }
+
+            let options = HeaderOptions {
+                index: i,
+                subtype: Subtype::Mixed,
+                name_encoding: self.name_encoding,
+                non_utf8_filename: self.non_utf8_filename,
+                content_length: false,
+            };
+            let mut head = vec![];
+
+            serializer.write_boundary(&mut head, &boundary, i > 0)?;
+            serializer.write_headers(&mut head, &part, &options)?;
+
+            readers.push(Box::new(Cursor::new(head)));
+            readers.push(Box::new(LazyFile {
+                path: path.to_path_buf(),
+                file: None,
+            }));
+        }
+
+        let mut tail = vec![];
+
+        serializer.write_final_boundary(&mut tail, &boundary)?;
+        readers.push(Box::new(Cursor::new(tail)));
+
+        let mime = Mime::from_str(&format!("multipart/mixed; boundary={}", boundary))
+            .expect("boundary to be a valid parameter");
+
+        self.parts_mut().push(Part::new::<_, String>(
+            Inner::Read(Box::new(ConcatReader(readers.into()))),
+            name,
+            Some(mime),
+            None,
+        ));
+
+        Ok(())
+    }
+
+    /// Internal method for adding a file part to the form.
+    #[cfg(feature = "fs")]
+    fn _add_file<P, F>(
+        &mut self,
+        name: F,
+        path: P,
+        filename: Option<String>,
+        mime: Option<Mime>,
+        range: Option<(u64, u64)>,
+    ) -> io::Result<()>
+    where
+        P: AsRef<Path>,
+        F: Display,
+    {
+        let f = File::open(&path)?;
+        // An explicit mime type takes precedence over the one derived from
+        // the path.
+        //
+        let mime = mime.or_else(|| mime_from_path(path.as_ref()));
+        match f.metadata() {
+            // If the path is not a file, it can't be uploaded because there
+            // is no content.
+            //
+            Ok(meta) if !meta.is_file() => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "expected a file not directory",
+            )),
+
+            // If there is some metadata on the file, try to derive some
+            // header values.
+            //
+            Ok(_) => Ok(()),
+
+            // The file metadata could not be accessed. This MIGHT not be an
+            // error, if the file could be opened.
+            //
+            Err(e) => Err(e),
+        }?;
+
+        let part = Part::new(
+            Inner::File {
+                path: path.as_ref().to_path_buf(),
+                file: Some(f),
+                range,
+            },
+            name,
+            mime,
+            filename.as_ref(),
+        );
+
+        let part = match filename {
+            Some(_) => part,
+            None => self
+                .filename_normalization
+                .apply_to(part.filename_os(path.as_ref())),
+        };
+
+        self.parts_mut().push(part);
+
+        Ok(())
+    }
+}
+
+/// Configuration for the body created from a form.
+///
+/// The configuration of a form is accessed with
+/// [`Form::body_config`](/hyper_multipart_rfc7578/client/multipart/struct.Form.html#method.body_config).
+#[derive(Clone, Debug)]
+pub struct BodyConfig {
+    chunk_size: usize,
+
+    /// Runs reads of blocking readers on a blocking pool, if set.
+    spawner: Option<Spawner>,
+
+    max_part_size: Option<u64>,
+
+    max_body_size: Option<u64>,
+
+    /// The number of frames written before yielding to other tasks.
+    yield_after: usize,
+
+    /// The trailers sent after the final boundary.
+    #[cfg(feature = "http")]
+    trailers: Vec<Trailer>,
+
+    /// Called once a body was written in full.
+    on_complete: Vec<Hook>,
+
+    /// Called when a body is dropped before it was written in full.
+    on_abort: Vec<Hook>,
+}
+
+/// A function called when a body is complete, or aborted.
+#[derive(Clone)]
+struct Hook(Arc<dyn Fn() + Send + Sync>);
+
+impl fmt::Debug for Hook {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("Hook")
+    }
+}
+
+/// A trailer field, with a function computing its value once the body was
+/// written.
+#[cfg(feature = "http")]
+#[derive(Clone)]
+struct Trailer {
+    name: HeaderName,
+
+    value: Arc<dyn Fn() -> HeaderValue + Send + Sync>,
+}
+
+#[cfg(feature = "http")]
+impl fmt::Debug for Trailer {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Trailer").field("name", &self.name).finish()
+    }
+}
+
+impl Default for BodyConfig {
+    #[inline]
+    fn default() -> BodyConfig {
+        BodyConfig {
+            chunk_size: 2048,
+            spawner: None,
+            max_part_size: None,
+            max_body_size: None,
+            yield_after: 32,
+            #[cfg(feature = "http")]
+            trailers: vec![],
+            on_complete: vec![],
+            on_abort: vec![],
+        }
+    }
+}
+
+impl BodyConfig {
+    /// Sets the maximum amount of part content written with each frame.
+    /// This defaults to 2 KiB. Larger chunks mean fewer frames for large
+    /// uploads, at the cost of more memory per frame.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `size` is zero.
+    pub fn chunk_size(&mut self, size: usize) -> &mut BodyConfig {
+        assert!(size > 0, "chunk size must be greater than zero");
+
+        self.chunk_size = size;
+        self
+    }
+
+    /// Sets the maximum size of the content of each part, as it is sent.
+    /// Once a part exceeds it, the body returns `Error::PartTooLarge`, so a
+    /// reader that never ends can't produce an unbounded upload.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hyper_multipart_rfc7578::client::multipart;
+    ///
+    /// let mut form = multipart::Form::default();
+    ///
+    /// form.body_config().max_part_size(10 * 1024 * 1024);
+    /// ```
+    pub fn max_part_size(&mut self, size: u64) -> &mut BodyConfig {
+        self.max_part_size = Some(size);
+        self
+    }
+
+    /// Sets the maximum size of the whole body, including boundaries and
+    /// headers. Once the body exceeds it, the body returns
+    /// `Error::BodyTooLarge`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hyper_multipart_rfc7578::client::multipart;
+    ///
+    /// let mut form = multipart::Form::default();
+    ///
+    /// form.body_config().max_body_size(100 * 1024 * 1024);
+    /// ```
+    pub fn max_body_size(&mut self, size: u64) -> &mut BodyConfig {
+        self.max_body_size = Some(size);
+        self
+    }
+
+    /// Sets the number of frames written in a row before the body yields to
+    /// other tasks, even if more data is ready. This defaults to 32, so a
+    /// large upload doesn't starve other requests, or other HTTP/2 streams
+    /// on the same connection. Use `usize::MAX` to never yield.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `frames` is zero.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hyper_multipart_rfc7578::client::multipart;
+    ///
+    /// let mut form = multipart::Form::default();
+    ///
+    /// form.body_config().yield_after(128);
+    /// ```
+    pub fn yield_after(&mut self, frames: usize) -> &mut BodyConfig {
+        assert!(
+            frames > 0,
+            "frames before yielding must be greater than zero"
+        );
+
+        self.yield_after = frames;
+        self
+    }
+
+    /// Runs reads of readers and files on Tokio's blocking thread pool, so
+    /// slow readers don't stall the task writing the body. This requires the
+    /// Tokio runtime.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hyper_multipart_rfc7578::client::multipart;
+    ///
+    /// let mut form = multipart::Form::default();
+    ///
+    /// form.body_config().spawn_blocking();
+    /// ```
+    #[cfg(feature = "tokio")]
+    pub fn spawn_blocking(&mut self) -> &mut BodyConfig {
+        self.spawner = Some(Spawner::tokio());
+        self
+    }
+
+    /// Runs reads of readers and files with a function that runs a task on
+    /// a blocking thread pool, so slow readers don't stall the task writing
+    /// the body.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hyper_multipart_rfc7578::client::multipart;
+    /// use std::thread;
+    ///
+    /// let mut form = multipart::Form::default();
+    ///
+    /// form.body_config().blocking_spawner(|task| {
+    ///     thread::spawn(task);
+    /// });
+    /// ```
+    pub fn blocking_spawner<F>(&mut self, spawn: F) -> &mut BodyConfig
+    where
+        F: 'static + Fn(Box<dyn FnOnce() + Send>) + Send + Sync,
+    {
+        self.spawner = Some(Spawner::new(spawn));
+        self
+    }
+
+    /// Adds a trailer field, sent as a trailers frame after the final
+    /// boundary. The value is computed once the whole body was written, so
+    /// it can depend on the content, such as a checksum computed while
+    /// streaming.
+    ///
+    /// `Form::set_body` declares the trailer fields in the Trailer header.
+    /// Trailers are only sent with chunked HTTP/1.1 requests and HTTP/2,
+    /// and only with a `Body` used as a stream of http-body frames.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use http::header::{HeaderName, HeaderValue};
+    /// use hyper::Request;
+    /// use hyper_multipart_rfc7578::client::multipart;
+    ///
+    /// let mut form = multipart::Form::default();
+    ///
+    /// form.add_text("text", "Hello World!");
+    /// form.body_config().trailer(HeaderName::from_static("x-upload-complete"), || {
+    ///     HeaderValue::from_static("true")
+    /// });
+    ///
+    /// let req = form.set_body(Request::post("http://localhost/upload")).unwrap();
+    ///
+    /// assert_eq!(req.headers()["trailer"], "x-upload-complete");
+    /// ```
+    #[cfg(feature = "http")]
+    pub fn trailer<F>(&mut self, name: HeaderName, value: F) -> &mut BodyConfig
+    where
+        F: 'static + Fn() -> HeaderValue + Send + Sync,
+    {
+        self.trailers.push(Trailer {
+            name,
+            value: Arc::new(value),
+        });
+        self
+    }
+
+    /// Adds a function called once a body created from the form was
+    /// written in full, including the final boundary. It is called once for
+    /// every body, so a form turned into a factory calls it for every body
+    /// that is completed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hyper_multipart_rfc7578::client::multipart;
+    ///
+    /// let mut form = multipart::Form::default();
+    ///
+    /// form.body_config().on_complete(|| println!("upload complete"));
+    /// ```
+    pub fn on_complete<F>(&mut self, hook: F) -> &mut BodyConfig
+    where
+        F: 'static + Fn() + Send + Sync,
+    {
+        self.on_complete.push(Hook(Arc::new(hook)));
+        self
+    }
+
+    /// Adds a function called when a body created from the form is dropped
+    /// before it was written in full, for example when a request is
+    /// cancelled, or when the body ends with an error, such as a part that
+    /// can't be read, or a form that isn't valid. This can be used to clean
+    /// up the sources of the parts, such as temporary files. It is called
+    /// at most once per body.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hyper_multipart_rfc7578::client::multipart;
+    ///
+    /// let mut form = multipart::Form::default();
+    ///
+    /// form.body_config().on_abort(|| println!("upload aborted"));
+    /// ```
+    pub fn on_abort<F>(&mut self, hook: F) -> &mut BodyConfig
+    where
+        F: 'static + Fn() + Send + Sync,
+    {
+        self.on_abort.push(Hook(Arc::new(hook)));
+        self
+    }
+
+    /// Returns the value of the Trailer header declaring the trailer
+    /// fields, if there are any.
+    #[cfg(feature = "http")]
+    fn trailer_header(&self) -> Option<String> {
+        if self.trailers.is_empty() {
+            return None;
+        }
+
+        let names: Vec<_> = self
+            .trailers
+            .iter()
+            .map(|trailer| trailer.name.as_str())
+            .collect();
+
+        Some(names.join(", "))
+    }
+
+    /// Declares the trailer fields in the Trailer header of a request, if
+    /// there are any.
+    #[cfg(feature = "http")]
+    fn set_trailer_header(&self, req: Builder) -> Builder {
+        match self.trailer_header() {
+            Some(names) => req.header(TRAILER, names),
+            None => req,
+        }
+    }
+}
+
+/// Creates a body with a fixed boundary that parts are added to while it is
+/// written. See [`Form::into_channel`].
+///
+/// # Examples
+///
+/// ```
+/// use hyper_multipart_rfc7578::client::multipart;
+///
+/// let (sender, body) = multipart::channel("boundary");
+///
+/// assert_eq!(sender.content_type(), "multipart/form-data; boundary=\"boundary\"");
+/// ```
+pub fn channel<S>(boundary: S) -> (Sender, Body)
+where
+    S: Into<String>,
+{
+    Form::with_boundary(boundary).into_channel()
+}
+
+/// Adds parts to a body while it is written.
+///
+/// A sender is created with
+/// [`Form::into_channel`](/hyper_multipart_rfc7578/client/multipart/struct.Form.html#method.into_channel).
+/// Adding a part waits until the body is ready for it, so parts are only
+/// produced as fast as the body is sent.
+pub struct Sender {
+    /// The value of the Content-Type header of the body.
+    content_type: String,
+
+    boundary: String,
+
+    subtype: Subtype,
+
+    part_content_length: bool,
+
+    name_encoding: NameEncoding,
+
+    non_utf8_filename: NonUtf8Filename,
+
+    /// Whether the boundary of the next part is preceded by a CRLF.
+    crlf: bool,
+
+    /// The index of the next part in the body.
+    index: usize,
+
+    serializer: Serializer,
+
+    sender: mpsc::Sender<(Bytes, Part)>,
+}
+
+impl Sender {
+    /// Returns the value of the multipart Content-Type header of the body.
+    #[inline]
+    pub fn content_type(&self) -> &str {
+        &self.content_type
+    }
+
+    /// Adds a part to the body, once the body is ready for it.
+    ///
+    /// The boundary can't change once the body is created, so this fails
+    /// with `Error::BoundaryCollision` if the content of the part contains
+    /// it. This fails with `Error::BodyDropped` if the body was dropped.
+    pub async fn add_part(&mut self, part: Part) -> Result<(), Error> {
+        part.check_names(self.index, self.name_encoding, self.non_utf8_filename)?;
+
+        if part.contains_boundary(&self.boundary) {
+            return Err(Error::BoundaryCollision {
+                index: self.index,
+                name: part.name,
+            });
+        }
+
+        let serializer = &*self.serializer.0;
+        let options = HeaderOptions {
+            index: self.index,
+            subtype: self.subtype,
+            name_encoding: self.name_encoding,
+            non_utf8_filename: self.non_utf8_filename,
+            content_length: self.part_content_length,
+        };
+        let mut head = vec![];
+
+        // Writing to a `Vec` can't fail.
+        //
+        serializer
+            .write_boundary(&mut head, &self.boundary, self.crlf)
+            .and_then(|_| serializer.write_headers(&mut head, &part, &options))
+            .expect("writing to a buffer");
+
+        self.sender
+            .send((Bytes::from(head), part))
+            .await
+            .map_err(|_| Error::BodyDropped)?;
+        self.crlf = true;
+        self.index += 1;
+
+        Ok(())
+    }
+
+    /// Adds a text part to the body, once the body is ready for it.
+    pub async fn add_text<N, T>(&mut self, name: N, text: T) -> Result<(), Error>
+    where
+        N: Display,
+        T: Into<String>,
+    {
+        self.add_part(Part::text(name, text)).await
+    }
+
+    /// Adds an asynchronous readable part to the body, once the body is
+    /// ready for it. The part is written until the reader is exhausted.
+    pub async fn add_async_reader<N, R>(&mut self, name: N, read: R) -> Result<(), Error>
+    where
+        N: Display,
+        R: 'static + AsyncRead + Send,
+    {
+        self.add_part(Part::async_reader::<_, String, _>(name, read, None))
+            .await
+    }
+
+    /// Adds a part with the content of a stream of chunks to the body, once
+    /// the body is ready for it. The part is written until the stream ends.
+    pub async fn add_stream<N, S, B>(&mut self, name: N, stream: S) -> Result<(), Error>
+    where
+        N: Display,
+        S: 'static + Stream<Item = io::Result<B>> + Send + Unpin,
+        B: 'static + AsRef<[u8]> + Send,
+    {
+        self.add_async_reader(name, stream.into_async_read()).await
+    }
+
+    /// Closes the sender, so the final boundary is written after the parts
+    /// added so far. This is the same as dropping the sender.
+    #[inline]
+    pub fn close(self) {}
+}
+
+/// Creates multipart bodies from the same form, for example to resend the
+/// body if a request fails.
+///
+/// A factory is created with
+/// [`Form::into_factory`](/hyper_multipart_rfc7578/client/multipart/struct.Form.html#method.into_factory).
+pub struct BodyFactory {
+    form: Form,
+}
+
+impl BodyFactory {
+    /// Creates a new body, rewinding seekable readers and reopening files.
+    #[inline]
+    pub fn body(&self) -> Body {
+        Body::from(self.form.try_clone().expect("parts to be replayable"))
+    }
+
+    /// Updates a request instance with the multipart Content-Type header
+    /// and a new body.
+    #[cfg(feature = "http")]
+    pub fn set_body(&self, req: Builder) -> Result<Request<StreamBody<Body>>, http::Error> {
+        let header = self.form.content_type();
+
+        let header: &str = header.as_ref();
+        let req = self
+            .form
+            .set_content_md5_header(req.header(CONTENT_TYPE, header));
+
+        self.form
+            .body_config
+            .set_trailer_header(req)
+            .body(StreamBody::new(self.body()))
+    }
+}
+
+/// A body that was written to a file or other storage up front, so its
+/// length is known, and it can be sent more than once.
+///
+/// A spooled body is created with
+/// [`Form::spool`](/hyper_multipart_rfc7578/client/multipart/struct.Form.html#method.spool)
+/// or
+/// [`Form::spool_to`](/hyper_multipart_rfc7578/client/multipart/struct.Form.html#method.spool_to).
+pub struct SpooledBody {
+    /// The value of the Content-Type header of the body.
+    content_type: String,
+
+    len: u64,
+
+    storage: SharedReader,
+
+    body_config: BodyConfig,
+
+    /// The base64 encoded MD5 digest of the body, computed while it was
+    /// written.
+    #[cfg(feature = "checksum")]
+    content_md5: String,
+}
+
+impl SpooledBody {
+    /// Returns the value of the multipart Content-Type header of the body.
+    #[inline]
+    pub fn content_type(&self) -> &str {
+        &self.content_type
+    }
+
+    /// Returns the length of the body, in bytes.
+    #[inline]
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    /// Returns whether the body is empty.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the MD5 digest of the body, base64 encoded, as the value of
+    /// a Content-MD5 header.
+    #[cfg(feature = "checksum")]
+    #[inline]
+    pub fn content_md5(&self) -> &str {
+        &self.content_md5
+    }
+
+    /// Creates a new body, read from the start of the storage. The bodies
+    /// created from the same spooled body share the storage, so only one of
+    /// them can be sent at a time.
+    pub fn body(&self) -> Body {
+        let part = Part::new::<_, String>(Inner::Seek(self.storage.clone()), "", None, None);
+
+        Body::raw(part, self.body_config.clone())
+    }
+
+    /// Updates a request instance with the multipart Content-Type header,
+    /// the Content-Length header, and a new body. With the `checksum`
+    /// feature, the Content-MD5 header is set too.
+    #[cfg(feature = "http")]
+    pub fn set_body(&self, req: Builder) -> Result<Request<StreamBody<Body>>, http::Error> {
+        let req = req
+            .header(CONTENT_TYPE, &self.content_type)
+            .header(CONTENT_LENGTH, self.len);
+
+        #[cfg(feature = "checksum")]
+        let req = req.header(
+            HeaderName::from_static("content-md5"),
+            self.content_md5.as_str(),
+        );
+
+        self.body_config
+            .set_trailer_header(req)
+            .body(StreamBody::new(self.body()))
+    }
+}
+
+impl fmt::Debug for SpooledBody {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("SpooledBody")
+            .field("content_type", &self.content_type)
+            .field("len", &self.len)
+            .finish()
+    }
+}
+
+impl Body {
+    /// Creates a body with the content of a single part, without a
+    /// boundary or headers.
+    fn raw(part: Part, body_config: BodyConfig) -> Body {
+        let mut form = Form::from_boundary(String::from("raw"));
+
+        form.body_config = body_config;
+
+        let mut body = Body::from(form);
+
+        body.remaining = part.content_length();
+        body.parts = vec![(Bytes::new(), part)].into_iter().peekable();
+        body.final_boundary = Bytes::new();
+
+        // The content is a whole body, which was limited when it was
+        // written.
+        //
+        body.max_part_size = None;
+        body
+    }
+}
+
+impl From<Form> for Body {
+    /// Turns a `Form` into a multipart `Body`. The boundary and headers of
+    /// every part are rendered up front.
+    fn from(mut form: Form) -> Self {
+        // The Content-Type header may already be sent, so the boundary
+        // can't be changed anymore, and a collision fails the body instead.
+        //
+        let error = form.check().err();
+
+        if error.is_some() {
+            form.parts.clear();
+            #[cfg(feature = "http")]
+            form.body_config.trailers.clear();
+        }
+
+        let (heads, final_boundary) = render_heads(&form);
+        let remaining = form
+            .parts
+            .iter()
+            .map(Part::content_length)
+            .sum::<Option<u64>>()
+            .filter(|_| error.is_none())
+            .map(|content| {
+                let heads: usize = heads.iter().map(Bytes::len).sum();
+
+                content + heads as u64 + final_boundary.len() as u64
+            });
+
+        Body {
+            buf_size: form.body_config.chunk_size,
+            part_buf_size: form.body_config.chunk_size,
+            scratch: vec![],
+            chunk: BytesMut::new(),
+            spawner: form.body_config.spawner.clone(),
+            #[cfg(feature = "http")]
+            trailers: form.body_config.trailers.clone(),
+            max_part_size: form.body_config.max_part_size,
+            max_body_size: form.body_config.max_body_size,
+            part_len: 0,
+            body_len: 0,
+            yield_after: form.body_config.yield_after,
+            budget: form.body_config.yield_after,
+            #[cfg(feature = "checksum")]
+            hasher: None,
+            error,
+            current: None,
+            parts: heads
+                .into_iter()
+                .zip(form.parts)
+                .collect::<Vec<_>>()
+                .into_iter()
+                .peekable(),
+            receiver: None,
+            final_boundary,
+            #[cfg(feature = "tracing")]
+            span: tracing::debug_span!("multipart_body", boundary = %form.boundary),
+            part_name: None,
+            part_index: 0,
+            #[cfg(feature = "throttle")]
+            rate_limit: None,
+            #[cfg(feature = "deadline")]
+            deadline: None,
+            on_complete: form.body_config.on_complete.clone(),
+            on_abort: form.body_config.on_abort.clone(),
+            failed: false,
+            remaining,
+        }
+    }
+}
+
+impl Drop for Body {
+    fn drop(&mut self) {
+        for hook in mem::take(&mut self.on_abort) {
+            (hook.0)();
+        }
+    }
+}
+
+#[cfg(feature = "reqwest")]
+impl From<Body> for reqwest::Body {
+    /// Turns a multipart `Body` into a streaming reqwest body. Use
+    /// [`Form::set_reqwest_body`] to also set the Content-Type header.
+    fn from(body: Body) -> Self {
+        reqwest::Body::wrap_stream(body.into_data_stream())
+    }
+}
+
+#[cfg(feature = "actix")]
+impl actix_http::body::MessageBody for Body {
+    type Error = Error;
+
+    /// The length of a body is known if the length of every part is known
+    /// up front (see [`Form::content_length`]). Otherwise, it is sent as a
+    /// stream.
+    #[inline]
+    fn size(&self) -> actix_http::body::BodySize {
+        match self.remaining {
+            Some(remaining) => actix_http::body::BodySize::Sized(remaining),
+            None => actix_http::body::BodySize::Stream,
+        }
+    }
+
+    /// Writes the body for actix-web and awc. With awc, the Content-Type
+    /// header is set with
+    /// [`Form::content_type`](/hyper_multipart_rfc7578/client/multipart/struct.Form.html#method.content_type)
+    /// before the body is sent with `ClientRequest::send_body`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use actix_http::{body::BoxBody, Response, StatusCode};
+    /// use hyper_multipart_rfc7578::client::multipart;
+    ///
+    /// let mut form = multipart::Form::default();
+    ///
+    /// form.add_text("text", "Hello World!");
+    ///
+    /// let content_type = form.content_type();
+    /// let res = Response::build(StatusCode::OK)
+    ///     .content_type(content_type)
+    ///     .body(BoxBody::new(multipart::Body::from(form)));
+    /// ```
+    #[inline]
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Result<Bytes, Error>>> {
+        self.poll_data(cx)
+    }
+}
+
+/// One part of a body delimited by a boundary line.
+///
+/// [See RFC2046 5.1](https://tools.ietf.org/html/rfc2046#section-5.1).
+#[derive(Debug)]
+pub struct Part {
+    inner: Inner,
+
+    /// The name of the form field the part belongs to. It is sent as the
+    /// `name` parameter of the Content-Disposition header field, which each
+    /// part must contain.
+    ///
+    /// [See](https://tools.ietf.org/html/rfc7578#section-4.2).
+    name: String,
+
+    /// The optional `filename` parameter of the Content-Disposition header
+    /// field.
+    ///
+    /// [See](https://tools.ietf.org/html/rfc7578#section-4.2).
+    filename: Option<String>,
+
+    /// The original bytes of the filename, if it isn't valid UTF-8. The
+    /// `filename` is then converted lossily.
+    raw_filename: Option<Vec<u8>>,
+
+    /// Each part can include a Content-Type header field. If this
+    /// is not specified, it defaults to "text/plain", or
+    /// "application/octet-stream" for file data.
+    ///
+    /// [See](https://tools.ietf.org/html/rfc7578#section-4.4)
+    mime: Option<Mime>,
+
+    /// An optional encoding applied to the content while it is streamed.
+    ///
+    /// [See](https://tools.ietf.org/html/rfc7578#section-4.7).
+    transfer_encoding: Option<TransferEncoding>,
+
+    /// An optional compression applied to the content while it is streamed,
+    /// before any transfer encoding.
+    #[cfg(feature = "gzip")]
+    content_encoding: Option<ContentEncoding>,
+
+    /// Overrides the chunk size of the body for this part.
+    chunk_size: Option<usize>,
+
+    /// An optional Content-ID, used to refer to the part from other parts.
+    ///
+    /// [See](https://tools.ietf.org/html/rfc2392).
+    content_id: Option<String>,
+
+    /// An optional charset parameter for the Content-Type header field.
+    ///
+    /// [See](https://tools.ietf.org/html/rfc7578#section-4.5).
+    charset: Option<String>,
+
+    /// Extra parameters of the Content-Disposition header, in order.
+    disposition_params: Vec<(String, String)>,
+
+    /// A handle to the checksum computed while the part is written, if
+    /// requested.
+    #[cfg(feature = "checksum")]
+    checksum: Option<Checksum>,
+
+    /// Transforms applied to the content while it is streamed, before any
+    /// encodings.
+    transforms: Transforms,
+}
+
+impl Part {
+    /// Internal method to build a new Part instance. Sets the content-type,
+    /// and the disposition parameters for name, and optionally for filename.
+    ///
+    /// Per [4.3](https://tools.ietf.org/html/rfc7578#section-4.3), if multiple
+    /// files need to be specified for one form field, they can all be specified
+    /// with the same name parameter.
+    fn new<N, F>(inner: Inner, name: N, mime: Option<Mime>, filename: Option<F>) -> Part
+    where
+        N: Display,
+        F: Display,
+    {
+        Part {
+            inner,
+            name: name.to_string(),
+            filename: filename.map(|filename| filename.to_string()),
+            raw_filename: None,
+            mime,
+            transfer_encoding: None,
+            #[cfg(feature = "gzip")]
+            content_encoding: None,
+            chunk_size: None,
+            content_id: None,
+            charset: None,
+            disposition_params: vec![],
+            #[cfg(feature = "checksum")]
+            checksum: None,
+            transforms: Transforms::default(),
+        }
+    }
+
+    /// Returns the name of the form field the part belongs to.
+    #[inline]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the filename of the part, if it is sent as a file.
+    #[inline]
+    pub fn filename(&self) -> Option<&str> {
+        self.filename.as_deref()
+    }
+
+    /// Returns the Content-Type of the part, falling back to the default
+    /// for its content.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hyper_multipart_rfc7578::client::multipart;
+    ///
+    /// let part = multipart::Part::text("text", "Hello World!");
+    ///
+    /// assert_eq!(part.content_type(), mime::TEXT_PLAIN);
+    /// ```
+    pub fn content_type(&self) -> Mime {
+        self.mime
+            .clone()
+            .unwrap_or_else(|| self.inner.default_content_type())
+    }
+
+    /// Returns the length of the content of the part as it will be sent,
+    /// after any encodings, if it is known up front. The length of readers,
+    /// and of compressed or transformed content, isn't known.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hyper_multipart_rfc7578::client::multipart;
+    /// use std::io::Cursor;
+    ///
+    /// let part = multipart::Part::text("text", "Hello World!");
+    ///
+    /// assert_eq!(part.content_length(), Some(12));
+    ///
+    /// let part = multipart::Part::reader("input", Cursor::new("Hello World!"), None::<String>);
+    ///
+    /// assert_eq!(part.content_length(), None);
+    /// ```
+    pub fn content_length(&self) -> Option<u64> {
+        #[cfg(feature = "gzip")]
+        if self.content_encoding.is_some() {
+            return None;
+        }
+
+        if !self.transforms.is_empty() {
+            return None;
+        }
+
+        let len = self.inner.len()?;
+
+        match self.transfer_encoding {
+            Some(encoding) => Some(encoding.encoded_len(len)),
+            None => Some(len),
+        }
+    }
+
+    /// Checks that the name and filename of the part, at `index` in its
+    /// form, can be written in a header with the given encodings.
+    fn check_names(
+        &self,
+        index: usize,
+        encoding: NameEncoding,
+        non_utf8: NonUtf8Filename,
+    ) -> Result<(), Error> {
+        // Both are written in a quoted string, so they can't contain a
+        // quote or a line break unless they are encoded.
+        //
+        const SPECIAL: [char; 3] = ['"', '\r', '\n'];
+
+        if non_utf8 == NonUtf8Filename::Reject && self.raw_filename.is_some() {
+            return Err(Error::InvalidFilename {
+                index,
+                name: self.name.clone(),
+                filename: self.filename.clone().unwrap_or_default(),
+            });
+        }
+
+        if encoding != NameEncoding::Rfc7578Strict {
+            return Ok(());
+        }
+
+        if self.name.contains(SPECIAL) {
+            return Err(Error::InvalidName {
+                index,
+                name: self.name.clone(),
+            });
+        }
+
+        match self.filename {
+            Some(ref filename) if filename.contains(SPECIAL) => Err(Error::InvalidFilename {
+                index,
+                name: self.name.clone(),
+                filename: filename.clone(),
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    /// Returns whether the content of the part is in memory, and contains
+    /// the delimiter of a boundary. Encoded content isn't checked.
+    fn contains_boundary(&self, boundary: &str) -> bool {
+        #[allow(unused_mut)]
+        let mut encoded = self.transfer_encoding.is_some() || !self.transforms.is_empty();
+
+        #[cfg(feature = "gzip")]
+        {
+            encoded |= self.content_encoding.is_some();
+        }
+
+        match self.inner {
+            Inner::Text(ref bytes) | Inner::Bytes(ref bytes) if !encoded => {
+                let delimiter = format!("--{}", boundary);
+
+                bytes
+                    .windows(delimiter.len())
+                    .any(|window| window == delimiter.as_bytes())
+            }
+            _ => false,
+        }
+    }
+
+    /// Turns the part into the content to write, applying any transforms
+    /// and encodings.
+    fn into_content(self) -> io::Result<Content> {
+        #[allow(unused_mut)]
+        let mut encoded = self.transfer_encoding.is_some();
+
+        #[cfg(feature = "gzip")]
+        {
+            encoded |= self.content_encoding.is_some();
+        }
+
+        let transformed = !self.transforms.is_empty();
+        let read: Box<dyn Read + Send> = match self.inner {
+            Inner::AsyncRead(_) if encoded => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "encodings are not supported for async readers",
+                ))
+            }
+            Inner::AsyncRead(read) if transformed => {
+                return Ok(Content::AsyncRead(Box::pin(Transformed::new(
+                    read,
+                    self.transforms,
+                ))))
+            }
+            Inner::AsyncRead(read) => return Ok(Content::AsyncRead(read)),
+            Inner::Read(read) => read,
+            Inner::Lazy(open) => open()?,
+            Inner::Sized(read, len) => Box::new(SizedReader {
+                read,
+                remaining: len,
+            }),
+            Inner::Text(text) if !encoded && !transformed => return Ok(Content::Bytes(text)),
+            Inner::Text(text) => Box::new(text.reader()),
+            Inner::Bytes(bytes) if !encoded && !transformed => return Ok(Content::Bytes(bytes)),
+            Inner::Bytes(bytes) => Box::new(bytes.reader()),
+            #[cfg(feature = "fs")]
+            Inner::File { path, file, range } => {
+                let mut file = match file {
+                    Some(file) => file,
+                    None => File::open(path)?,
+                };
+
+                match range {
+                    Some((offset, len)) => {
+                        file.seek(SeekFrom::Start(offset))?;
+
+                        Box::new(file.take(len))
+                    }
+                    None => Box::new(file),
+                }
+            }
+            Inner::Seek(read) => {
+                read.rewind()?;
+
+                Box::new(read)
+            }
+        };
+        let read: Box<dyn Read + Send> = if transformed {
+            Box::new(Transformed::new(read, self.transforms))
+        } else {
+            read
+        };
+        #[cfg(feature = "gzip")]
+        let read = match self.content_encoding {
+            Some(encoding) => encoding.encode(read),
+            None => read,
+        };
+        let read = match self.transfer_encoding {
+            Some(TransferEncoding::Base64) => Box::new(Base64Reader::new(read)),
+            None => read,
+        };
+
+        Ok(Content::Read(read))
+    }
+
+    /// Attempts to clone the part. This fails if the part's content is an
+    /// arbitrary reader, or if the part has transforms.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hyper_multipart_rfc7578::client::multipart;
+    /// use std::io::Cursor;
+    ///
+    /// assert!(multipart::Part::text("text", "Hello World!").try_clone().is_some());
+    /// assert!(multipart::Part::reader("input", Cursor::new("Hello World!"), None::<String>)
+    ///     .try_clone()
+    ///     .is_none());
+    /// ```
+    pub fn try_clone(&self) -> Option<Part> {
+        if !self.transforms.is_empty() {
+            return None;
+        }
+
+        Some(Part {
+            inner: self.inner.try_clone()?,
+            name: self.name.clone(),
+            filename: self.filename.clone(),
+            raw_filename: self.raw_filename.clone(),
+            mime: self.mime.clone(),
+            transfer_encoding: self.transfer_encoding,
+            #[cfg(feature = "gzip")]
+            content_encoding: self.content_encoding,
+            chunk_size: self.chunk_size,
+            content_id: self.content_id.clone(),
+            charset: self.charset.clone(),
+            disposition_params: self.disposition_params.clone(),
+            #[cfg(feature = "checksum")]
+            checksum: self.checksum.clone(),
+            transforms: Transforms::default(),
+        })
+    }
+
+    /// Creates a text part.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hyper_multipart_rfc7578::client::multipart;
+    ///
+    /// let part = multipart::Part::text("text", "Hello World!");
+    /// ```
+    pub fn text<N, T>(name: N, text: T) -> Part
+    where
+        N: Display,
+        T: Into<String>,
+    {
+        Part::new::<_, String>(Inner::Text(Bytes::from(text.into())), name, None, None)
+    }
+
+    /// Creates a part from in-memory bytes. If a filename is given, the part
+    /// is sent as a file.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hyper_multipart_rfc7578::client::multipart;
+    ///
+    /// let part = multipart::Part::bytes("input", &b"Hello World!"[..], None::<String>);
+    /// ```
+    pub fn bytes<N, F, B>(name: N, bytes: B, filename: Option<F>) -> Part
+    where
+        N: Display,
+        F: Display,
+        B: Into<Bytes>,
+    {
+        Part::new(Inner::Bytes(bytes.into()), name, None, filename)
+    }
+
+    /// Creates a readable part. If a filename is given, the part is sent as
+    /// a file.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hyper_multipart_rfc7578::client::multipart;
+    /// use std::io::Cursor;
+    ///
+    /// let part = multipart::Part::reader("input", Cursor::new("Hello World!"), Some("a.txt"));
+    /// ```
+    pub fn reader<N, F, R>(name: N, read: R, filename: Option<F>) -> Part
+    where
+        N: Display,
+        F: Display,
         R: 'static + Read + Send,
     {
-        let read = Box::new(read);
+        Part::new(Inner::Read(Box::new(read)), name, None, filename)
+    }
+
+    /// Creates an asynchronous readable part. If a filename is given, the
+    /// part is sent as a file.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hyper_multipart_rfc7578::client::multipart;
+    ///
+    /// let bytes = futures::io::Cursor::new("Hello World!");
+    /// let part = multipart::Part::async_reader("input", bytes, Some("a.txt"));
+    /// ```
+    pub fn async_reader<N, F, R>(name: N, read: R, filename: Option<F>) -> Part
+    where
+        N: Display,
+        F: Display,
+        R: 'static + AsyncRead + Send,
+    {
+        Part::new(Inner::AsyncRead(Box::pin(read)), name, None, filename)
+    }
+
+    /// Creates a Tokio asynchronous readable part. If a filename is given,
+    /// the part is sent as a file.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hyper_multipart_rfc7578::client::multipart;
+    ///
+    /// let part = multipart::Part::tokio_reader("input", &b"Hello World!"[..], Some("a.txt"));
+    /// ```
+    #[cfg(feature = "tokio")]
+    pub fn tokio_reader<N, F, R>(name: N, read: R, filename: Option<F>) -> Part
+    where
+        N: Display,
+        F: Display,
+        R: 'static + tokio::io::AsyncRead + Send,
+    {
+        Part::async_reader(name, TokioReader(Box::pin(read)), filename)
+    }
+
+    /// Sets the Content-Type of the part.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hyper_multipart_rfc7578::client::multipart;
+    ///
+    /// let part = multipart::Part::text("data", "a,b,c").mime(mime::TEXT_CSV);
+    /// ```
+    pub fn mime(mut self, mime: Mime) -> Part {
+        self.mime = Some(mime);
+        self
+    }
+
+    /// Sets the maximum amount of content written with each frame for this
+    /// part, overriding the chunk size of the body.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `size` is zero.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hyper_multipart_rfc7578::client::multipart;
+    /// use std::io::Cursor;
+    ///
+    /// let bytes = Cursor::new(vec![0; 1024 * 1024]);
+    /// let part = multipart::Part::reader("input", bytes, Some("data.bin")).chunk_size(256 * 1024);
+    /// ```
+    pub fn chunk_size(mut self, size: usize) -> Part {
+        assert!(size > 0, "chunk size must be greater than zero");
+
+        self.chunk_size = Some(size);
+        self
+    }
+
+    /// Sets the Content-ID of the part, without the surrounding angle
+    /// brackets. Parts of a multipart/related body refer to each other by
+    /// Content-ID.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hyper_multipart_rfc7578::client::multipart;
+    ///
+    /// let part = multipart::Part::text("metadata", "{}").content_id("root@example.com");
+    /// ```
+    pub fn content_id<S>(mut self, content_id: S) -> Part
+    where
+        S: Into<String>,
+    {
+        self.content_id = Some(content_id.into());
+        self
+    }
+
+    /// Sets the charset parameter of the Content-Type of the part, replacing
+    /// any charset set through the mime type. Text parts are otherwise
+    /// assumed to use the charset of the `_charset_` field, or UTF-8.
+    ///
+    /// [See](https://tools.ietf.org/html/rfc7578#section-4.5).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hyper_multipart_rfc7578::client::multipart;
+    ///
+    /// let part = multipart::Part::text("text", "Hello World!").charset("us-ascii");
+    /// ```
+    pub fn charset<S>(mut self, charset: S) -> Part
+    where
+        S: Into<String>,
+    {
+        self.charset = Some(charset.into());
+        self
+    }
+
+    /// Sets the filename of the part from an OS string, such as the name of
+    /// a file on disk. A filename that isn't valid UTF-8 is written as set
+    /// with [`Form::non_utf8_filenames`], instead of being silently
+    /// converted.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hyper_multipart_rfc7578::client::multipart;
+    /// use std::path::Path;
+    ///
+    /// let path = Path::new("/tmp/data.csv");
+    /// let part = multipart::Part::bytes::<_, String, _>("file", &b"a,b,c"[..], None)
+    ///     .filename_os(path.file_name().unwrap());
+    ///
+    /// assert_eq!(part.filename(), Some("data.csv"));
+    /// ```
+    pub fn filename_os<S>(mut self, filename: S) -> Part
+    where
+        S: AsRef<OsStr>,
+    {
+        let filename = filename.as_ref();
+
+        self.filename = Some(filename.to_string_lossy().into_owned());
+        self.raw_filename = non_utf8_bytes(filename);
+        self
+    }
+
+    /// Adds a parameter to the Content-Disposition header of the part, such
+    /// as the `creation-date`, `modification-date`, `read-date`, or `size`
+    /// parameters of
+    /// [RFC 2183](https://tools.ietf.org/html/rfc2183#section-2). The value
+    /// is quoted if needed, so dates are written as quoted strings.
+    ///
+    /// The parameters are only written with a Content-Disposition header,
+    /// which parts of a multipart/mixed body only have with a filename.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the name isn't a token, or if the value contains a line
+    /// break.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hyper_multipart_rfc7578::client::multipart;
+    ///
+    /// let part = multipart::Part::bytes("file", &b"a,b,c"[..], Some("data.csv"))
+    ///     .disposition_param("modification-date", "Wed, 12 Feb 1997 16:29:51 -0500")
+    ///     .disposition_param("size", "5");
+    /// ```
+    pub fn disposition_param<N, V>(mut self, name: N, value: V) -> Part
+    where
+        N: Into<String>,
+        V: Into<String>,
+    {
+        let name = name.into();
+        let value = value.into();
+
+        assert!(is_token(&name), "parameter name must be a token");
+        assert!(
+            !value.contains(['\r', '\n']),
+            "parameter value must not contain a line break"
+        );
+
+        self.disposition_params.push((name, value));
+        self
+    }
+
+    /// Sets the Content-Transfer-Encoding of the part. The content is
+    /// encoded on the fly while the body is streamed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hyper_multipart_rfc7578::client::multipart::{self, TransferEncoding};
+    /// use std::io::Cursor;
+    ///
+    /// let bytes = Cursor::new(vec![0u8, 159, 146, 150]);
+    /// let part = multipart::Part::reader("input", bytes, Some("data.bin"))
+    ///     .transfer_encoding(TransferEncoding::Base64);
+    /// ```
+    pub fn transfer_encoding(mut self, encoding: TransferEncoding) -> Part {
+        self.transfer_encoding = Some(encoding);
+        self
+    }
+
+    /// Sets the Content-Encoding of the part. The content is compressed on
+    /// the fly while the body is streamed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hyper_multipart_rfc7578::client::multipart::{self, ContentEncoding};
+    ///
+    /// let part = multipart::Part::text("data", "a,b,c")
+    ///     .mime(mime::TEXT_CSV)
+    ///     .content_encoding(ContentEncoding::Gzip);
+    /// ```
+    #[cfg(feature = "gzip")]
+    pub fn content_encoding(mut self, encoding: ContentEncoding) -> Part {
+        self.content_encoding = Some(encoding);
+        self
+    }
+
+    /// Adds a transform applied to the content of the part while the body
+    /// is streamed, after the transforms added before it, and before any
+    /// encodings. A part with transforms can't be cloned, and the length of
+    /// its content isn't known up front.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bytes::Bytes;
+    /// use hyper_multipart_rfc7578::client::multipart;
+    ///
+    /// let part = multipart::Part::text("text", "Hello World!").transform(|chunk: Bytes| {
+    ///     Ok(Bytes::from(chunk.to_ascii_uppercase()))
+    /// });
+    /// ```
+    pub fn transform<T>(mut self, transform: T) -> Part
+    where
+        T: 'static + Transform,
+    {
+        self.transforms.push(Box::new(transform));
+        self
+    }
+}
+
+enum Inner {
+    /// The `Read` variant captures multiple cases.
+    ///
+    ///   * The first is it supports uploading a file, which is explicitly
+    ///     described in RFC 7578.
+    ///
+    ///   * The second (which is not described by RFC 7578), is it can handle
+    ///     arbitrary input streams (for example, a server response).
+    ///     Any arbitrary input stream is automatically considered a file,
+    ///     and assigned the corresponding content type if not explicitly
+    ///     specified.
+    Read(Box<dyn Read + Send + 'static>),
+
+    /// The `AsyncRead` variant handles asynchronous input streams, which
+    /// are read without blocking the task writing the body.
+    AsyncRead(Pin<Box<dyn AsyncRead + Send + 'static>>),
+
+    /// The `Lazy` variant handles readers that are only created once the
+    /// part starts to be written, such as connections or spawned processes.
+    Lazy(Box<dyn FnOnce() -> io::Result<Box<dyn Read + Send>> + Send + 'static>),
+
+    /// The `Sized` variant handles readers with a length declared up front.
+    /// Exactly that many bytes are sent.
+    Sized(Box<dyn Read + Send + 'static>, u64),
+
+    /// The `Text` variant handles "text/plain" form data payloads. The text
+    /// is stored as bytes, so static strings don't need to be copied.
+    Text(Bytes),
+
+    /// The `Bytes` variant handles in-memory binary payloads, which are sent
+    /// without copying them.
+    Bytes(Bytes),
+
+    /// The `File` variant handles files added by path. The file is kept open
+    /// if it was opened when it was added, otherwise it is opened when the
+    /// part is written.
+    /// Only the given range of the file, as an offset and a length, is
+    /// sent if one is set.
+    #[cfg(feature = "fs")]
+    File {
+        path: PathBuf,
+        file: Option<File>,
+        range: Option<(u64, u64)>,
+    },
+
+    /// The `Seek` variant handles readers that can be rewound, so the part
+    /// can be sent more than once.
+    Seek(SharedReader),
+}
+
+impl fmt::Debug for Inner {
+    /// Formats the kind of content, without the content itself.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Inner::Read(_) => f.write_str("Read"),
+            Inner::Lazy(_) => f.write_str("Lazy"),
+            Inner::Sized(_, len) => f.debug_struct("Sized").field("len", &len).finish(),
+            Inner::AsyncRead(_) => f.write_str("AsyncRead"),
+            Inner::Text(ref text) => f.debug_struct("Text").field("len", &text.len()).finish(),
+            Inner::Bytes(ref bytes) => f.debug_struct("Bytes").field("len", &bytes.len()).finish(),
+            #[cfg(feature = "fs")]
+            Inner::File {
+                ref path, range, ..
+            } => f
+                .debug_struct("File")
+                .field("path", path)
+                .field("range", &range)
+                .finish(),
+            Inner::Seek(_) => f.write_str("Seek"),
+        }
+    }
+}
+
+/// A reader that reads exactly the declared length from a reader, and fails
+/// if the reader ends before it.
+struct SizedReader {
+    read: Box<dyn Read + Send>,
+
+    /// The number of bytes left to read.
+    remaining: u64,
+}
+
+impl Read for SizedReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.remaining == 0 || buf.is_empty() {
+            return Ok(0);
+        }
+
+        let max = buf
+            .len()
+            .min(usize::try_from(self.remaining).unwrap_or(usize::MAX));
+        let n = self.read.read(&mut buf[..max])?;
+
+        if n == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "reader ended before its declared length",
+            ));
+        }
+
+        self.remaining -= n as u64;
+
+        Ok(n)
+    }
+}
+
+/// A reader that reads from a sequence of readers, one after another.
+#[cfg(feature = "fs")]
+struct ConcatReader(VecDeque<Box<dyn Read + Send>>);
+
+#[cfg(feature = "fs")]
+impl Read for ConcatReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while let Some(read) = self.0.front_mut() {
+            match read.read(buf)? {
+                0 if !buf.is_empty() => {
+                    self.0.pop_front();
+                }
+                n => return Ok(n),
+            }
+        }
+
+        Ok(0)
+    }
+}
+
+/// A file that is only opened when it is first read. Errors name the path
+/// of the file.
+#[cfg(feature = "fs")]
+struct LazyFile {
+    path: PathBuf,
+    file: Option<File>,
+}
+
+#[cfg(feature = "fs")]
+impl Read for LazyFile {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let file = match self.file {
+            Some(ref mut file) => file,
+            None => self
+                .file
+                .insert(File::open(&self.path).map_err(|e| path_error(&self.path, e))?),
+        };
+
+        file.read(buf).map_err(|e| path_error(&self.path, e))
+    }
+}
 
-        self.parts.push(Part::new::<_, String>(
-            Inner::Read(read),
-            name,
-            None,
-            Some(filename.into()),
-        ));
+/// A file read with io_uring by a task that is only spawned when the file
+/// is first read.
+#[cfg(all(feature = "tokio-uring", target_os = "linux"))]
+struct UringFile {
+    path: PathBuf,
+
+    /// The chunks read by the task, once it was spawned.
+    read: Option<futures::stream::IntoAsyncRead<mpsc::Receiver<io::Result<Bytes>>>>,
+}
+
+#[cfg(all(feature = "tokio-uring", target_os = "linux"))]
+impl UringFile {
+    /// Spawns the task opening and reading the file in chunks of
+    /// `chunk_size` bytes, and returns the receiver of its chunks.
+    fn spawn(&self, chunk_size: usize) -> mpsc::Receiver<io::Result<Bytes>> {
+        let path = self.path.clone();
+        let (mut sender, receiver) = mpsc::channel(0);
+
+        tokio_uring::spawn(async move {
+            let file = match tokio_uring::fs::File::open(&path).await {
+                Ok(file) => file,
+                Err(e) => {
+                    let _ = sender.send(Err(e)).await;
+                    return;
+                }
+            };
+            let mut buf = Vec::with_capacity(chunk_size);
+            let mut pos = 0;
+
+            loop {
+                // The buffer is handed to the kernel for each read, and
+                // returned along with the result.
+                //
+                let (res, read) = file.read_at(buf, pos).await;
+
+                buf = read;
+
+                let chunk = match res {
+                    Ok(0) => break,
+                    Ok(len) => {
+                        pos += len as u64;
+
+                        Ok(Bytes::copy_from_slice(&buf[..len]))
+                    }
+                    Err(e) => Err(e),
+                };
+                let failed = chunk.is_err();
+
+                // The body was dropped, or the read failed.
+                //
+                if sender.send(chunk).await.is_err() || failed {
+                    break;
+                }
+
+                buf.clear();
+            }
+
+            let _ = file.close().await;
+        });
+
+        receiver
     }
+}
 
-    /// Adds a readable part to the Form as a file with a specified mime.
-    ///
-    /// # Examples
-    ///
-    /// ```
-    /// use hyper_multipart_rfc7578::client::multipart;
-    /// use std::io::Cursor;
-    ///
-    /// # fn main() {
-    /// let bytes = Cursor::new("Hello World!");
-    /// let mut form = multipart::Form::default();
-    ///
-    /// form.add_reader_file_with_mime("input", bytes, "filename.txt", mime::TEXT_PLAIN);
-    /// # }
-    /// ```
-    pub fn add_reader_file_with_mime<F, G, R>(&mut self, name: F, read: R, filename: G, mime: Mime)
-    where
-        F: Display,
-        G: Into<String>,
-        R: 'static + Read + Send,
-    {
-        let read = Box::new(read);
+#[cfg(all(feature = "tokio-uring", target_os = "linux"))]
+impl AsyncRead for UringFile {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        ctx: &mut Context,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = &mut *self;
+        let read = match this.read {
+            Some(ref mut read) => read,
+            // The body reads with a buffer of the chunk size of the part,
+            // which is only known once the part starts.
+            //
+            None => {
+                let receiver = this.spawn(buf.len());
 
-        self.parts.push(Part::new::<_, String>(
-            Inner::Read(read),
-            name,
-            Some(mime),
-            Some(filename.into()),
-        ));
+                this.read.insert(receiver.into_async_read())
+            }
+        };
+
+        Pin::new(read).poll_read(ctx, buf)
     }
+}
 
-    /// Adds a file with the specified mime type to the form.
-    /// If the mime type isn't specified, a mime type will try to
-    /// be derived.
-    ///
-    /// # Examples
-    ///
-    /// ```
-    /// use hyper_multipart_rfc7578::client::multipart;
-    ///
-    /// # fn main() {
-    /// let mut form = multipart::Form::default();
-    ///
-    /// form.add_file_with_mime("data", "test.csv", mime::TEXT_CSV);
-    /// # }
-    /// ```
-    #[inline]
-    pub fn add_file_with_mime<P, F>(&mut self, name: F, path: P, mime: Mime) -> io::Result<()>
-    where
-        P: AsRef<Path>,
-        F: Display,
-    {
-        self._add_file(name, path, Some(mime))
+/// Adapts a Tokio reader to the futures `AsyncRead` trait.
+#[cfg(feature = "tokio")]
+struct TokioReader(Pin<Box<dyn tokio::io::AsyncRead + Send>>);
+
+#[cfg(feature = "tokio")]
+impl AsyncRead for TokioReader {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        ctx: &mut Context,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        let mut buf = tokio::io::ReadBuf::new(buf);
+
+        futures::ready!(self.0.as_mut().poll_read(ctx, &mut buf))?;
+
+        Poll::Ready(Ok(buf.filled().len()))
     }
+}
 
-    /// Internal method for adding a file part to the form.
-    fn _add_file<P, F>(&mut self, name: F, path: P, mime: Option<Mime>) -> io::Result<()>
-    where
-        P: AsRef<Path>,
-        F: Display,
-    {
-        let f = File::open(&path)?;
-        let mime = if let Some(ext) = path.as_ref().extension() {
-            Mime::from_str(ext.to_string_lossy().borrow()).ok()
-        } else {
-            mime
-        };
-        match f.metadata() {
-            // If the path is not a file, it can't be uploaded because there
-            // is no content.
-            //
-            Ok(meta) if !meta.is_file() => Err(io::Error::new(
-                io::ErrorKind::InvalidInput,
-                "expected a file not directory",
-            )),
+/// Writes the content of a part while the body is written.
+///
+/// A writer is created with
+/// [`Form::add_writer`](/hyper_multipart_rfc7578/client/multipart/struct.Form.html#method.add_writer).
+/// It implements the futures `AsyncWrite` trait, and Tokio's with the
+/// `tokio` feature.
+pub struct PartWriter {
+    sender: mpsc::Sender<Bytes>,
+}
 
-            // If there is some metadata on the file, try to derive some
-            // header values.
-            //
-            Ok(_) => Ok(()),
+impl PartWriter {
+    /// Returns an error for writing to a part whose body was dropped.
+    fn body_dropped() -> io::Error {
+        io::Error::from(Error::BodyDropped)
+    }
+}
 
-            // The file metadata could not be accessed. This MIGHT not be an
-            // error, if the file could be opened.
-            //
-            Err(e) => Err(e),
-        }?;
+impl AsyncWrite for PartWriter {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        ctx: &mut Context,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        futures::ready!(self.sender.poll_ready(ctx)).map_err(|_| PartWriter::body_dropped())?;
 
-        let read = Box::new(f);
+        self.sender
+            .start_send(Bytes::copy_from_slice(buf))
+            .map_err(|_| PartWriter::body_dropped())?;
 
-        self.parts.push(Part::new(
-            Inner::Read(read),
-            name,
-            mime,
-            Some(path.as_ref().as_os_str().to_string_lossy()),
-        ));
+        Poll::Ready(Ok(buf.len()))
+    }
 
-        Ok(())
+    fn poll_flush(mut self: Pin<&mut Self>, ctx: &mut Context) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.sender)
+            .poll_flush(ctx)
+            .map_err(|_| PartWriter::body_dropped())
+    }
+
+    fn poll_close(mut self: Pin<&mut Self>, _: &mut Context) -> Poll<io::Result<()>> {
+        self.sender.close_channel();
+
+        Poll::Ready(Ok(()))
     }
 }
 
-impl From<Form> for Body {
-    /// Turns a `Form` into a multipart `Body`.
-    #[inline]
-    fn from(form: Form) -> Self {
-        Body {
-            buf_size: 2048,
-            current: None,
-            parts: form.parts.into_iter().peekable(),
-            boundary: form.boundary,
-        }
+#[cfg(feature = "tokio")]
+impl tokio::io::AsyncWrite for PartWriter {
+    fn poll_write(self: Pin<&mut Self>, ctx: &mut Context, buf: &[u8]) -> Poll<io::Result<usize>> {
+        AsyncWrite::poll_write(self, ctx, buf)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, ctx: &mut Context) -> Poll<io::Result<()>> {
+        AsyncWrite::poll_flush(self, ctx)
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, ctx: &mut Context) -> Poll<io::Result<()>> {
+        AsyncWrite::poll_close(self, ctx)
     }
 }
 
-/// One part of a body delimited by a boundary line.
-///
-/// [See RFC2046 5.1](https://tools.ietf.org/html/rfc2046#section-5.1).
-pub struct Part {
-    inner: Inner,
+/// A reader and seeker.
+trait ReadSeek: Read + Seek + Send {}
 
-    /// Each part can include a Content-Type header field. If this
-    /// is not specified, it defaults to "text/plain", or
-    /// "application/octet-stream" for file data.
-    ///
-    /// [See](https://tools.ietf.org/html/rfc7578#section-4.4)
-    content_type: String,
+impl<T> ReadSeek for T where T: Read + Seek + Send {}
 
-    /// Each part must contain a Content-Disposition header field.
-    ///
-    /// [See](https://tools.ietf.org/html/rfc7578#section-4.2).
-    content_disposition: String,
+struct SharedReaderInner {
+    read: Box<dyn ReadSeek>,
+
+    /// The position the reader started at, recorded the first time it is
+    /// rewound.
+    start: Option<u64>,
 }
 
-impl Part {
-    /// Internal method to build a new Part instance. Sets the disposition type,
-    /// content-type, and the disposition parameters for name, and optionally
-    /// for filename.
-    ///
-    /// Per [4.3](https://tools.ietf.org/html/rfc7578#section-4.3), if multiple
-    /// files need to be specified for one form field, they can all be specified
-    /// with the same name parameter.
-    fn new<N, F>(inner: Inner, name: N, mime: Option<Mime>, filename: Option<F>) -> Part
+/// A seekable reader shared between clones of a part.
+#[derive(Clone)]
+struct SharedReader(Arc<Mutex<SharedReaderInner>>);
+
+impl SharedReader {
+    fn new<R>(read: R) -> SharedReader
     where
-        N: Display,
-        F: Display,
+        R: 'static + Read + Seek + Send,
     {
-        // `name` disposition parameter is required. It should correspond to the
-        // name of a form field.
-        //
-        // [See 4.2](https://tools.ietf.org/html/rfc7578#section-4.2)
-        //
-        let mut disposition_params = vec![format!("name=\"{}\"", name)];
+        SharedReader(Arc::new(Mutex::new(SharedReaderInner {
+            read: Box::new(read),
+            start: None,
+        })))
+    }
 
-        // `filename` can be supplied for files, but is totally optional.
-        //
-        // [See 4.2](https://tools.ietf.org/html/rfc7578#section-4.2)
-        //
-        if let Some(filename) = filename {
-            disposition_params.push(format!("filename=\"{}\"", filename));
-        }
+    /// Seeks back to the start position of the reader.
+    fn rewind(&self) -> io::Result<()> {
+        let mut inner = self.lock()?;
 
-        let content_type = format!("{}", mime.unwrap_or_else(|| inner.default_content_type()));
+        match inner.start {
+            Some(start) => inner.read.seek(SeekFrom::Start(start)).map(|_| ()),
+            None => {
+                inner.start = Some(inner.read.stream_position()?);
 
-        Part {
-            inner,
-            content_type,
-            content_disposition: format!("form-data; {}", disposition_params.join("; ")),
+                Ok(())
+            }
         }
     }
+
+    fn lock(&self) -> io::Result<MutexGuard<'_, SharedReaderInner>> {
+        self.0
+            .lock()
+            .map_err(|_| io::Error::other("shared reader lock poisoned"))
+    }
 }
 
-enum Inner {
-    /// The `Read` variant captures multiple cases.
-    ///
-    ///   * The first is it supports uploading a file, which is explicitly
-    ///     described in RFC 7578.
-    ///
-    ///   * The second (which is not described by RFC 7578), is it can handle
-    ///     arbitrary input streams (for example, a server response).
-    ///     Any arbitrary input stream is automatically considered a file,
-    ///     and assigned the corresponding content type if not explicitly
-    ///     specified.
+impl Read for SharedReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.lock()?.read.read(buf)
+    }
+}
+
+/// The content of a part that is being written.
+enum Content {
     Read(Box<dyn Read + Send + 'static>),
 
-    /// The `String` variant handles "text/plain" form data payloads.
-    Text(String),
+    AsyncRead(Pin<Box<dyn AsyncRead + Send + 'static>>),
+
+    Bytes(Bytes),
 }
 
 impl Inner {
@@ -543,17 +6091,90 @@ impl Inner {
     #[inline]
     fn default_content_type(&self) -> Mime {
         match *self {
-            Inner::Read(_) => mime::APPLICATION_OCTET_STREAM,
+            Inner::Read(_)
+            | Inner::AsyncRead(_)
+            | Inner::Lazy(_)
+            | Inner::Sized(..)
+            | Inner::Bytes(_)
+            | Inner::Seek(_) => mime::APPLICATION_OCTET_STREAM,
+            #[cfg(feature = "fs")]
+            Inner::File { .. } => mime::APPLICATION_OCTET_STREAM,
             Inner::Text(_) => mime::TEXT_PLAIN,
         }
     }
+
+    /// Returns the length of the content, if it is known without reading
+    /// it.
+    fn len(&self) -> Option<u64> {
+        match *self {
+            Inner::Text(ref bytes) | Inner::Bytes(ref bytes) => Some(bytes.len() as u64),
+            Inner::Sized(_, len) => Some(len),
+            #[cfg(feature = "fs")]
+            Inner::File {
+                ref path,
+                ref file,
+                range,
+            } => {
+                let len = match *file {
+                    Some(ref file) => file.metadata(),
+                    None => fs::metadata(path),
+                }
+                .ok()?
+                .len();
+
+                match range {
+                    Some((offset, range_len)) => Some(len.saturating_sub(offset).min(range_len)),
+                    None => Some(len),
+                }
+            }
+            Inner::Read(_) | Inner::AsyncRead(_) | Inner::Lazy(_) | Inner::Seek(_) => None,
+        }
+    }
+
+    /// Returns whether reading the content may block.
+    #[inline]
+    fn is_blocking(&self) -> bool {
+        !matches!(
+            *self,
+            Inner::Text(_) | Inner::Bytes(_) | Inner::AsyncRead(_)
+        )
+    }
+
+    /// Returns whether the content can be cloned.
+    #[inline]
+    fn is_replayable(&self) -> bool {
+        !matches!(
+            *self,
+            Inner::Read(_) | Inner::AsyncRead(_) | Inner::Lazy(_) | Inner::Sized(..)
+        )
+    }
+
+    /// Attempts to clone the content. Files are reopened, and seekable
+    /// readers are rewound, when the clone is written.
+    fn try_clone(&self) -> Option<Inner> {
+        match *self {
+            Inner::Read(_) | Inner::AsyncRead(_) | Inner::Lazy(_) | Inner::Sized(..) => None,
+            Inner::Seek(ref read) => Some(Inner::Seek(read.clone())),
+            Inner::Text(ref text) => Some(Inner::Text(text.clone())),
+            Inner::Bytes(ref bytes) => Some(Inner::Bytes(bytes.clone())),
+            #[cfg(feature = "fs")]
+            Inner::File {
+                ref path, range, ..
+            } => Some(Inner::File {
+                path: path.clone(),
+                file: None,
+                range,
+            }),
+        }
+    }
 }
 
 /// A `BoundaryGenerator` is a policy to generate a random string to use
 /// as a part boundary.
 ///
-/// The default generator will build a random string of 6 ascii characters.
-/// If you need more complexity, you can implement this, and use it with
+/// The default generator is [`RandomBoundary`], and [`WebKitBoundary`] and
+/// [`CurlBoundary`] mimic the format of common clients. If you need a
+/// boundary in another format, you can implement this, and use it with
 /// [`Form::new`](/hyper_multipart_rfc7578/client/multipart/struct.Form.html#method.new).
 ///
 /// # Examples
@@ -574,14 +6195,149 @@ pub trait BoundaryGenerator {
     fn generate_boundary() -> String;
 }
 
-struct RandomAsciiGenerator;
+/// A `BoundaryGeneratorExt` is a boundary generator that is used through
+/// an instance, rather than through its type.
+///
+/// This is useful if the generator needs state, for example an injected or
+/// seeded random number generator. It is implemented for any closure
+/// returning a `String`. Use it with
+/// [`Form::with_generator`](/hyper_multipart_rfc7578/client/multipart/struct.Form.html#method.with_generator).
+///
+/// # Examples
+///
+/// ```
+/// use hyper_multipart_rfc7578::client::multipart::BoundaryGeneratorExt;
+/// use std::cell::Cell;
+///
+/// struct CountingGenerator(Cell<u32>);
+///
+/// impl BoundaryGeneratorExt for CountingGenerator {
+///     fn generate_boundary(&self) -> String {
+///         let n = self.0.get();
+///
+///         self.0.set(n + 1);
+///
+///         format!("boundary{}", n)
+///     }
+/// }
+/// ```
+pub trait BoundaryGeneratorExt {
+    /// Generates a String to use as a boundary.
+    fn generate_boundary(&self) -> String;
+}
+
+impl<F> BoundaryGeneratorExt for F
+where
+    F: Fn() -> String,
+{
+    #[inline]
+    fn generate_boundary(&self) -> String {
+        self()
+    }
+}
+
+/// The default boundary generator, which builds a boundary from a fixed
+/// prefix followed by `LEN` random alphanumeric characters. `LEN` defaults
+/// to 16, which is about 95 bits of randomness. The characters are drawn
+/// from `E`, which defaults to [`DefaultEntropy`].
+///
+/// # Panics
+///
+/// Generating a boundary panics if it would be longer than the 70
+/// characters allowed by
+/// [RFC 2046](https://tools.ietf.org/html/rfc2046#section-5.1.1), which
+/// is when `LEN` is greater than 52.
+///
+/// # Examples
+///
+/// ```
+/// use hyper_multipart_rfc7578::client::multipart::{
+///     self, BoundaryGenerator, RandomBoundary,
+/// };
+///
+/// assert!(RandomBoundary::<32>::generate_boundary().starts_with("----HyperMultipart"));
+/// assert_eq!(RandomBoundary::<32>::generate_boundary().len(), 50);
+///
+/// let form = multipart::Form::new::<RandomBoundary<32>>();
+/// ```
+#[derive(Clone, Copy, Debug, Default)]
+pub struct RandomBoundary<const LEN: usize = 16, E = DefaultEntropy>(PhantomData<fn() -> E>);
+
+impl<const LEN: usize, E> RandomBoundary<LEN, E> {
+    /// The prefix of every boundary, which makes it easy to recognize.
+    const PREFIX: &'static str = "----HyperMultipart";
+}
+
+impl<const LEN: usize, E> BoundaryGenerator for RandomBoundary<LEN, E>
+where
+    E: Entropy,
+{
+    fn generate_boundary() -> String {
+        assert!(
+            Self::PREFIX.len() + LEN <= 70,
+            "boundary must not be longer than 70 characters"
+        );
+
+        random_alphanumeric::<E>(Self::PREFIX, LEN)
+    }
+}
+
+/// A boundary generator in the format of WebKit and Blink based browsers,
+/// such as `----WebKitFormBoundary7MA4YWxkTrZu0gW`.
+///
+/// Some servers and web application firewalls expect a boundary in the
+/// format a browser sends.
+///
+/// # Examples
+///
+/// ```
+/// use hyper_multipart_rfc7578::client::multipart::{self, WebKitBoundary};
+///
+/// let form = multipart::Form::new::<WebKitBoundary>();
+///
+/// assert!(form.content_type().contains("----WebKitFormBoundary"));
+/// ```
+#[derive(Clone, Copy, Debug, Default)]
+pub struct WebKitBoundary;
 
-impl BoundaryGenerator for RandomAsciiGenerator {
-    /// Creates a boundary of 6 ascii characters.
+impl BoundaryGenerator for WebKitBoundary {
     fn generate_boundary() -> String {
-        let rng = rand::thread_rng();
-        let ascii = rng.sample_iter(&Alphanumeric);
+        random_alphanumeric::<DefaultEntropy>("----WebKitFormBoundary", 16)
+    }
+}
+
+/// A boundary generator in the format of curl, which is 24 dashes followed
+/// by 16 hexadecimal digits, such as
+/// `------------------------d74496d66958873e`.
+///
+/// # Examples
+///
+/// ```
+/// use hyper_multipart_rfc7578::client::multipart::{self, CurlBoundary};
+///
+/// let form = multipart::Form::new::<CurlBoundary>();
+/// ```
+#[derive(Clone, Copy, Debug, Default)]
+pub struct CurlBoundary;
 
-        String::from_iter(ascii.take(6).map(char::from))
+impl BoundaryGenerator for CurlBoundary {
+    fn generate_boundary() -> String {
+        format!(
+            "{}{:016x}",
+            "-".repeat(24),
+            entropy::random_u64::<DefaultEntropy>()
+        )
     }
 }
+
+/// Returns a boundary made of a prefix followed by `len` random
+/// alphanumeric characters.
+fn random_alphanumeric<E>(prefix: &str, len: usize) -> String
+where
+    E: Entropy,
+{
+    prefix
+        .chars()
+        .chain(entropy::alphanumeric::<E>(len))
+        .collect()
+}
@@ -0,0 +1,209 @@
+// Copyright 2017 rust-hyper-multipart-rfc7578 Developers
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+//
+
+use std::{
+    fmt::{self, Display},
+    io::Write,
+    ops::{Deref, DerefMut},
+    str::FromStr,
+};
+
+use http::{header::CONTENT_LENGTH, request::Builder, Request};
+use http_body_util::StreamBody;
+use mime::Mime;
+
+use super::{Body, BoundaryGenerator, Form, Inner, Part, Subtype};
+
+/// A multipart/mixed batch of HTTP requests, as sent to the batch endpoints
+/// of Google APIs, OData services, and other cloud APIs. Each request is a
+/// part with the `application/http` content type and a Content-ID, which
+/// the server echoes in the part of its response.
+///
+/// Every method of [`Form`] is available through the batch form, to
+/// configure the body.
+///
+/// # Examples
+///
+/// ```
+/// use hyper::Request;
+/// use hyper_multipart_rfc7578::client::multipart;
+///
+/// let mut batch = multipart::BatchForm::default();
+///
+/// batch.add_request(Request::get("/farm/v1/animals/pony").body("").unwrap());
+/// batch.add_request(
+///     Request::patch("/farm/v1/animals/sheep")
+///         .header("Content-Type", "application/json")
+///         .body(r#"{"name":"Dolly"}"#)
+///         .unwrap(),
+/// );
+///
+/// let req = batch.set_body(Request::post("http://localhost/batch")).unwrap();
+/// ```
+pub struct BatchForm {
+    form: Form,
+}
+
+impl BatchForm {
+    /// Creates a new batch with the specified boundary generator function.
+    /// See [`Form::new`].
+    #[inline]
+    pub fn new<G>() -> BatchForm
+    where
+        G: BoundaryGenerator,
+    {
+        BatchForm::from(Form::new::<G>())
+    }
+
+    /// Adds a request to the batch, with the number of the part as its
+    /// Content-ID, starting from 1.
+    ///
+    /// The request is written with its URI as it was given, so a relative
+    /// URI such as `/v1/items/1` stays relative. A Content-Length header is
+    /// added if the body isn't empty and the request doesn't have one.
+    pub fn add_request<B>(&mut self, req: Request<B>)
+    where
+        B: AsRef<[u8]>,
+    {
+        let content_id = (self.form.parts.len() + 1).to_string();
+
+        self.add_request_with_id(content_id, req)
+    }
+
+    /// Adds a request to the batch, with the given Content-ID. See
+    /// [`BatchForm::add_request`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hyper::Request;
+    /// use hyper_multipart_rfc7578::client::multipart;
+    ///
+    /// let mut batch = multipart::BatchForm::default();
+    ///
+    /// batch.add_request_with_id("item1", Request::delete("/v1/items/1").body("").unwrap());
+    /// ```
+    pub fn add_request_with_id<S, B>(&mut self, content_id: S, req: Request<B>)
+    where
+        S: Display,
+        B: AsRef<[u8]>,
+    {
+        let content_id = content_id.to_string();
+
+        self.form.parts_mut().push(
+            Part::new::<_, String>(
+                Inner::Bytes(serialize_request(&req).into()),
+                &content_id,
+                Some(Mime::from_str("application/http").expect("a valid mime type")),
+                None,
+            )
+            .content_id(content_id),
+        );
+    }
+
+    /// Updates a request instance with the multipart/mixed Content-Type
+    /// header and the payload data. See [`Form::set_body`].
+    #[inline]
+    pub fn set_body(self, req: Builder) -> Result<Request<StreamBody<Body>>, http::Error> {
+        self.form.set_body(req)
+    }
+}
+
+impl Default for BatchForm {
+    /// Creates a new batch with the default boundary generator.
+    #[inline]
+    fn default() -> BatchForm {
+        BatchForm::from(Form::default())
+    }
+}
+
+impl From<Form> for BatchForm {
+    /// Turns a form into a batch, by making it multipart/mixed. Parts the
+    /// form already has are kept.
+    #[inline]
+    fn from(mut form: Form) -> Self {
+        form.subtype(Subtype::Mixed);
+
+        BatchForm { form }
+    }
+}
+
+impl From<BatchForm> for Form {
+    #[inline]
+    fn from(batch: BatchForm) -> Self {
+        batch.form
+    }
+}
+
+impl From<BatchForm> for Body {
+    /// Turns a `BatchForm` into a multipart `Body`.
+    #[inline]
+    fn from(batch: BatchForm) -> Self {
+        Body::from(batch.form)
+    }
+}
+
+impl Deref for BatchForm {
+    type Target = Form;
+
+    #[inline]
+    fn deref(&self) -> &Form {
+        &self.form
+    }
+}
+
+impl DerefMut for BatchForm {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut Form {
+        &mut self.form
+    }
+}
+
+impl fmt::Debug for BatchForm {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("BatchForm")
+            .field("form", &self.form)
+            .finish()
+    }
+}
+
+/// Writes a request in the HTTP/1.1 message format.
+///
+/// [See](https://tools.ietf.org/html/rfc9112#section-2.1).
+fn serialize_request<B>(req: &Request<B>) -> Vec<u8>
+where
+    B: AsRef<[u8]>,
+{
+    let body = req.body().as_ref();
+    let mut buf = vec![];
+
+    // Writing into a vector can't fail.
+    //
+    let _ = write!(
+        buf,
+        "{} {} {:?}\r\n",
+        req.method(),
+        req.uri(),
+        req.version()
+    );
+
+    for (name, value) in req.headers() {
+        let _ = write!(buf, "{}: ", name);
+
+        buf.extend_from_slice(value.as_bytes());
+        buf.extend_from_slice(b"\r\n");
+    }
+
+    if !body.is_empty() && !req.headers().contains_key(CONTENT_LENGTH) {
+        let _ = write!(buf, "{}: {}\r\n", CONTENT_LENGTH, body.len());
+    }
+
+    buf.extend_from_slice(b"\r\n");
+    buf.extend_from_slice(body);
+    buf
+}
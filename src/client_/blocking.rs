@@ -0,0 +1,197 @@
+// Copyright 2017 rust-hyper-multipart-rfc7578 Developers
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+//
+
+//! A multipart body written without an async runtime, for blocking
+//! clients such as a `std::net::TcpStream`, or CGI-style environments.
+//!
+//! The form is built with the same [`Form`] as the async body, and turned
+//! into a [`Body`] that implements `Read`, and `Iterator` over its chunks.
+//! Async readers added to the form are driven on the calling thread.
+//!
+//! With the `curl` feature, a `CurlReader` sends the body through
+//! libcurl's read callback.
+
+use std::{
+    fmt,
+    io::{self, Read},
+};
+
+use bytes::{Buf, Bytes};
+use futures::{executor, future};
+
+pub use super::Form;
+
+/// Multipart body that is read on the calling thread.
+///
+/// # Examples
+///
+/// ```
+/// use hyper_multipart_rfc7578::client::multipart::blocking;
+/// use std::io::Read;
+///
+/// let mut form = blocking::Form::with_generator(&|| "boundary".to_string());
+///
+/// form.add_text("text", "Hello World!");
+///
+/// let mut body = vec![];
+///
+/// blocking::Body::from(form).read_to_end(&mut body).unwrap();
+///
+/// assert!(body.ends_with(b"Hello World!\r\n--boundary--"));
+/// ```
+pub struct Body {
+    body: super::Body,
+
+    /// Data of the current chunk that wasn't read yet.
+    buf: Bytes,
+}
+
+impl Body {
+    /// Writes the next chunk of the body.
+    fn next_chunk(&mut self) -> Option<io::Result<Bytes>> {
+        let body = &mut self.body;
+
+        executor::block_on(future::poll_fn(|ctx| body.poll_data(ctx)))
+            .map(|res| res.map_err(io::Error::from))
+    }
+}
+
+impl From<Form> for Body {
+    /// Turns a `Form` into a blocking multipart `Body`. Blocking readers are
+    /// always read on the calling thread, even if the form was set to
+    /// spawn them on a blocking pool.
+    fn from(mut form: Form) -> Self {
+        form.body_config.spawner = None;
+
+        Body {
+            body: super::Body::from(form),
+            buf: Bytes::new(),
+        }
+    }
+}
+
+impl Read for Body {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.buf.is_empty() && !buf.is_empty() {
+            match self.next_chunk() {
+                Some(chunk) => self.buf = chunk?,
+                None => return Ok(0),
+            }
+        }
+
+        let n = buf.len().min(self.buf.len());
+
+        buf[..n].copy_from_slice(&self.buf[..n]);
+        self.buf.advance(n);
+
+        Ok(n)
+    }
+}
+
+impl Iterator for Body {
+    type Item = io::Result<Vec<u8>>;
+
+    /// Returns the next chunk of the body, starting with whatever wasn't
+    /// read through `Read` yet.
+    fn next(&mut self) -> Option<Self::Item> {
+        if !self.buf.is_empty() {
+            return Some(Ok(std::mem::take(&mut self.buf).to_vec()));
+        }
+
+        self.next_chunk().map(|chunk| chunk.map(Vec::from))
+    }
+}
+
+impl fmt::Debug for Body {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Body")
+            .field("buffered", &self.buf.len())
+            .finish()
+    }
+}
+
+/// Presents a blocking [`Body`] to libcurl, which pulls the body through a
+/// read callback. This lets the form builder be used with the `curl` crate,
+/// or other libcurl bindings.
+///
+/// The reader is a [`Handler`](curl::easy::Handler) that can be passed to
+/// `Easy2`, or read from the read function of an `Easy` transfer. If the
+/// body fails, the transfer is aborted, and the error is kept to be
+/// returned by [`CurlReader::take_error`].
+///
+/// # Examples
+///
+/// ```no_run
+/// use curl::easy::{Easy2, List};
+/// use hyper_multipart_rfc7578::client::multipart::blocking;
+///
+/// # fn run() -> Result<(), curl::Error> {
+/// let mut form = blocking::Form::default();
+///
+/// form.add_text("text", "Hello World!");
+///
+/// let mut headers = List::new();
+///
+/// headers.append(&format!("Content-Type: {}", form.content_type()))?;
+///
+/// let mut easy = Easy2::new(blocking::CurlReader::from(blocking::Body::from(form)));
+///
+/// easy.url("http://localhost/upload")?;
+/// easy.post(true)?;
+/// easy.http_headers(headers)?;
+/// easy.perform()?;
+///
+/// if let Some(e) = easy.get_mut().take_error() {
+///     eprintln!("the body failed: {}", e);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[cfg(feature = "curl")]
+#[derive(Debug)]
+pub struct CurlReader {
+    body: Body,
+
+    /// The error the body failed with, if any.
+    error: Option<io::Error>,
+}
+
+#[cfg(feature = "curl")]
+impl CurlReader {
+    /// Returns the error the body failed with, if the transfer was aborted
+    /// because of it.
+    #[inline]
+    pub fn take_error(&mut self) -> Option<io::Error> {
+        self.error.take()
+    }
+}
+
+#[cfg(feature = "curl")]
+impl From<Body> for CurlReader {
+    #[inline]
+    fn from(body: Body) -> Self {
+        CurlReader { body, error: None }
+    }
+}
+
+#[cfg(feature = "curl")]
+impl curl::easy::Handler for CurlReader {
+    fn read(&mut self, data: &mut [u8]) -> Result<usize, curl::easy::ReadError> {
+        loop {
+            match self.body.read(data) {
+                Ok(n) => return Ok(n),
+                Err(ref e) if e.kind() == io::ErrorKind::Interrupted => (),
+                Err(e) => {
+                    self.error = Some(e);
+
+                    return Err(curl::easy::ReadError::Abort);
+                }
+            }
+        }
+    }
+}
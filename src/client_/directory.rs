@@ -0,0 +1,439 @@
+// Copyright 2017 rust-hyper-multipart-rfc7578 Developers
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+//
+
+use std::{
+    collections::HashSet,
+    ffi::{OsStr, OsString},
+    fmt::Display,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+#[cfg(feature = "tar")]
+use mime::Mime;
+#[cfg(feature = "tar")]
+use std::{io::Read, str::FromStr};
+
+use super::{mime_from_path, path_error, Form, Inner, Part};
+
+/// Options for adding the files of a directory with
+/// [`Form::add_directory_with_options`].
+///
+/// By default, hidden files and directories are skipped, and so are
+/// symbolic links.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct DirectoryOptions {
+    hidden: bool,
+
+    follow_symlinks: bool,
+}
+
+impl DirectoryOptions {
+    /// Creates the default options.
+    #[inline]
+    pub fn new() -> DirectoryOptions {
+        DirectoryOptions::default()
+    }
+
+    /// Sets whether files and directories whose name starts with a dot are
+    /// added.
+    #[inline]
+    pub fn hidden(mut self, hidden: bool) -> DirectoryOptions {
+        self.hidden = hidden;
+        self
+    }
+
+    /// Sets whether symbolic links are followed. Links to files are added
+    /// as the file they point to, and links to directories are walked,
+    /// unless the directory was already walked. Otherwise, links are
+    /// skipped.
+    #[inline]
+    pub fn follow_symlinks(mut self, follow: bool) -> DirectoryOptions {
+        self.follow_symlinks = follow;
+        self
+    }
+}
+
+impl Form {
+    /// Adds every file in a directory and its subdirectories, as one part
+    /// per file with the same name, and attempts to derive their mime
+    /// types. The filename of each part is the path of the file relative to
+    /// the directory, with components separated by `/`. Hidden files and
+    /// symbolic links are skipped.
+    ///
+    /// Files are added in order of their path, and are only opened when
+    /// their part starts streaming. The whole directory is walked even if
+    /// some of its entries can't be read. If any of them failed, no file is
+    /// added, and the error names every path that failed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hyper_multipart_rfc7578::client::multipart;
+    ///
+    /// let mut form = multipart::Form::default();
+    ///
+    /// form.add_directory("files", "src").expect("directory to exist");
+    ///
+    /// assert!(form.parts().iter().any(|part| part.filename() == Some("lib.rs")));
+    /// ```
+    #[inline]
+    pub fn add_directory<F, P>(&mut self, name: F, path: P) -> io::Result<()>
+    where
+        F: Display,
+        P: AsRef<Path>,
+    {
+        self.add_directory_with_options(name, path, DirectoryOptions::default())
+    }
+
+    /// Adds every file in a directory and its subdirectories, with options
+    /// for hidden files and symbolic links. See [`Form::add_directory`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hyper_multipart_rfc7578::client::multipart::{self, DirectoryOptions};
+    ///
+    /// let mut form = multipart::Form::default();
+    /// let options = DirectoryOptions::new().hidden(true).follow_symlinks(true);
+    ///
+    /// form.add_directory_with_options("files", "src", options)
+    ///     .expect("directory to exist");
+    /// ```
+    pub fn add_directory_with_options<F, P>(
+        &mut self,
+        name: F,
+        path: P,
+        options: DirectoryOptions,
+    ) -> io::Result<()>
+    where
+        F: Display,
+        P: AsRef<Path>,
+    {
+        for (path, filename) in walk(path.as_ref(), options)? {
+            let mime = mime_from_path(&path);
+
+            self.parts_mut().push(
+                Part::new::<_, String>(
+                    Inner::File {
+                        path,
+                        file: None,
+                        range: None,
+                    },
+                    &name,
+                    mime,
+                    None,
+                )
+                .filename_os(filename),
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Adds a directory and its subdirectories as a single file part
+    /// containing a tar archive, named after the directory. The archive is
+    /// written while the body is streamed, so it is never stored in memory
+    /// or on disk in full. Hidden files and symbolic links are skipped.
+    ///
+    /// The archive contains the files of the directory, with their path
+    /// relative to it, in order of their path. Empty directories aren't
+    /// archived. The directory is walked when it is added, and each file is
+    /// only opened when it is archived. The size of each entry is the size
+    /// of the file when it is opened, and a file that shrinks or grows while
+    /// it is archived fails the body, rather than corrupting the archive.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hyper_multipart_rfc7578::client::multipart;
+    ///
+    /// let mut form = multipart::Form::default();
+    ///
+    /// form.add_directory_as_tar("artifacts", "src").expect("directory to exist");
+    ///
+    /// assert_eq!(form.parts()[0].filename(), Some("src.tar"));
+    /// ```
+    #[cfg(feature = "tar")]
+    #[inline]
+    pub fn add_directory_as_tar<F, P>(&mut self, name: F, path: P) -> io::Result<()>
+    where
+        F: Display,
+        P: AsRef<Path>,
+    {
+        self.add_directory_as_tar_with_options(name, path, DirectoryOptions::default())
+    }
+
+    /// Adds a directory and its subdirectories as a single file part
+    /// containing a tar archive, with options for hidden files and symbolic
+    /// links. See [`Form::add_directory_as_tar`].
+    #[cfg(feature = "tar")]
+    pub fn add_directory_as_tar_with_options<F, P>(
+        &mut self,
+        name: F,
+        path: P,
+        options: DirectoryOptions,
+    ) -> io::Result<()>
+    where
+        F: Display,
+        P: AsRef<Path>,
+    {
+        let path = path.as_ref();
+        let files = walk(path, options)?;
+        let mut filename = path
+            .canonicalize()
+            .ok()
+            .and_then(|path| path.file_name().map(OsStr::to_os_string))
+            .unwrap_or_else(|| OsString::from("archive"));
+
+        filename.push(".tar");
+
+        self.parts_mut().push(
+            Part::new::<_, String>(
+                Inner::Read(Box::new(TarReader::new(files))),
+                name,
+                Some(Mime::from_str("application/x-tar").expect("a valid mime type")),
+                None,
+            )
+            .filename_os(filename),
+        );
+
+        Ok(())
+    }
+}
+
+/// Writes a tar archive of files as it is read.
+#[cfg(feature = "tar")]
+struct TarReader {
+    /// The files left to archive.
+    files: std::vec::IntoIter<(PathBuf, OsString)>,
+
+    /// Renders the headers of each file, and the end of the archive.
+    builder: tar::Builder<Vec<u8>>,
+
+    /// Headers or padding that weren't read yet.
+    pending: io::Cursor<Vec<u8>>,
+
+    /// The file being archived, along with the size written in its header.
+    current: Option<(io::Take<fs::File>, u64)>,
+
+    /// Whether the end of the archive was rendered.
+    finished: bool,
+}
+
+#[cfg(feature = "tar")]
+impl TarReader {
+    fn new(files: Vec<(PathBuf, OsString)>) -> TarReader {
+        TarReader {
+            files: files.into_iter(),
+            builder: tar::Builder::new(vec![]),
+            pending: io::Cursor::new(vec![]),
+            current: None,
+            finished: false,
+        }
+    }
+
+    /// Opens the next file, and renders its header, or renders the end of
+    /// the archive if there are no files left.
+    fn next_entry(&mut self) -> io::Result<()> {
+        match self.files.next() {
+            Some((path, relative)) => {
+                let file = fs::File::open(&path).map_err(|e| path_error(&path, e))?;
+                let meta = file.metadata().map_err(|e| path_error(&path, e))?;
+                let mut header = tar::Header::new_gnu();
+
+                header.set_metadata(&meta);
+
+                // The content is written separately, so only the header, and
+                // any extension for a long path, is rendered.
+                //
+                self.builder
+                    .append_data(&mut header, Path::new(&relative), io::empty())
+                    .map_err(|e| path_error(&path, e))?;
+                self.current = Some((file.take(meta.len()), meta.len()));
+            }
+            None => {
+                self.builder.finish()?;
+                self.finished = true;
+            }
+        }
+
+        self.pending = io::Cursor::new(std::mem::take(self.builder.get_mut()));
+
+        Ok(())
+    }
+}
+
+#[cfg(feature = "tar")]
+impl Read for TarReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        loop {
+            let n = self.pending.read(buf)?;
+
+            if n > 0 || buf.is_empty() {
+                return Ok(n);
+            }
+
+            if let Some((ref mut file, len)) = self.current {
+                let n = file.read(buf)?;
+
+                if n > 0 {
+                    return Ok(n);
+                }
+
+                if file.limit() > 0 {
+                    return Err(io::Error::new(
+                        io::ErrorKind::UnexpectedEof,
+                        "file shrank while it was archived",
+                    ));
+                }
+
+                // Anything past the size in the header would be cut off, so
+                // the file must end there.
+                //
+                if file.get_mut().read(&mut [0])? > 0 {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        "file grew while it was archived",
+                    ));
+                }
+
+                // The content of each entry is padded to a whole block.
+                //
+                let padding = (512 - len % 512) % 512;
+
+                self.pending = io::Cursor::new(vec![0; padding as usize]);
+                self.current = None;
+            } else if self.finished {
+                return Ok(0);
+            } else {
+                self.next_entry()?;
+            }
+        }
+    }
+}
+
+/// Returns the files in a directory and its subdirectories, in order of
+/// their path, along with their path relative to the directory. Entries
+/// that can't be read don't stop the walk, and are all reported in the
+/// error.
+fn walk(root: &Path, options: DirectoryOptions) -> io::Result<Vec<(PathBuf, OsString)>> {
+    if !fs::metadata(root)
+        .map_err(|e| path_error(root, e))?
+        .is_dir()
+    {
+        return Err(path_error(
+            root,
+            io::Error::new(io::ErrorKind::InvalidInput, "expected a directory"),
+        ));
+    }
+
+    let mut files = vec![];
+    let mut errors = vec![];
+    let mut visited = HashSet::new();
+
+    walk_dir(
+        root,
+        &OsString::new(),
+        options,
+        &mut visited,
+        &mut files,
+        &mut errors,
+    );
+
+    match errors.len() {
+        0 => Ok(files),
+        1 => Err(errors.remove(0)),
+        len => {
+            let messages = errors
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join("; ");
+
+            Err(io::Error::new(
+                errors[0].kind(),
+                format!("{} paths can't be read: {}", len, messages),
+            ))
+        }
+    }
+}
+
+fn walk_dir(
+    dir: &Path,
+    prefix: &OsStr,
+    options: DirectoryOptions,
+    visited: &mut HashSet<PathBuf>,
+    files: &mut Vec<(PathBuf, OsString)>,
+    errors: &mut Vec<io::Error>,
+) {
+    // A directory reached through links more than once is only walked the
+    // first time, so a link to a parent doesn't loop forever.
+    //
+    match fs::canonicalize(dir) {
+        Ok(canonical) => {
+            if !visited.insert(canonical) {
+                return;
+            }
+        }
+        Err(e) => return errors.push(path_error(dir, e)),
+    }
+
+    let mut entries =
+        match fs::read_dir(dir).and_then(|entries| entries.collect::<io::Result<Vec<_>>>()) {
+            Ok(entries) => entries,
+            Err(e) => return errors.push(path_error(dir, e)),
+        };
+
+    entries.sort_by_key(|entry| entry.file_name());
+
+    for entry in entries {
+        let name = entry.file_name();
+        let path = entry.path();
+
+        if !options.hidden && name.as_encoded_bytes().starts_with(b".") {
+            continue;
+        }
+
+        let mut file_type = match entry.file_type() {
+            Ok(file_type) => file_type,
+            Err(e) => {
+                errors.push(path_error(&path, e));
+                continue;
+            }
+        };
+
+        if file_type.is_symlink() {
+            if !options.follow_symlinks {
+                continue;
+            }
+
+            file_type = match fs::metadata(&path) {
+                Ok(meta) => meta.file_type(),
+                Err(e) => {
+                    errors.push(path_error(&path, e));
+                    continue;
+                }
+            };
+        }
+
+        let mut relative = prefix.to_os_string();
+
+        if !relative.is_empty() {
+            relative.push("/");
+        }
+
+        relative.push(&name);
+
+        if file_type.is_dir() {
+            walk_dir(&path, &relative, options, visited, files, errors);
+        } else if file_type.is_file() {
+            files.push((path, relative));
+        }
+    }
+}
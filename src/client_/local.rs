@@ -0,0 +1,346 @@
+// Copyright 2017 rust-hyper-multipart-rfc7578 Developers
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+//
+
+use std::{
+    fmt::{self, Display},
+    io::{self, Read},
+    ops::{Deref, DerefMut},
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use bytes::{Bytes, BytesMut};
+#[cfg(feature = "http")]
+use futures::stream::Stream;
+use futures::{
+    channel::mpsc,
+    future,
+    io::{AllowStdIo, AsyncRead},
+    stream::TryStreamExt,
+};
+#[cfg(feature = "http")]
+use http::{header::CONTENT_TYPE, request::Builder, Request};
+#[cfg(feature = "http")]
+use http_body::Frame;
+#[cfg(feature = "http")]
+use http_body_util::StreamBody;
+
+use super::{Body, BoundaryGenerator, Form, Inner, Part};
+use crate::error::Error;
+
+/// A form with parts whose content doesn't need to be `Send`, such as
+/// readers over `Rc`-based caches, or JavaScript objects on wasm. The body
+/// it is turned into, a [`LocalBody`], isn't `Send` either, and must be
+/// written on the thread it was created on, for example by a `LocalSet`
+/// task, or a single-threaded executor.
+///
+/// Every method of [`Form`] is available through the local form, to add
+/// parts that are `Send`, and to configure the body.
+///
+/// # Examples
+///
+/// ```
+/// use hyper_multipart_rfc7578::client::multipart;
+/// use std::{io::Cursor, rc::Rc};
+///
+/// let cached: Rc<[u8]> = Rc::from(&b"Hello World!"[..]);
+/// let mut form = multipart::LocalForm::default();
+///
+/// form.add_text("text", "Hello World!");
+/// form.add_local_reader("cached", Cursor::new(cached));
+///
+/// let body = multipart::LocalBody::from(form);
+/// ```
+#[derive(Default)]
+pub struct LocalForm {
+    form: Form,
+
+    /// The content of the local parts, sent to the parts added for them
+    /// while the body is written.
+    sources: Vec<Source>,
+}
+
+impl LocalForm {
+    /// Creates a new local form with the specified boundary generator
+    /// function. See [`Form::new`].
+    #[inline]
+    pub fn new<G>() -> LocalForm
+    where
+        G: BoundaryGenerator,
+    {
+        LocalForm::from(Form::new::<G>())
+    }
+
+    /// Adds a readable part that doesn't need to be `Send`. Reads block the
+    /// thread the body is written on.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hyper_multipart_rfc7578::client::multipart;
+    /// use std::{io::Cursor, rc::Rc};
+    ///
+    /// let mut form = multipart::LocalForm::default();
+    ///
+    /// form.add_local_reader("input", Cursor::new(Rc::<[u8]>::from(&b"Hello World!"[..])));
+    /// ```
+    pub fn add_local_reader<F, R>(&mut self, name: F, read: R)
+    where
+        F: Display,
+        R: 'static + Read,
+    {
+        self.add_local_part::<_, String, _>(name, AllowStdIo::new(read), None)
+    }
+
+    /// Adds a readable part that doesn't need to be `Send`, sent as a file
+    /// with the given filename. Reads block the thread the body is written
+    /// on.
+    pub fn add_local_reader_file<F, G, R>(&mut self, name: F, read: R, filename: G)
+    where
+        F: Display,
+        G: Display,
+        R: 'static + Read,
+    {
+        self.add_local_part(name, AllowStdIo::new(read), Some(filename))
+    }
+
+    /// Adds an asynchronous readable part that doesn't need to be `Send`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hyper_multipart_rfc7578::client::multipart;
+    /// use std::rc::Rc;
+    ///
+    /// let mut form = multipart::LocalForm::default();
+    /// let bytes = futures::io::Cursor::new(Rc::<[u8]>::from(&b"Hello World!"[..]));
+    ///
+    /// form.add_local_async_reader("input", bytes);
+    /// ```
+    pub fn add_local_async_reader<F, R>(&mut self, name: F, read: R)
+    where
+        F: Display,
+        R: 'static + AsyncRead,
+    {
+        self.add_local_part::<_, String, _>(name, read, None)
+    }
+
+    /// Adds an asynchronous readable part that doesn't need to be `Send`,
+    /// sent as a file with the given filename.
+    pub fn add_local_async_reader_file<F, G, R>(&mut self, name: F, read: R, filename: G)
+    where
+        F: Display,
+        G: Display,
+        R: 'static + AsyncRead,
+    {
+        self.add_local_part(name, read, Some(filename))
+    }
+
+    /// Internal method for adding a part whose content is sent from a
+    /// local reader through a channel, which is `Send`.
+    fn add_local_part<F, G, R>(&mut self, name: F, read: R, filename: Option<G>)
+    where
+        F: Display,
+        G: Display,
+        R: 'static + AsyncRead,
+    {
+        let (sender, receiver) = mpsc::channel(0);
+
+        self.form.parts_mut().push(Part::new(
+            Inner::AsyncRead(Box::pin(receiver.into_async_read())),
+            name,
+            None,
+            filename,
+        ));
+        self.sources.push(Source {
+            read: Box::pin(read),
+            sender,
+        });
+    }
+
+    /// Updates a request instance with the multipart Content-Type header
+    /// and the payload data, and the Content-MD5 header if its digest was
+    /// computed. See [`Form::set_body`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hyper::Request;
+    /// use hyper_multipart_rfc7578::client::multipart;
+    ///
+    /// let mut form = multipart::LocalForm::default();
+    ///
+    /// form.add_text("text", "Hello World!");
+    ///
+    /// let req = form.set_body(Request::post("http://localhost/upload")).unwrap();
+    /// ```
+    #[cfg(feature = "http")]
+    pub fn set_body(mut self, req: Builder) -> Result<Request<StreamBody<LocalBody>>, http::Error> {
+        self.form.resolve_boundary_collision();
+
+        let header = self.form.content_type();
+        let req = self
+            .form
+            .set_content_md5_header(req.header(CONTENT_TYPE, header));
+
+        self.form
+            .body_config
+            .set_trailer_header(req)
+            .body(StreamBody::new(LocalBody::from(self)))
+    }
+}
+
+impl From<Form> for LocalForm {
+    /// Turns a form into a local form, to add parts that aren't `Send`.
+    #[inline]
+    fn from(form: Form) -> Self {
+        LocalForm {
+            form,
+            sources: vec![],
+        }
+    }
+}
+
+impl Deref for LocalForm {
+    type Target = Form;
+
+    #[inline]
+    fn deref(&self) -> &Form {
+        &self.form
+    }
+}
+
+impl DerefMut for LocalForm {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut Form {
+        &mut self.form
+    }
+}
+
+impl fmt::Debug for LocalForm {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("LocalForm")
+            .field("form", &self.form)
+            .field("sources", &self.sources.len())
+            .finish()
+    }
+}
+
+/// The content of a local part, and the channel it is sent through.
+struct Source {
+    read: Pin<Box<dyn AsyncRead>>,
+
+    sender: mpsc::Sender<io::Result<Bytes>>,
+}
+
+/// Multipart body with parts that aren't `Send`, created from a
+/// [`LocalForm`].
+///
+/// It is a stream of the same frames as [`Body`], so it can be sent with
+/// hyper on a single-threaded runtime.
+pub struct LocalBody {
+    body: Body,
+
+    /// The sources of the local parts that weren't read in full yet.
+    sources: Vec<Source>,
+
+    /// The buffer the content of local parts is read into.
+    scratch: Vec<u8>,
+}
+
+impl LocalBody {
+    /// Sends content from each local part that has room in its channel.
+    /// Only one chunk is buffered for each part.
+    fn poll_sources(&mut self, ctx: &mut Context) {
+        let scratch = &mut self.scratch;
+
+        self.sources.retain_mut(|source| loop {
+            match source.sender.poll_ready(ctx) {
+                Poll::Ready(Ok(())) => (),
+
+                // The body dropped the part.
+                //
+                Poll::Ready(Err(_)) => return false,
+                Poll::Pending => return true,
+            }
+
+            let res = match source.read.as_mut().poll_read(ctx, scratch) {
+                Poll::Ready(Ok(0)) => return false,
+                Poll::Ready(Ok(n)) => Ok(Bytes::copy_from_slice(&scratch[..n])),
+                Poll::Ready(Err(ref e)) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Poll::Ready(Err(e)) => Err(e),
+                Poll::Pending => return true,
+            };
+            let failed = res.is_err();
+
+            if source.sender.start_send(res).is_err() || failed {
+                return false;
+            }
+        });
+    }
+
+    /// Collects the whole body into memory. See [`Body::collect_bytes`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hyper_multipart_rfc7578::client::multipart;
+    ///
+    /// # futures::executor::block_on(async {
+    /// let mut form = multipart::LocalForm::default();
+    ///
+    /// form.add_text("text", "Hello World!");
+    ///
+    /// let body = multipart::LocalBody::from(form).collect_bytes().await.unwrap();
+    /// # });
+    /// ```
+    pub async fn collect_bytes(mut self) -> Result<Bytes, Error> {
+        let mut buf = BytesMut::new();
+
+        while let Some(data) = future::poll_fn(|ctx| self.poll_data(ctx)).await {
+            buf.extend_from_slice(&data?);
+        }
+
+        Ok(buf.freeze())
+    }
+
+    /// Writes the next chunk of the body, after sending what is ready from
+    /// the local parts.
+    fn poll_data(&mut self, ctx: &mut Context) -> Poll<Option<Result<Bytes, Error>>> {
+        self.poll_sources(ctx);
+        self.body.poll_data(ctx)
+    }
+}
+
+impl From<LocalForm> for LocalBody {
+    /// Turns a `LocalForm` into a multipart `LocalBody`.
+    fn from(form: LocalForm) -> Self {
+        let scratch = vec![0; form.form.body_config.chunk_size];
+
+        LocalBody {
+            body: Body::from(form.form),
+            sources: form.sources,
+            scratch,
+        }
+    }
+}
+
+#[cfg(feature = "http")]
+impl Stream for LocalBody {
+    type Item = Result<Frame<Bytes>, Error>;
+
+    fn poll_next(mut self: Pin<&mut Self>, ctx: &mut Context) -> Poll<Option<Self::Item>> {
+        self.poll_sources(ctx);
+
+        Pin::new(&mut self.body).poll_next(ctx)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, None)
+    }
+}
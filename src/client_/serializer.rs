@@ -0,0 +1,143 @@
+// Copyright 2017 rust-hyper-multipart-rfc7578 Developers
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+//
+
+use std::{
+    fmt,
+    io::{self, Write},
+    sync::Arc,
+};
+
+use super::{
+    write_boundary, write_final_boundary, write_headers, NameEncoding, NonUtf8Filename, Part,
+    Subtype,
+};
+
+/// Writes the boundaries and headers of a body, around the content of its
+/// parts. Each method writes what the crate writes by default, so an
+/// implementation only overrides what a server needs differently, such as
+/// the order or capitalization of headers, extra line breaks, or markers
+/// to debug the layout of a body.
+///
+/// Use it with [`Form::part_serializer`](super::Form::part_serializer). A
+/// serializer can write anything, so it is responsible for producing a
+/// body the server can parse.
+///
+/// # Examples
+///
+/// ```
+/// use hyper_multipart_rfc7578::client::multipart::{
+///     self, DefaultSerializer, HeaderOptions, Part, PartSerializer,
+/// };
+/// use std::io::{self, Write};
+///
+/// /// Capitalizes headers like an old server expects.
+/// struct Legacy;
+///
+/// impl PartSerializer for Legacy {
+///     fn write_headers(
+///         &self,
+///         write: &mut dyn Write,
+///         part: &Part,
+///         options: &HeaderOptions,
+///     ) -> io::Result<()> {
+///         let mut headers = vec![];
+///
+///         DefaultSerializer.write_headers(&mut headers, part, options)?;
+///
+///         let headers = String::from_utf8_lossy(&headers)
+///             .replace("Content-Disposition:", "CONTENT-DISPOSITION:");
+///
+///         write.write_all(headers.as_bytes())
+///     }
+/// }
+///
+/// let mut form = multipart::Form::default();
+///
+/// form.part_serializer(Legacy);
+/// form.add_text("text", "Hello World!");
+/// ```
+pub trait PartSerializer: Send + Sync {
+    /// Writes the boundary before a part, preceded by a CRLF if `crlf` is
+    /// set, which it is for every part but the first.
+    fn write_boundary(
+        &self,
+        mut write: &mut dyn Write,
+        boundary: &str,
+        crlf: bool,
+    ) -> io::Result<()> {
+        write_boundary(&mut write, boundary, crlf)
+    }
+
+    /// Writes the headers of a part, from the CRLF ending the boundary to
+    /// the empty line before the content.
+    fn write_headers(
+        &self,
+        mut write: &mut dyn Write,
+        part: &Part,
+        options: &HeaderOptions,
+    ) -> io::Result<()> {
+        write_headers(
+            &mut write,
+            part,
+            options.subtype,
+            options.name_encoding,
+            options.non_utf8_filename,
+            options.content_length,
+        )
+    }
+
+    /// Writes the final boundary, after the content of the last part.
+    fn write_final_boundary(&self, mut write: &mut dyn Write, boundary: &str) -> io::Result<()> {
+        write_final_boundary(&mut write, boundary)
+    }
+}
+
+/// The serializer used by default, which writes bodies as described by
+/// RFC 7578 and RFC 2046. It can be called from another serializer to
+/// change what it writes.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct DefaultSerializer;
+
+impl PartSerializer for DefaultSerializer {}
+
+/// The settings of the form the headers of a part are written with.
+#[derive(Clone, Copy, Debug)]
+#[non_exhaustive]
+pub struct HeaderOptions {
+    /// The index of the part in the body.
+    pub index: usize,
+
+    /// The multipart subtype of the body.
+    pub subtype: Subtype,
+
+    /// How names and filenames are written.
+    pub name_encoding: NameEncoding,
+
+    /// How filenames that aren't valid UTF-8 are written.
+    pub non_utf8_filename: NonUtf8Filename,
+
+    /// Whether parts of a known length have a Content-Length header.
+    pub content_length: bool,
+}
+
+/// The serializer of a form, shared with the bodies created from it.
+#[derive(Clone)]
+pub(crate) struct Serializer(pub(crate) Arc<dyn PartSerializer>);
+
+impl Default for Serializer {
+    #[inline]
+    fn default() -> Serializer {
+        Serializer(Arc::new(DefaultSerializer))
+    }
+}
+
+impl fmt::Debug for Serializer {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("Serializer")
+    }
+}
@@ -0,0 +1,48 @@
+// Copyright 2017 rust-hyper-multipart-rfc7578 Developers
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+//
+
+use std::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+    time::Duration,
+};
+
+use tokio::time::{self, Sleep};
+
+/// The time a body has to be written in full.
+pub(crate) struct Deadline {
+    after: Duration,
+
+    /// The timer, registered the first time the body is polled.
+    sleep: Option<Pin<Box<Sleep>>>,
+}
+
+impl Deadline {
+    pub(crate) fn new(after: Duration) -> Deadline {
+        Deadline { after, sleep: None }
+    }
+
+    /// The time the body had to be written in.
+    #[inline]
+    pub(crate) fn after(&self) -> Duration {
+        self.after
+    }
+
+    /// Starts the timer if it wasn't started yet, and returns whether it
+    /// expired. The waker is woken once it expires, even if the body is
+    /// waiting on something else.
+    pub(crate) fn poll_expired(&mut self, cx: &mut Context) -> Poll<()> {
+        let after = self.after;
+
+        self.sleep
+            .get_or_insert_with(|| Box::pin(time::sleep(after)))
+            .as_mut()
+            .poll(cx)
+    }
+}
@@ -0,0 +1,171 @@
+// Copyright 2017 rust-hyper-multipart-rfc7578 Developers
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+//
+
+use std::{
+    fmt,
+    io::{self, Read},
+};
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+
+/// The number of input bytes encoded on each line. Each line of base64
+/// output is at most 76 characters long.
+///
+/// [See](https://tools.ietf.org/html/rfc2045#section-6.8).
+const BASE64_LINE_INPUT: usize = 57;
+
+/// A Content-Transfer-Encoding applied to the content of a part.
+///
+/// [See](https://tools.ietf.org/html/rfc2045#section-6).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TransferEncoding {
+    /// Encodes the content using base64, split into lines of 76
+    /// characters.
+    Base64,
+}
+
+impl fmt::Display for TransferEncoding {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            TransferEncoding::Base64 => write!(f, "base64"),
+        }
+    }
+}
+
+impl TransferEncoding {
+    /// Returns the length of `len` bytes of content once encoded.
+    pub(crate) fn encoded_len(self, len: u64) -> u64 {
+        match self {
+            TransferEncoding::Base64 => {
+                let lines = len.div_ceil(BASE64_LINE_INPUT as u64);
+
+                // Every line after the first is preceded by a CRLF.
+                //
+                len.div_ceil(3) * 4 + lines.saturating_sub(1) * 2
+            }
+        }
+    }
+}
+
+/// A Content-Encoding applied to the content of a part.
+#[cfg(feature = "gzip")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ContentEncoding {
+    /// Compresses the content using gzip.
+    Gzip,
+}
+
+#[cfg(feature = "gzip")]
+impl fmt::Display for ContentEncoding {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ContentEncoding::Gzip => write!(f, "gzip"),
+        }
+    }
+}
+
+#[cfg(feature = "gzip")]
+impl ContentEncoding {
+    /// Wraps a reader so the data read from it is encoded.
+    pub(crate) fn encode<R>(self, read: R) -> Box<dyn Read + Send>
+    where
+        R: 'static + Read + Send,
+    {
+        match self {
+            ContentEncoding::Gzip => Box::new(flate2::read::GzEncoder::new(
+                read,
+                flate2::Compression::default(),
+            )),
+        }
+    }
+}
+
+/// A reader that base64 encodes the data read from an inner reader.
+pub(crate) struct Base64Reader<R> {
+    inner: R,
+
+    /// Encoded output that has not been read yet.
+    out: Vec<u8>,
+
+    /// The position of the next byte to read in `out`.
+    pos: usize,
+
+    /// Whether at least one line was encoded. Every line after the first
+    /// is preceded by a CRLF.
+    started: bool,
+
+    eof: bool,
+}
+
+impl<R> Base64Reader<R>
+where
+    R: Read,
+{
+    pub(crate) fn new(inner: R) -> Base64Reader<R> {
+        Base64Reader {
+            inner,
+            out: vec![],
+            pos: 0,
+            started: false,
+            eof: false,
+        }
+    }
+
+    /// Reads and encodes the next line of input. Returns false if the inner
+    /// reader is exhausted.
+    fn encode_line(&mut self) -> io::Result<bool> {
+        let mut input = [0; BASE64_LINE_INPUT];
+        let mut len = 0;
+
+        while len < input.len() {
+            match self.inner.read(&mut input[len..]) {
+                Ok(0) => break,
+                Ok(n) => len += n,
+                Err(ref e) if e.kind() == io::ErrorKind::Interrupted => (),
+                Err(e) => return Err(e),
+            }
+        }
+
+        if len == 0 {
+            return Ok(false);
+        }
+
+        if self.started {
+            self.out.extend_from_slice(b"\r\n");
+        }
+
+        self.started = true;
+        self.out
+            .extend_from_slice(STANDARD.encode(&input[..len]).as_bytes());
+
+        Ok(len == input.len())
+    }
+}
+
+impl<R> Read for Base64Reader<R>
+where
+    R: Read,
+{
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.pos == self.out.len() {
+            self.out.clear();
+            self.pos = 0;
+
+            while !self.eof && self.out.len() < buf.len() {
+                self.eof = !self.encode_line()?;
+            }
+        }
+
+        let n = buf.len().min(self.out.len() - self.pos);
+
+        buf[..n].copy_from_slice(&self.out[self.pos..self.pos + n]);
+        self.pos += n;
+
+        Ok(n)
+    }
+}
@@ -0,0 +1,127 @@
+// Copyright 2017 rust-hyper-multipart-rfc7578 Developers
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+//
+
+use std::{
+    collections::hash_map::RandomState,
+    hash::{BuildHasher, Hasher},
+};
+
+/// A source of random bytes for the built-in boundary generators.
+///
+/// Implement this to control where the randomness of boundaries comes
+/// from, for example a hardware generator, or an audited library, and use
+/// it with
+/// [`RandomBoundary`](/hyper_multipart_rfc7578/client/multipart/struct.RandomBoundary.html).
+///
+/// # Examples
+///
+/// ```
+/// use hyper_multipart_rfc7578::client::multipart::{self, Entropy, RandomBoundary};
+///
+/// struct Getrandom;
+///
+/// impl Entropy for Getrandom {
+///     fn fill_bytes(buf: &mut [u8]) {
+///         # let getrandom = |buf: &mut [u8]| -> Result<(), ()> { buf.fill(7); Ok(()) };
+///         getrandom(buf).expect("entropy to be available");
+///     }
+/// }
+///
+/// let form = multipart::Form::new::<RandomBoundary<16, Getrandom>>();
+/// ```
+pub trait Entropy {
+    /// Fills `buf` with random bytes.
+    fn fill_bytes(buf: &mut [u8]);
+}
+
+/// Entropy from the thread-local random number generator of the `rand`
+/// crate, which is seeded by the operating system.
+#[cfg(feature = "rand")]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct RandEntropy;
+
+#[cfg(feature = "rand")]
+impl Entropy for RandEntropy {
+    #[inline]
+    fn fill_bytes(buf: &mut [u8]) {
+        rand::RngCore::fill_bytes(&mut rand::thread_rng(), buf);
+    }
+}
+
+/// Entropy from the standard library, without any dependency.
+///
+/// Each 8 bytes are the output of a hasher with a new key from
+/// [`RandomState`], which is seeded by the operating system once per
+/// thread. This is enough to make boundaries unique, but isn't a
+/// cryptographic random number generator. On targets without a source of
+/// randomness, such as `wasm32-unknown-unknown`, the seed is fixed, so
+/// provide another [`Entropy`] there.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct StdEntropy;
+
+impl Entropy for StdEntropy {
+    fn fill_bytes(buf: &mut [u8]) {
+        for chunk in buf.chunks_mut(8) {
+            let bytes = RandomState::new().build_hasher().finish().to_le_bytes();
+
+            chunk.copy_from_slice(&bytes[..chunk.len()]);
+        }
+    }
+}
+
+/// The entropy used by the built-in boundary generators, which is
+/// [`RandEntropy`] if the `rand` feature is enabled.
+#[cfg(feature = "rand")]
+pub type DefaultEntropy = RandEntropy;
+
+/// The entropy used by the built-in boundary generators, which is
+/// [`StdEntropy`] if the `rand` feature is disabled.
+#[cfg(not(feature = "rand"))]
+pub type DefaultEntropy = StdEntropy;
+
+/// Returns `len` random alphanumeric characters.
+pub(crate) fn alphanumeric<E>(len: usize) -> impl Iterator<Item = char>
+where
+    E: Entropy,
+{
+    const ALPHANUMERIC: &[u8; 62] =
+        b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789";
+
+    let mut buf = [0; 64];
+    let mut pos = buf.len();
+
+    // Bytes from 248 up are rejected, so every character is as likely.
+    //
+    std::iter::from_fn(move || loop {
+        if pos == buf.len() {
+            E::fill_bytes(&mut buf);
+            pos = 0;
+        }
+
+        let byte = buf[pos];
+
+        pos += 1;
+
+        if byte < 248 {
+            return Some(char::from(ALPHANUMERIC[usize::from(byte % 62)]));
+        }
+    })
+    .take(len)
+}
+
+/// Returns a random `u64`.
+pub(crate) fn random_u64<E>() -> u64
+where
+    E: Entropy,
+{
+    let mut buf = [0; 8];
+
+    E::fill_bytes(&mut buf);
+
+    u64::from_le_bytes(buf)
+}
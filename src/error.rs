@@ -6,13 +6,86 @@
 // copied, modified, or distributed except according to those terms.
 //
 
-use std::{error::Error as StdError, fmt, io::Error as IoError};
+use std::{
+    error::Error as StdError,
+    fmt,
+    io::{self, Error as IoError},
+    path::PathBuf,
+    time::Duration,
+};
 
+/// An error encountered while writing a multipart body.
 #[derive(Debug)]
+#[non_exhaustive]
 pub enum Error {
+    /// Writing the headers of a part failed. Headers are rendered in memory,
+    /// so this is no longer returned, and is only kept for compatibility.
     HeaderWrite(IoError),
+
+    /// Writing a boundary failed. Boundaries are rendered in memory, so this
+    /// is no longer returned, and is only kept for compatibility.
     BoundaryWrite(IoError),
-    ContentRead(IoError),
+
+    /// Reading the content of a part failed, after `offset` bytes of its
+    /// content were read.
+    ContentRead {
+        index: usize,
+        name: String,
+        offset: u64,
+        source: IoError,
+    },
+
+    /// The writer failed while the body was written to it by
+    /// [`Body::write_into`](crate::client::multipart::Body::write_into).
+    BodyWrite(IoError),
+
+    /// The content of a part exceeded the maximum size of `max` bytes.
+    PartTooLarge {
+        index: usize,
+        name: String,
+        max: u64,
+    },
+
+    /// The body exceeded the maximum size of `max` bytes.
+    BodyTooLarge { max: u64 },
+
+    /// The content of a part contains the boundary.
+    BoundaryCollision { index: usize, name: String },
+
+    /// The boundary is empty, longer than 70 characters, or contains
+    /// characters not allowed by RFC 2046.
+    InvalidBoundary(String),
+
+    /// The name of a part can't be written in a header.
+    InvalidName { index: usize, name: String },
+
+    /// The filename of a part can't be written in a header.
+    InvalidFilename {
+        index: usize,
+        name: String,
+        filename: String,
+    },
+
+    /// The body a part was added to was dropped.
+    BodyDropped,
+
+    /// The name of a part is used by an earlier part, and the form doesn't
+    /// allow duplicate names.
+    DuplicateName { index: usize, name: String },
+
+    /// The form has no parts.
+    EmptyForm,
+
+    /// The body wasn't written in full before its deadline.
+    DeadlineExceeded { after: Duration },
+
+    /// The file of a part can't be opened, or isn't a file.
+    UnreadableFile {
+        index: usize,
+        name: String,
+        path: PathBuf,
+        source: IoError,
+    },
 }
 
 impl fmt::Display for Error {
@@ -20,7 +93,68 @@ impl fmt::Display for Error {
         match *self {
             Error::HeaderWrite(ref e) => write!(f, "Error writing headers: {}", e),
             Error::BoundaryWrite(ref e) => write!(f, "Error writing boundary: {}", e),
-            Error::ContentRead(ref e) => write!(f, "Error reading content: {}", e),
+            Error::ContentRead {
+                index,
+                ref name,
+                offset,
+                ref source,
+            } => write!(
+                f,
+                "Error reading content of part {} ({:?}) at offset {}: {}",
+                index, name, offset, source
+            ),
+            Error::BodyWrite(ref e) => write!(f, "Error writing body: {}", e),
+            Error::PartTooLarge {
+                index,
+                ref name,
+                max,
+            } => write!(
+                f,
+                "Part {} ({:?}) exceeds the maximum size of {} bytes",
+                index, name, max
+            ),
+            Error::BodyTooLarge { max } => {
+                write!(f, "Body exceeds the maximum size of {} bytes", max)
+            }
+            Error::BoundaryCollision { index, ref name } => write!(
+                f,
+                "Content of part {} ({:?}) contains the boundary",
+                index, name
+            ),
+            Error::InvalidBoundary(ref boundary) => write!(f, "Invalid boundary {:?}", boundary),
+            Error::InvalidName { index, ref name } => {
+                write!(f, "Invalid name {:?} for part {}", name, index)
+            }
+            Error::InvalidFilename {
+                index,
+                ref name,
+                ref filename,
+            } => write!(
+                f,
+                "Invalid filename {:?} for part {} ({:?})",
+                filename, index, name
+            ),
+            Error::BodyDropped => write!(f, "Body was dropped"),
+            Error::DuplicateName { index, ref name } => {
+                write!(f, "Duplicate name {:?} for part {}", name, index)
+            }
+            Error::EmptyForm => write!(f, "Form has no parts"),
+            Error::DeadlineExceeded { after } => {
+                write!(f, "Body was not written within {:?}", after)
+            }
+            Error::UnreadableFile {
+                index,
+                ref name,
+                ref path,
+                ref source,
+            } => write!(
+                f,
+                "File {} of part {} ({:?}) can't be read: {}",
+                path.display(),
+                index,
+                name,
+                source
+            ),
         }
     }
 }
@@ -30,15 +164,201 @@ impl StdError for Error {
         match *self {
             Error::HeaderWrite(_) => "Error writing headers",
             Error::BoundaryWrite(_) => "Error writing boundary",
-            Error::ContentRead(_) => "Error reading content",
+            Error::ContentRead { .. } => "Error reading content",
+            Error::BodyWrite(_) => "Error writing body",
+            Error::PartTooLarge { .. } => "Part exceeds the maximum size",
+            Error::BodyTooLarge { .. } => "Body exceeds the maximum size",
+            Error::BoundaryCollision { .. } => "Content of a part contains the boundary",
+            Error::InvalidBoundary(_) => "Invalid boundary",
+            Error::InvalidName { .. } => "Invalid name",
+            Error::InvalidFilename { .. } => "Invalid filename",
+            Error::BodyDropped => "Body was dropped",
+            Error::DuplicateName { .. } => "Duplicate name",
+            Error::EmptyForm => "Form has no parts",
+            Error::DeadlineExceeded { .. } => "Body was not written before its deadline",
+            Error::UnreadableFile { .. } => "File can't be read",
         }
     }
 
-    fn cause(&self) -> Option<&dyn StdError> {
+    fn source(&self) -> Option<&(dyn StdError + 'static)> {
         match *self {
             Error::HeaderWrite(ref e) => Some(e),
             Error::BoundaryWrite(ref e) => Some(e),
-            Error::ContentRead(ref e) => Some(e),
+            Error::ContentRead { ref source, .. } | Error::UnreadableFile { ref source, .. } => {
+                Some(source)
+            }
+            Error::BodyWrite(ref e) => Some(e),
+            Error::PartTooLarge { .. }
+            | Error::BodyTooLarge { .. }
+            | Error::BoundaryCollision { .. }
+            | Error::InvalidBoundary(_)
+            | Error::InvalidName { .. }
+            | Error::InvalidFilename { .. }
+            | Error::BodyDropped
+            | Error::DuplicateName { .. }
+            | Error::EmptyForm
+            | Error::DeadlineExceeded { .. } => None,
+        }
+    }
+}
+
+// The error is sent across threads by the executors and services bodies are
+// used with, so it must stay `Send + Sync + 'static`.
+//
+const _: () = {
+    const fn assert_send_sync<T: Send + Sync + 'static>() {}
+
+    assert_send_sync::<Error>();
+};
+
+impl Error {
+    /// Boxes the error into the error type used by hyper, tower, and most
+    /// other libraries.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hyper_multipart_rfc7578::client::Error;
+    ///
+    /// let err = Error::InvalidBoundary("".to_string()).into_boxed();
+    ///
+    /// assert_eq!(err.to_string(), "Invalid boundary \"\"");
+    /// ```
+    #[inline]
+    pub fn into_boxed(self) -> Box<dyn StdError + Send + Sync> {
+        Box::new(self)
+    }
+}
+
+impl From<Error> for IoError {
+    /// Wraps the error in an I/O error, for APIs that only report I/O
+    /// errors. Errors caused by an I/O error keep its kind.
+    fn from(err: Error) -> IoError {
+        let kind = match err {
+            Error::HeaderWrite(ref e)
+            | Error::BoundaryWrite(ref e)
+            | Error::ContentRead { source: ref e, .. }
+            | Error::UnreadableFile { source: ref e, .. }
+            | Error::BodyWrite(ref e) => e.kind(),
+            Error::PartTooLarge { .. } | Error::BodyTooLarge { .. } => io::ErrorKind::InvalidData,
+            Error::BoundaryCollision { .. }
+            | Error::InvalidBoundary(_)
+            | Error::InvalidName { .. }
+            | Error::InvalidFilename { .. }
+            | Error::DuplicateName { .. }
+            | Error::EmptyForm => io::ErrorKind::InvalidInput,
+            Error::BodyDropped => io::ErrorKind::BrokenPipe,
+            Error::DeadlineExceeded { .. } => io::ErrorKind::TimedOut,
+        };
+
+        IoError::new(kind, err)
+    }
+}
+
+/// An error encountered while parsing a multipart body.
+#[cfg(feature = "http")]
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum ParseError {
+    /// The Content-Type header is missing, is not multipart, or has no
+    /// boundary parameter.
+    InvalidContentType,
+
+    /// The underlying body returned an error.
+    Body(Box<dyn StdError + Send + Sync>),
+
+    /// The body ended before the final boundary.
+    UnexpectedEof,
+
+    /// A boundary line was followed by unexpected data.
+    InvalidBoundary,
+
+    /// A part header could not be parsed.
+    InvalidHeader,
+
+    /// The headers of a part exceeded the maximum allowed size.
+    HeadersTooLarge,
+
+    /// The content of a part exceeded the maximum size of `max` bytes.
+    PartTooLarge { max: u64 },
+}
+
+#[cfg(feature = "http")]
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ParseError::InvalidContentType => write!(f, "Invalid multipart Content-Type"),
+            ParseError::Body(ref e) => write!(f, "Error reading body: {}", e),
+            ParseError::UnexpectedEof => write!(f, "Unexpected end of body"),
+            ParseError::InvalidBoundary => write!(f, "Invalid boundary line"),
+            ParseError::InvalidHeader => write!(f, "Invalid part header"),
+            ParseError::HeadersTooLarge => write!(f, "Part headers too large"),
+            ParseError::PartTooLarge { max } => {
+                write!(f, "Part exceeds the maximum size of {} bytes", max)
+            }
+        }
+    }
+}
+
+#[cfg(feature = "http")]
+impl StdError for ParseError {
+    fn description(&self) -> &str {
+        match *self {
+            ParseError::InvalidContentType => "Invalid multipart Content-Type",
+            ParseError::Body(_) => "Error reading body",
+            ParseError::UnexpectedEof => "Unexpected end of body",
+            ParseError::InvalidBoundary => "Invalid boundary line",
+            ParseError::InvalidHeader => "Invalid part header",
+            ParseError::HeadersTooLarge => "Part headers too large",
+            ParseError::PartTooLarge { .. } => "Part exceeds the maximum size",
+        }
+    }
+
+    fn source(&self) -> Option<&(dyn StdError + 'static)> {
+        match *self {
+            ParseError::Body(ref e) => Some(&**e),
+            _ => None,
+        }
+    }
+}
+
+/// An error encountered while serializing a value into a form.
+#[cfg(feature = "serde")]
+#[derive(Debug)]
+pub enum SerializeError {
+    /// The value, or one of its fields, can't be represented as form fields.
+    Unsupported(&'static str),
+
+    /// The `Serialize` implementation of the value returned an error.
+    Custom(String),
+}
+
+#[cfg(feature = "serde")]
+impl fmt::Display for SerializeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            SerializeError::Unsupported(what) => write!(f, "Unsupported form value: {}", what),
+            SerializeError::Custom(ref msg) => write!(f, "Error serializing form: {}", msg),
         }
     }
 }
+
+#[cfg(feature = "serde")]
+impl StdError for SerializeError {
+    fn description(&self) -> &str {
+        match *self {
+            SerializeError::Unsupported(_) => "Unsupported form value",
+            SerializeError::Custom(_) => "Error serializing form",
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::ser::Error for SerializeError {
+    fn custom<T>(msg: T) -> Self
+    where
+        T: fmt::Display,
+    {
+        SerializeError::Custom(msg.to_string())
+    }
+}
@@ -10,7 +10,8 @@
 //! type described in [RFC 7578](https://tools.ietf.org/html/rfc7578) for
 //! hyper.
 //!
-//! Currently, only the client-side is implemented.
+//! The `client` module builds multipart bodies to send to a server, and the
+//! `server` module parses multipart bodies received from a client.
 //!
 //! ## Usage
 //!
@@ -55,15 +56,134 @@
 //! # Ok(())
 //! # }
 //! ```
+//!
+//! ## Features
+//!
+//! * `actix`: Implements actix-http's `MessageBody` for `Body`, so it can
+//!   be sent with awc or returned from actix-web.
+//! * `http` (enabled by default): Implements the stream of http-body frames
+//!   hyper expects for `Body` (`Form::set_body`), and enables batches of
+//!   HTTP requests (`BatchForm`) and the `server` module. Without it, the
+//!   encoded body is still available as a stream of bytes
+//!   (`Body::into_data_stream`), with no dependency on the http types.
+//! * `fs` (enabled by default): Enables adding files and directories by
+//!   path (`Form::add_file`, `Form::add_directory`), and spooling a body to a temporary file
+//!   (`Form::spool`). Without it, the crate builds for targets without a
+//!   filesystem, such as `wasm32-unknown-unknown`. There, boundaries are
+//!   generated using the browser's random number generator only with the
+//!   `rand` feature. Without `rand`, `StdEntropy` has a fixed seed on
+//!   `wasm32-unknown-unknown`, so another `Entropy` should be plugged in.
+//! * `rand` (enabled by default): Generates boundaries with the `rand`
+//!   crate (`RandEntropy`). Without it, boundaries are generated from the
+//!   standard library's randomly keyed hasher (`StdEntropy`), and another
+//!   source of randomness can be plugged into `RandomBoundary` by
+//!   implementing `Entropy`.
+//! * `tar`: Enables adding a directory as a tar archive written while the
+//!   body is streamed (`Form::add_directory_as_tar`). This implies `fs`.
+//! * `gzip`: Enables compressing the content of individual parts with gzip
+//!   (`Part::content_encoding`).
+//! * `checksum`: Enables computing the MD5 or SHA-256 digest of a part
+//!   while it is written (`Form::add_part_with_checksum`), and the
+//!   Content-MD5 header of a whole body (`Form::compute_content_md5`).
+//! * `tracing`: Emits [tracing](https://docs.rs/tracing) spans and events
+//!   while a body is written, such as the start and end of each part, the
+//!   size of each frame, and errors along with the name of the failing part.
+//! * `tokio`: Enables adding parts from Tokio asynchronous readers
+//!   (`Form::add_tokio_reader`), and running blocking reads on Tokio's
+//!   blocking thread pool (`BodyConfig::spawn_blocking`). Readers implementing the futures
+//!   `AsyncRead` trait, such as those from async-std or smol, are supported
+//!   without it.
+//! * `tokio-uring`: Enables adding files read with io_uring on the
+//!   tokio-uring runtime (`Form::add_uring_file`), instead of blocking
+//!   reads. This is only available on Linux.
+//! * `throttle`: Enables limiting the rate a body is written at
+//!   (`Body::with_rate_limit`). This depends on the Tokio timer.
+//! * `deadline`: Enables ending a body with an error if it isn't written
+//!   in full within a duration (`Body::with_deadline`). This depends on
+//!   the Tokio timer.
+//! * `serde`: Enables building a form from any value implementing
+//!   `Serialize` (`Form::from_serialize`).
+//! * `serde_json`: Enables adding parts containing a value serialized as
+//!   JSON (`Form::add_json`). This implies `serde`.
+//! * `urlencoded`: Enables building a form from
+//!   `application/x-www-form-urlencoded` data (`Form::from_urlencoded`).
+//! * `reqwest`: Enables sending a form with a reqwest client
+//!   (`Form::set_reqwest_body`), and converting a `Body` into a
+//!   `reqwest::Body`.
+//! * `curl`: Enables sending a blocking body through libcurl's read
+//!   callback (`blocking::CurlReader`), for the `curl` crate.
+//! * `tower`: Enables a tower layer (`UploadLayer`) sending a form to a URI
+//!   through an inner HTTP service, so uploads can go through existing
+//!   middleware such as retries, timeouts, or authentication.
+//! * `test-util`: Enables the `test_util` module, which decodes encoded
+//!   bodies back into their parts to write assertions against them.
+#![forbid(unsafe_code)]
+
+#[cfg(feature = "checksum")]
+mod checksum;
 mod client_;
+#[cfg(feature = "deadline")]
+mod deadline;
+mod encoding;
+mod entropy;
 mod error;
+#[cfg(feature = "serde")]
+mod ser;
+#[cfg(feature = "http")]
+mod server_;
+#[cfg(feature = "tower")]
+mod service;
+mod spawner;
+#[cfg(feature = "test-util")]
+pub mod test_util;
+#[cfg(feature = "throttle")]
+mod throttle;
+mod transform;
 
 pub mod client {
     pub use crate::error::Error;
+    #[cfg(feature = "serde")]
+    pub use crate::error::SerializeError;
 
     /// This module contains data structures for building a multipart/form
     /// body to send a server.
     pub mod multipart {
-        pub use crate::client_::{Body, BoundaryGenerator, Form, Part};
+        #[cfg(feature = "checksum")]
+        pub use crate::checksum::{Checksum, ChecksumAlgorithm};
+        #[cfg(feature = "http")]
+        pub use crate::client_::batch::BatchForm;
+        pub use crate::client_::blocking;
+        #[cfg(feature = "fs")]
+        pub use crate::client_::directory::DirectoryOptions;
+        pub use crate::client_::local::{LocalBody, LocalForm};
+        pub use crate::client_::serializer::{DefaultSerializer, HeaderOptions, PartSerializer};
+        #[cfg(feature = "fs")]
+        pub use crate::client_::FilenameNormalization;
+        pub use crate::client_::{
+            channel, Body, BodyChunks, BodyConfig, BodyFactory, BodyReader, BoundaryGenerator,
+            BoundaryGeneratorExt, CurlBoundary, DataStream, DuplicateNames, Form, NameEncoding,
+            NonUtf8Filename, Part, PartWriter, RandomBoundary, Sender, SpooledBody, Subtype,
+            WebKitBoundary,
+        };
+        #[cfg(feature = "gzip")]
+        pub use crate::encoding::ContentEncoding;
+        pub use crate::encoding::TransferEncoding;
+        #[cfg(feature = "rand")]
+        pub use crate::entropy::RandEntropy;
+        pub use crate::entropy::{DefaultEntropy, Entropy, StdEntropy};
+        #[cfg(feature = "tower")]
+        pub use crate::service::{BoxError, UploadLayer, UploadService};
+        pub use crate::transform::Transform;
+    }
+}
+
+#[cfg(feature = "http")]
+pub mod server {
+    pub use crate::error::ParseError as Error;
+
+    /// This module contains data structures for parsing a multipart/form
+    /// body received from a client.
+    pub mod multipart {
+        pub use crate::server_::{Field, Multipart, Part};
     }
 }
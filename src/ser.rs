@@ -0,0 +1,457 @@
+// Copyright 2017 rust-hyper-multipart-rfc7578 Developers
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+//
+
+use serde::ser::{self, Impossible, Serialize};
+
+use crate::{client_::Form, error::SerializeError};
+
+/// Serializes a struct or map into text parts of a form.
+///
+/// Each field becomes a text part named after the field. `None` and unit
+/// values are skipped, and sequences of values are added as several parts
+/// with the same name.
+pub(crate) struct FormSerializer<'a> {
+    pub(crate) form: &'a mut Form,
+}
+
+/// Serializes the value of a single field into zero or more strings.
+struct ValueSerializer;
+
+/// Collects the values of a sequence.
+struct SeqSerializer(Vec<String>);
+
+/// Adds the fields of a struct or map to the form.
+pub(crate) struct FieldSerializer<'a> {
+    form: &'a mut Form,
+    key: Option<String>,
+}
+
+impl FieldSerializer<'_> {
+    fn add<T>(&mut self, name: &str, value: &T) -> Result<(), SerializeError>
+    where
+        T: ?Sized + Serialize,
+    {
+        for text in value.serialize(ValueSerializer)? {
+            self.form.add_text(name, text);
+        }
+
+        Ok(())
+    }
+}
+
+/// The error message for values that can't be the top level of a form.
+const NOT_A_FORM: &str = "a form must be a struct or map";
+
+macro_rules! unsupported {
+    ($what:expr => $($method:ident($($ty:ty),*);)*) => {
+        $(
+            fn $method(self, $(_: $ty),*) -> Result<Self::Ok, Self::Error> {
+                Err(SerializeError::Unsupported($what))
+            }
+        )*
+    };
+}
+
+impl<'a> ser::Serializer for FormSerializer<'a> {
+    type Ok = ();
+    type Error = SerializeError;
+
+    type SerializeSeq = Impossible<(), SerializeError>;
+    type SerializeTuple = Impossible<(), SerializeError>;
+    type SerializeTupleStruct = Impossible<(), SerializeError>;
+    type SerializeTupleVariant = Impossible<(), SerializeError>;
+    type SerializeMap = FieldSerializer<'a>;
+    type SerializeStruct = FieldSerializer<'a>;
+    type SerializeStructVariant = Impossible<(), SerializeError>;
+
+    unsupported! { NOT_A_FORM =>
+        serialize_bool(bool);
+        serialize_i8(i8);
+        serialize_i16(i16);
+        serialize_i32(i32);
+        serialize_i64(i64);
+        serialize_u8(u8);
+        serialize_u16(u16);
+        serialize_u32(u32);
+        serialize_u64(u64);
+        serialize_f32(f32);
+        serialize_f64(f64);
+        serialize_char(char);
+        serialize_str(&str);
+        serialize_bytes(&[u8]);
+        serialize_none();
+        serialize_unit();
+        serialize_unit_struct(&'static str);
+        serialize_unit_variant(&'static str, u32, &'static str);
+    }
+
+    fn serialize_some<T>(self, value: &T) -> Result<(), SerializeError>
+    where
+        T: ?Sized + Serialize,
+    {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_struct<T>(self, _: &'static str, value: &T) -> Result<(), SerializeError>
+    where
+        T: ?Sized + Serialize,
+    {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T>(
+        self,
+        _: &'static str,
+        _: u32,
+        _: &'static str,
+        _: &T,
+    ) -> Result<(), SerializeError>
+    where
+        T: ?Sized + Serialize,
+    {
+        Err(SerializeError::Unsupported(NOT_A_FORM))
+    }
+
+    fn serialize_seq(self, _: Option<usize>) -> Result<Self::SerializeSeq, SerializeError> {
+        Err(SerializeError::Unsupported(NOT_A_FORM))
+    }
+
+    fn serialize_tuple(self, _: usize) -> Result<Self::SerializeTuple, SerializeError> {
+        Err(SerializeError::Unsupported(NOT_A_FORM))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _: &'static str,
+        _: usize,
+    ) -> Result<Self::SerializeTupleStruct, SerializeError> {
+        Err(SerializeError::Unsupported(NOT_A_FORM))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _: &'static str,
+        _: u32,
+        _: &'static str,
+        _: usize,
+    ) -> Result<Self::SerializeTupleVariant, SerializeError> {
+        Err(SerializeError::Unsupported(NOT_A_FORM))
+    }
+
+    fn serialize_map(self, _: Option<usize>) -> Result<Self::SerializeMap, SerializeError> {
+        Ok(FieldSerializer {
+            form: self.form,
+            key: None,
+        })
+    }
+
+    fn serialize_struct(
+        self,
+        _: &'static str,
+        _: usize,
+    ) -> Result<Self::SerializeStruct, SerializeError> {
+        Ok(FieldSerializer {
+            form: self.form,
+            key: None,
+        })
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _: &'static str,
+        _: u32,
+        _: &'static str,
+        _: usize,
+    ) -> Result<Self::SerializeStructVariant, SerializeError> {
+        Err(SerializeError::Unsupported(NOT_A_FORM))
+    }
+}
+
+impl ser::SerializeStruct for FieldSerializer<'_> {
+    type Ok = ();
+    type Error = SerializeError;
+
+    fn serialize_field<T>(&mut self, key: &'static str, value: &T) -> Result<(), SerializeError>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.add(key, value)
+    }
+
+    fn end(self) -> Result<(), SerializeError> {
+        Ok(())
+    }
+}
+
+impl ser::SerializeMap for FieldSerializer<'_> {
+    type Ok = ();
+    type Error = SerializeError;
+
+    fn serialize_key<T>(&mut self, key: &T) -> Result<(), SerializeError>
+    where
+        T: ?Sized + Serialize,
+    {
+        let mut key = key.serialize(ValueSerializer)?;
+
+        if key.len() != 1 {
+            return Err(SerializeError::Unsupported(
+                "a field name must be a single value",
+            ));
+        }
+
+        self.key = key.pop();
+
+        Ok(())
+    }
+
+    fn serialize_value<T>(&mut self, value: &T) -> Result<(), SerializeError>
+    where
+        T: ?Sized + Serialize,
+    {
+        let key = self
+            .key
+            .take()
+            .expect("serialize_value called before serialize_key");
+
+        self.add(&key, value)
+    }
+
+    fn end(self) -> Result<(), SerializeError> {
+        Ok(())
+    }
+}
+
+impl ser::Serializer for ValueSerializer {
+    type Ok = Vec<String>;
+    type Error = SerializeError;
+
+    type SerializeSeq = SeqSerializer;
+    type SerializeTuple = SeqSerializer;
+    type SerializeTupleStruct = SeqSerializer;
+    type SerializeTupleVariant = Impossible<Vec<String>, SerializeError>;
+    type SerializeMap = Impossible<Vec<String>, SerializeError>;
+    type SerializeStruct = Impossible<Vec<String>, SerializeError>;
+    type SerializeStructVariant = Impossible<Vec<String>, SerializeError>;
+
+    fn serialize_bool(self, v: bool) -> Result<Vec<String>, SerializeError> {
+        Ok(vec![v.to_string()])
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<Vec<String>, SerializeError> {
+        Ok(vec![v.to_string()])
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<Vec<String>, SerializeError> {
+        Ok(vec![v.to_string()])
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<Vec<String>, SerializeError> {
+        Ok(vec![v.to_string()])
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<Vec<String>, SerializeError> {
+        Ok(vec![v.to_string()])
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<Vec<String>, SerializeError> {
+        Ok(vec![v.to_string()])
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<Vec<String>, SerializeError> {
+        Ok(vec![v.to_string()])
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<Vec<String>, SerializeError> {
+        Ok(vec![v.to_string()])
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<Vec<String>, SerializeError> {
+        Ok(vec![v.to_string()])
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<Vec<String>, SerializeError> {
+        Ok(vec![v.to_string()])
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<Vec<String>, SerializeError> {
+        Ok(vec![v.to_string()])
+    }
+
+    fn serialize_char(self, v: char) -> Result<Vec<String>, SerializeError> {
+        Ok(vec![v.to_string()])
+    }
+
+    fn serialize_str(self, v: &str) -> Result<Vec<String>, SerializeError> {
+        Ok(vec![v.to_string()])
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<Vec<String>, SerializeError> {
+        String::from_utf8(v.to_vec())
+            .map(|text| vec![text])
+            .map_err(|_| SerializeError::Unsupported("bytes must be valid UTF-8"))
+    }
+
+    fn serialize_none(self) -> Result<Vec<String>, SerializeError> {
+        Ok(vec![])
+    }
+
+    fn serialize_some<T>(self, value: &T) -> Result<Vec<String>, SerializeError>
+    where
+        T: ?Sized + Serialize,
+    {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<Vec<String>, SerializeError> {
+        Ok(vec![])
+    }
+
+    fn serialize_unit_struct(self, _: &'static str) -> Result<Vec<String>, SerializeError> {
+        Ok(vec![])
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _: &'static str,
+        _: u32,
+        variant: &'static str,
+    ) -> Result<Vec<String>, SerializeError> {
+        Ok(vec![variant.to_string()])
+    }
+
+    fn serialize_newtype_struct<T>(
+        self,
+        _: &'static str,
+        value: &T,
+    ) -> Result<Vec<String>, SerializeError>
+    where
+        T: ?Sized + Serialize,
+    {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T>(
+        self,
+        _: &'static str,
+        _: u32,
+        _: &'static str,
+        _: &T,
+    ) -> Result<Vec<String>, SerializeError>
+    where
+        T: ?Sized + Serialize,
+    {
+        Err(SerializeError::Unsupported("enum variants with data"))
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<SeqSerializer, SerializeError> {
+        Ok(SeqSerializer(Vec::with_capacity(len.unwrap_or(0))))
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<SeqSerializer, SerializeError> {
+        Ok(SeqSerializer(Vec::with_capacity(len)))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _: &'static str,
+        len: usize,
+    ) -> Result<SeqSerializer, SerializeError> {
+        Ok(SeqSerializer(Vec::with_capacity(len)))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _: &'static str,
+        _: u32,
+        _: &'static str,
+        _: usize,
+    ) -> Result<Self::SerializeTupleVariant, SerializeError> {
+        Err(SerializeError::Unsupported("enum variants with data"))
+    }
+
+    fn serialize_map(self, _: Option<usize>) -> Result<Self::SerializeMap, SerializeError> {
+        Err(SerializeError::Unsupported("nested maps"))
+    }
+
+    fn serialize_struct(
+        self,
+        _: &'static str,
+        _: usize,
+    ) -> Result<Self::SerializeStruct, SerializeError> {
+        Err(SerializeError::Unsupported("nested structs"))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _: &'static str,
+        _: u32,
+        _: &'static str,
+        _: usize,
+    ) -> Result<Self::SerializeStructVariant, SerializeError> {
+        Err(SerializeError::Unsupported("enum variants with data"))
+    }
+}
+
+impl SeqSerializer {
+    fn push<T>(&mut self, value: &T) -> Result<(), SerializeError>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.0.extend(value.serialize(ValueSerializer)?);
+
+        Ok(())
+    }
+}
+
+impl ser::SerializeSeq for SeqSerializer {
+    type Ok = Vec<String>;
+    type Error = SerializeError;
+
+    fn serialize_element<T>(&mut self, value: &T) -> Result<(), SerializeError>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Vec<String>, SerializeError> {
+        Ok(self.0)
+    }
+}
+
+impl ser::SerializeTuple for SeqSerializer {
+    type Ok = Vec<String>;
+    type Error = SerializeError;
+
+    fn serialize_element<T>(&mut self, value: &T) -> Result<(), SerializeError>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Vec<String>, SerializeError> {
+        Ok(self.0)
+    }
+}
+
+impl ser::SerializeTupleStruct for SeqSerializer {
+    type Ok = Vec<String>;
+    type Error = SerializeError;
+
+    fn serialize_field<T>(&mut self, value: &T) -> Result<(), SerializeError>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Vec<String>, SerializeError> {
+        Ok(self.0)
+    }
+}
@@ -0,0 +1,678 @@
+// Copyright 2017 rust-hyper-multipart-rfc7578 Developers
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+//
+
+use std::{
+    error::Error as StdError,
+    pin::Pin,
+    str::FromStr,
+    task::{Context, Poll},
+};
+
+use bytes::{Buf, Bytes, BytesMut};
+use futures::{future, stream::Stream, StreamExt};
+use http::{
+    header::{HeaderMap, HeaderName, HeaderValue, CONTENT_DISPOSITION, CONTENT_TYPE},
+    Request,
+};
+use mime::{self, Mime};
+
+use crate::error::ParseError;
+
+/// The maximum size of the header block of a single part.
+const MAX_HEADERS_SIZE: usize = 8 * 1024;
+
+/// Finds the first occurrence of `needle` in `haystack`.
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
+
+/// Parses a multipart/form-data body.
+///
+/// The body can be any [`http_body::Body`], for example
+/// `hyper::body::Incoming`.
+///
+/// [See](https://tools.ietf.org/html/rfc7578#section-4).
+pub struct Multipart<B> {
+    body: B,
+
+    /// Bytes read from the body that have not been parsed yet.
+    buf: BytesMut,
+
+    /// The delimiter separating parts, including the leading CRLF.
+    ///
+    /// [See](https://tools.ietf.org/html/rfc2046#section-5.1.1).
+    delimiter: Vec<u8>,
+
+    state: State,
+
+    /// Whether the body has been read to the end.
+    eof: bool,
+
+    /// An optional limit on the size of a part buffered by
+    /// [`Multipart::next_part`].
+    max_part_size: Option<u64>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum State {
+    /// Searching for the first boundary, discarding anything before it.
+    Preamble,
+
+    /// A boundary was read, and is followed by either a part or the end
+    /// of the body.
+    Boundary,
+
+    /// Reading the content of a part.
+    Data,
+
+    /// The final boundary was read.
+    End,
+}
+
+impl<B> Multipart<B>
+where
+    B: http_body::Body + Unpin,
+    B::Error: Into<Box<dyn StdError + Send + Sync>>,
+{
+    /// Creates a parser for a body delimited by `boundary`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bytes::Bytes;
+    /// use http_body_util::Full;
+    /// use hyper_multipart_rfc7578::server::multipart::Multipart;
+    ///
+    /// let body = Full::<Bytes>::from("--abc\r\n\r\nHello World!\r\n--abc--");
+    /// let multipart = Multipart::new(body, "abc");
+    /// ```
+    pub fn new<S>(body: B, boundary: S) -> Multipart<B>
+    where
+        S: AsRef<str>,
+    {
+        let mut delimiter = b"\r\n--".to_vec();
+
+        delimiter.extend_from_slice(boundary.as_ref().as_bytes());
+
+        // The first boundary may be at the very start of the body, in which
+        // case it isn't preceded by a CRLF. Seeding the buffer with a CRLF
+        // means it is found just like every other delimiter.
+        //
+        let mut buf = BytesMut::new();
+
+        buf.extend_from_slice(b"\r\n");
+
+        Multipart {
+            body,
+            buf,
+            delimiter,
+            state: State::Preamble,
+            eof: false,
+            max_part_size: None,
+        }
+    }
+
+    /// Limits the size of the content of a part read with
+    /// [`Multipart::next_part`], in bytes. A larger part fails with
+    /// [`ParseError::PartTooLarge`]. By default, there is no limit.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bytes::Bytes;
+    /// use http_body_util::Full;
+    /// use hyper_multipart_rfc7578::server::multipart::Multipart;
+    ///
+    /// let body = Full::<Bytes>::from("--abc\r\n\r\nHello World!\r\n--abc--");
+    /// let mut multipart = Multipart::new(body, "abc");
+    ///
+    /// multipart.max_part_size(1024 * 1024);
+    /// ```
+    pub fn max_part_size(&mut self, max: u64) {
+        self.max_part_size = Some(max);
+    }
+
+    /// Creates a parser from a request, reading the boundary from its
+    /// Content-Type header.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bytes::Bytes;
+    /// use http_body_util::Full;
+    /// use hyper::Request;
+    /// use hyper_multipart_rfc7578::server::multipart::Multipart;
+    ///
+    /// let req = Request::post("http://localhost/upload")
+    ///     .header("content-type", "multipart/form-data; boundary=abc")
+    ///     .body(Full::<Bytes>::from("--abc\r\n\r\nHello World!\r\n--abc--"))
+    ///     .unwrap();
+    ///
+    /// let multipart = Multipart::from_request(req).expect("a multipart request");
+    /// ```
+    pub fn from_request(req: Request<B>) -> Result<Multipart<B>, ParseError> {
+        let boundary = {
+            let content_type = req
+                .headers()
+                .get(CONTENT_TYPE)
+                .and_then(|value| value.to_str().ok())
+                .and_then(|value| Mime::from_str(value).ok())
+                .ok_or(ParseError::InvalidContentType)?;
+
+            if content_type.type_() != mime::MULTIPART {
+                return Err(ParseError::InvalidContentType);
+            }
+
+            content_type
+                .get_param(mime::BOUNDARY)
+                .ok_or(ParseError::InvalidContentType)?
+                .as_str()
+                .to_string()
+        };
+
+        Ok(Multipart::new(req.into_body(), boundary))
+    }
+
+    /// Reads the next part, buffering its content in memory. Returns `None`
+    /// once the final boundary is reached.
+    ///
+    /// The whole content of the part is held in memory, so a client could
+    /// exhaust it with a single large part. For untrusted input, set a
+    /// limit with [`Multipart::max_part_size`], or stream the content with
+    /// [`Multipart::next_field`] instead.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bytes::Bytes;
+    /// use http_body_util::Full;
+    /// use hyper_multipart_rfc7578::server::multipart::Multipart;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let body = Full::<Bytes>::from(
+    ///     "--abc\r\nContent-Disposition: form-data; name=\"text\"\r\n\r\nHello World!\r\n--abc--",
+    /// );
+    /// let mut multipart = Multipart::new(body, "abc");
+    ///
+    /// while let Some(part) = multipart.next_part().await? {
+    ///     assert_eq!(part.name(), Some("text"));
+    ///     assert_eq!(&part.data()[..], b"Hello World!");
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn next_part(&mut self) -> Result<Option<Part>, ParseError> {
+        let headers = match future::poll_fn(|cx| self.poll_headers(cx)).await? {
+            Some(headers) => PartHeaders::new(headers),
+            None => return Ok(None),
+        };
+
+        let mut data = BytesMut::new();
+
+        while let Some(chunk) = future::poll_fn(|cx| self.poll_data(cx)).await {
+            let chunk = chunk?;
+
+            if let Some(max) = self.max_part_size {
+                if (data.len() + chunk.len()) as u64 > max {
+                    return Err(ParseError::PartTooLarge { max });
+                }
+            }
+
+            data.extend_from_slice(&chunk);
+        }
+
+        Ok(Some(Part {
+            headers,
+            data: data.freeze(),
+        }))
+    }
+
+    /// Reads the headers of the next field, returning a stream over its
+    /// content. Returns `None` once the final boundary is reached.
+    ///
+    /// Fields are streamed directly from the body, so large uploads can be
+    /// processed without buffering them in memory.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bytes::Bytes;
+    /// use futures::TryStreamExt;
+    /// use http_body_util::Full;
+    /// use hyper_multipart_rfc7578::server::multipart::Multipart;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let body = Full::<Bytes>::from(
+    ///     "--abc\r\nContent-Disposition: form-data; name=\"file\"; filename=\"a.txt\"\r\n\r\nHello World!\r\n--abc--",
+    /// );
+    /// let mut multipart = Multipart::new(body, "abc");
+    ///
+    /// while let Some(mut field) = multipart.next_field().await? {
+    ///     assert_eq!(field.filename(), Some("a.txt"));
+    ///
+    ///     while let Some(chunk) = field.try_next().await? {
+    ///         println!("read {} bytes", chunk.len());
+    ///     }
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn next_field(&mut self) -> Result<Option<Field<'_, B>>, ParseError> {
+        match future::poll_fn(|cx| self.poll_headers(cx)).await? {
+            Some(headers) => Ok(Some(Field {
+                multipart: self,
+                headers: PartHeaders::new(headers),
+            })),
+            None => Ok(None),
+        }
+    }
+
+    /// Reads the next frame of the body into the buffer.
+    fn poll_fill(&mut self, cx: &mut Context) -> Poll<Result<(), ParseError>> {
+        while !self.eof {
+            match Pin::new(&mut self.body).poll_frame(cx) {
+                Poll::Ready(Some(Ok(frame))) => {
+                    if let Ok(mut data) = frame.into_data() {
+                        while data.has_remaining() {
+                            let chunk = data.chunk();
+                            let len = chunk.len();
+
+                            self.buf.extend_from_slice(chunk);
+                            data.advance(len);
+                        }
+
+                        return Poll::Ready(Ok(()));
+                    }
+                }
+                Poll::Ready(Some(Err(e))) => return Poll::Ready(Err(ParseError::Body(e.into()))),
+                Poll::Ready(None) => self.eof = true,
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+
+        Poll::Ready(Err(ParseError::UnexpectedEof))
+    }
+
+    /// Skips the remaining content of the current part, and reads the
+    /// headers of the next part. Returns `None` if there are no parts left.
+    fn poll_headers(&mut self, cx: &mut Context) -> Poll<Result<Option<HeaderMap>, ParseError>> {
+        loop {
+            match self.state {
+                State::Preamble => match find(&self.buf, &self.delimiter) {
+                    Some(i) => {
+                        self.buf.advance(i + self.delimiter.len());
+                        self.state = State::Boundary;
+                    }
+                    None => {
+                        // Keep enough bytes to match a delimiter that is split
+                        // across frames.
+                        //
+                        let keep = self.delimiter.len() - 1;
+
+                        if self.buf.len() > keep {
+                            self.buf.advance(self.buf.len() - keep);
+                        }
+
+                        futures::ready!(self.poll_fill(cx))?;
+                    }
+                },
+                State::Boundary => {
+                    if self.buf.len() < 2 {
+                        futures::ready!(self.poll_fill(cx))?;
+
+                        continue;
+                    }
+
+                    if self.buf.starts_with(b"--") {
+                        self.state = State::End;
+
+                        continue;
+                    }
+
+                    // The boundary line can be followed by transport padding
+                    // before the CRLF.
+                    //
+                    // [See](https://tools.ietf.org/html/rfc2046#section-5.1.1).
+                    //
+                    let line_end = match find(&self.buf, b"\r\n") {
+                        Some(i) => i,
+                        None if self.buf.len() > MAX_HEADERS_SIZE => {
+                            return Poll::Ready(Err(ParseError::InvalidBoundary))
+                        }
+                        None => {
+                            futures::ready!(self.poll_fill(cx))?;
+
+                            continue;
+                        }
+                    };
+
+                    if !self.buf[..line_end]
+                        .iter()
+                        .all(|b| *b == b' ' || *b == b'\t')
+                    {
+                        return Poll::Ready(Err(ParseError::InvalidBoundary));
+                    }
+
+                    // The header block ends with an empty line. If the part
+                    // has no headers, the empty line follows immediately.
+                    //
+                    let headers_end = if self.buf[line_end + 2..].starts_with(b"\r\n") {
+                        Some(line_end + 2)
+                    } else {
+                        find(&self.buf[line_end + 2..], b"\r\n\r\n").map(|i| line_end + 4 + i)
+                    };
+
+                    match headers_end {
+                        Some(i) => {
+                            let headers = parse_headers(&self.buf[line_end + 2..i])?;
+
+                            self.buf.advance(i + 2);
+                            self.state = State::Data;
+
+                            return Poll::Ready(Ok(Some(headers)));
+                        }
+                        None if self.buf.len() > MAX_HEADERS_SIZE => {
+                            return Poll::Ready(Err(ParseError::HeadersTooLarge))
+                        }
+                        None => futures::ready!(self.poll_fill(cx))?,
+                    }
+                }
+                State::Data => while futures::ready!(self.poll_data(cx)).transpose()?.is_some() {},
+                State::End => return Poll::Ready(Ok(None)),
+            }
+        }
+    }
+
+    /// Reads the next chunk of content of the current part. Returns `None`
+    /// once the delimiter ending the part is reached.
+    fn poll_data(&mut self, cx: &mut Context) -> Poll<Option<Result<Bytes, ParseError>>> {
+        loop {
+            if self.state != State::Data {
+                return Poll::Ready(None);
+            }
+
+            match find(&self.buf, &self.delimiter) {
+                Some(0) => {
+                    self.buf.advance(self.delimiter.len());
+                    self.state = State::Boundary;
+                }
+                Some(i) => return Poll::Ready(Some(Ok(self.buf.split_to(i).freeze()))),
+                None => {
+                    // Everything except a possible partial delimiter at the end
+                    // of the buffer is content.
+                    //
+                    let keep = self.delimiter.len() - 1;
+
+                    if self.buf.len() > keep {
+                        let len = self.buf.len() - keep;
+
+                        return Poll::Ready(Some(Ok(self.buf.split_to(len).freeze())));
+                    }
+
+                    if let Err(e) = futures::ready!(self.poll_fill(cx)) {
+                        return Poll::Ready(Some(Err(e)));
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Parses a block of header lines.
+fn parse_headers(block: &[u8]) -> Result<HeaderMap, ParseError> {
+    let mut headers = HeaderMap::new();
+
+    for line in block.split(|b| *b == b'\n') {
+        let line = line.strip_suffix(b"\r").unwrap_or(line);
+
+        if line.is_empty() {
+            continue;
+        }
+
+        let colon = line
+            .iter()
+            .position(|b| *b == b':')
+            .ok_or(ParseError::InvalidHeader)?;
+        let name = HeaderName::from_bytes(&line[..colon]).map_err(|_| ParseError::InvalidHeader)?;
+        let value = HeaderValue::from_bytes(line[colon + 1..].trim_ascii())
+            .map_err(|_| ParseError::InvalidHeader)?;
+
+        headers.append(name, value);
+    }
+
+    Ok(headers)
+}
+
+/// Parses the parameters of a Content-Disposition header value, returning
+/// pairs of lowercased parameter names and unquoted values.
+fn parse_disposition_params(value: &str) -> Vec<(String, String)> {
+    let mut params = vec![];
+    let mut rest = match value.find(';') {
+        Some(i) => &value[i + 1..],
+        None => return params,
+    };
+
+    loop {
+        rest = rest.trim_start_matches([' ', '\t', ';']);
+
+        let eq = match rest.find('=') {
+            Some(i) => i,
+            None => break,
+        };
+        let name = rest[..eq].trim().to_ascii_lowercase();
+
+        rest = rest[eq + 1..].trim_start();
+
+        let mut value = String::new();
+
+        if let Some(quoted) = rest.strip_prefix('"') {
+            let mut chars = quoted.char_indices();
+
+            rest = "";
+
+            while let Some((i, c)) = chars.next() {
+                match c {
+                    '\\' => value.extend(chars.next().map(|(_, c)| c)),
+                    '"' => {
+                        rest = &quoted[i + 1..];
+
+                        break;
+                    }
+                    c => value.push(c),
+                }
+            }
+        } else {
+            let end = rest.find(';').unwrap_or(rest.len());
+
+            value.push_str(rest[..end].trim_end());
+            rest = &rest[end..];
+        }
+
+        params.push((name, value));
+    }
+
+    params
+}
+
+/// The headers of a part, and the values derived from them.
+#[derive(Debug)]
+struct PartHeaders {
+    headers: HeaderMap,
+
+    /// The `name` parameter of the Content-Disposition header.
+    name: Option<String>,
+
+    /// The `filename` parameter of the Content-Disposition header.
+    filename: Option<String>,
+}
+
+impl PartHeaders {
+    fn new(headers: HeaderMap) -> PartHeaders {
+        let mut name = None;
+        let mut filename = None;
+
+        let params = headers
+            .get(CONTENT_DISPOSITION)
+            .and_then(|value| value.to_str().ok())
+            .map(parse_disposition_params)
+            .unwrap_or_default();
+
+        for (param, value) in params {
+            match &param[..] {
+                "name" => name = Some(value),
+                "filename" => filename = Some(value),
+                _ => (),
+            }
+        }
+
+        PartHeaders {
+            headers,
+            name,
+            filename,
+        }
+    }
+
+    fn content_type(&self) -> Mime {
+        self.headers
+            .get(CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| Mime::from_str(value).ok())
+            .unwrap_or(mime::TEXT_PLAIN)
+    }
+}
+
+/// A part of a multipart body whose content is streamed as it is read.
+///
+/// The content is read with the `Stream` implementation. Dropping the field
+/// before it is read to the end is allowed; the remaining content is skipped
+/// when the next field is requested.
+pub struct Field<'a, B> {
+    multipart: &'a mut Multipart<B>,
+
+    headers: PartHeaders,
+}
+
+impl<'a, B> Field<'a, B>
+where
+    B: http_body::Body + Unpin,
+    B::Error: Into<Box<dyn StdError + Send + Sync>>,
+{
+    /// Returns the name of the form field.
+    ///
+    /// [See](https://tools.ietf.org/html/rfc7578#section-4.2).
+    #[inline]
+    pub fn name(&self) -> Option<&str> {
+        self.headers.name.as_deref()
+    }
+
+    /// Returns the filename, if the field contains the content of a file.
+    ///
+    /// [See](https://tools.ietf.org/html/rfc7578#section-4.2).
+    #[inline]
+    pub fn filename(&self) -> Option<&str> {
+        self.headers.filename.as_deref()
+    }
+
+    /// Returns the Content-Type of the field. If it isn't specified, this
+    /// defaults to "text/plain".
+    ///
+    /// [See](https://tools.ietf.org/html/rfc7578#section-4.4).
+    #[inline]
+    pub fn content_type(&self) -> Mime {
+        self.headers.content_type()
+    }
+
+    /// Returns all headers of the field.
+    #[inline]
+    pub fn headers(&self) -> &HeaderMap {
+        &self.headers.headers
+    }
+
+    /// Reads the remaining content of the field into memory.
+    pub async fn bytes(mut self) -> Result<Bytes, ParseError> {
+        let mut data = BytesMut::new();
+
+        while let Some(chunk) = self.next().await {
+            data.extend_from_slice(&chunk?);
+        }
+
+        Ok(data.freeze())
+    }
+}
+
+impl<'a, B> Stream for Field<'a, B>
+where
+    B: http_body::Body + Unpin,
+    B::Error: Into<Box<dyn StdError + Send + Sync>>,
+{
+    type Item = Result<Bytes, ParseError>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Self::Item>> {
+        self.multipart.poll_data(cx)
+    }
+}
+
+/// One part of a parsed multipart/form-data body, with its content
+/// buffered in memory.
+///
+/// [See](https://tools.ietf.org/html/rfc7578#section-4).
+#[derive(Debug)]
+pub struct Part {
+    headers: PartHeaders,
+
+    data: Bytes,
+}
+
+impl Part {
+    /// Returns the name of the form field this part belongs to.
+    ///
+    /// [See](https://tools.ietf.org/html/rfc7578#section-4.2).
+    #[inline]
+    pub fn name(&self) -> Option<&str> {
+        self.headers.name.as_deref()
+    }
+
+    /// Returns the filename, if the part contains the content of a file.
+    ///
+    /// [See](https://tools.ietf.org/html/rfc7578#section-4.2).
+    #[inline]
+    pub fn filename(&self) -> Option<&str> {
+        self.headers.filename.as_deref()
+    }
+
+    /// Returns the Content-Type of the part. If it isn't specified, this
+    /// defaults to "text/plain".
+    ///
+    /// [See](https://tools.ietf.org/html/rfc7578#section-4.4).
+    #[inline]
+    pub fn content_type(&self) -> Mime {
+        self.headers.content_type()
+    }
+
+    /// Returns all headers of the part.
+    #[inline]
+    pub fn headers(&self) -> &HeaderMap {
+        &self.headers.headers
+    }
+
+    /// Returns the content of the part.
+    #[inline]
+    pub fn data(&self) -> &Bytes {
+        &self.data
+    }
+
+    /// Consumes the part, returning its content.
+    #[inline]
+    pub fn into_data(self) -> Bytes {
+        self.data
+    }
+}
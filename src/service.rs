@@ -0,0 +1,208 @@
+// Copyright 2017 rust-hyper-multipart-rfc7578 Developers
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+//
+
+use std::{
+    error::Error as StdError,
+    fmt,
+    task::{Context, Poll},
+};
+
+use futures::{
+    future::{self, Either, ErrInto, Ready},
+    TryFutureExt,
+};
+use http::{Request, Uri};
+use http_body_util::StreamBody;
+use tower_layer::Layer;
+use tower_service::Service;
+
+use crate::client_::{Body, Form};
+
+/// The error returned by an [`UploadService`]: either the request couldn't
+/// be built, or the inner service failed.
+pub type BoxError = Box<dyn StdError + Send + Sync>;
+
+/// A layer wrapping an HTTP service into an [`UploadService`].
+///
+/// The requests sent to the inner service have a [`StreamBody`] over the
+/// multipart [`Body`], unless another conversion is set with
+/// [`UploadLayer::with_convert`].
+///
+/// # Examples
+///
+/// ```
+/// use hyper_multipart_rfc7578::client::multipart;
+/// use hyper_util::{client::legacy::Client, rt::TokioExecutor};
+/// use tower::{ServiceBuilder, ServiceExt};
+///
+/// # async fn run() -> Result<(), multipart::BoxError> {
+/// let client = Client::builder(TokioExecutor::new()).build_http();
+/// let service = ServiceBuilder::new()
+///     .layer(multipart::UploadLayer::new())
+///     .service(client);
+/// let mut form = multipart::Form::default();
+///
+/// form.add_text("text", "Hello World!");
+///
+/// let res = service
+///     .oneshot(("http://localhost/upload".parse()?, form))
+///     .await?;
+///
+/// println!("{}", res.status());
+/// # Ok(())
+/// # }
+/// ```
+pub struct UploadLayer<B = StreamBody<Body>> {
+    convert: fn(Body) -> B,
+}
+
+impl UploadLayer {
+    /// Creates a new layer.
+    #[inline]
+    pub fn new() -> UploadLayer {
+        UploadLayer::with_convert(StreamBody::new)
+    }
+}
+
+impl<B> UploadLayer<B> {
+    /// Creates a new layer, converting the multipart body into the body
+    /// type of the inner service with `convert`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hyper_multipart_rfc7578::client::multipart;
+    ///
+    /// struct MyBody(multipart::Body);
+    ///
+    /// let layer = multipart::UploadLayer::with_convert(MyBody);
+    /// ```
+    #[inline]
+    pub fn with_convert(convert: fn(Body) -> B) -> UploadLayer<B> {
+        UploadLayer { convert }
+    }
+}
+
+impl Default for UploadLayer {
+    #[inline]
+    fn default() -> UploadLayer {
+        UploadLayer::new()
+    }
+}
+
+impl<B> Clone for UploadLayer<B> {
+    #[inline]
+    fn clone(&self) -> UploadLayer<B> {
+        *self
+    }
+}
+
+impl<B> Copy for UploadLayer<B> {}
+
+impl<B> fmt::Debug for UploadLayer<B> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("UploadLayer")
+    }
+}
+
+impl<S, B> Layer<S> for UploadLayer<B> {
+    type Service = UploadService<S, B>;
+
+    #[inline]
+    fn layer(&self, inner: S) -> UploadService<S, B> {
+        UploadService {
+            inner,
+            convert: self.convert,
+        }
+    }
+}
+
+/// A service taking a `(Uri, Form)` pair, which sends the form as a POST
+/// request to the URI through an inner HTTP service.
+///
+/// This lets multipart uploads go through existing tower middleware, such
+/// as retries, timeouts, or authentication.
+pub struct UploadService<S, B = StreamBody<Body>> {
+    inner: S,
+
+    convert: fn(Body) -> B,
+}
+
+impl<S> UploadService<S> {
+    /// Wraps an HTTP service.
+    #[inline]
+    pub fn new(inner: S) -> UploadService<S> {
+        UploadLayer::new().layer(inner)
+    }
+}
+
+impl<S, B> UploadService<S, B> {
+    /// Returns a reference to the inner service.
+    #[inline]
+    pub fn get_ref(&self) -> &S {
+        &self.inner
+    }
+
+    /// Returns a mutable reference to the inner service.
+    #[inline]
+    pub fn get_mut(&mut self) -> &mut S {
+        &mut self.inner
+    }
+
+    /// Returns the inner service.
+    #[inline]
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+}
+
+impl<S, B> Clone for UploadService<S, B>
+where
+    S: Clone,
+{
+    #[inline]
+    fn clone(&self) -> UploadService<S, B> {
+        UploadService {
+            inner: self.inner.clone(),
+            convert: self.convert,
+        }
+    }
+}
+
+impl<S, B> fmt::Debug for UploadService<S, B>
+where
+    S: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("UploadService")
+            .field("inner", &self.inner)
+            .finish()
+    }
+}
+
+impl<S, B> Service<(Uri, Form)> for UploadService<S, B>
+where
+    S: Service<Request<B>>,
+    S::Error: Into<BoxError>,
+{
+    type Response = S::Response;
+    type Error = BoxError;
+    type Future = Either<Ready<Result<S::Response, BoxError>>, ErrInto<S::Future, BoxError>>;
+
+    #[inline]
+    fn poll_ready(&mut self, ctx: &mut Context<'_>) -> Poll<Result<(), BoxError>> {
+        self.inner.poll_ready(ctx).map_err(Into::into)
+    }
+
+    fn call(&mut self, (uri, form): (Uri, Form)) -> Self::Future {
+        match form.set_body_convert::<Body>(Request::post(uri)) {
+            Ok(req) => Either::Right(self.inner.call(req.map(self.convert)).err_into()),
+            Err(e) => Either::Left(future::err(e.into())),
+        }
+    }
+}
@@ -0,0 +1,133 @@
+// Copyright 2017 rust-hyper-multipart-rfc7578 Developers
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+//
+
+use std::{
+    fmt,
+    io::{self, Read},
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+};
+
+use bytes::{Buf, Bytes};
+use futures::{channel::oneshot, future::Future, io::AsyncRead};
+
+/// A task that may block.
+type Task = Box<dyn FnOnce() + Send>;
+
+/// A function running a task on a pool of threads that may block.
+#[derive(Clone)]
+pub(crate) struct Spawner(Arc<dyn Fn(Task) + Send + Sync>);
+
+impl Spawner {
+    pub(crate) fn new<F>(spawn: F) -> Spawner
+    where
+        F: 'static + Fn(Task) + Send + Sync,
+    {
+        Spawner(Arc::new(spawn))
+    }
+
+    /// Runs tasks with `tokio::task::spawn_blocking`.
+    #[cfg(feature = "tokio")]
+    pub(crate) fn tokio() -> Spawner {
+        Spawner::new(|task| {
+            tokio::task::spawn_blocking(task);
+        })
+    }
+}
+
+impl fmt::Debug for Spawner {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("Spawner")
+    }
+}
+
+/// The result of a read on the blocking pool, along with the reader.
+type ReadResult = (Box<dyn Read + Send>, io::Result<Vec<u8>>);
+
+/// Turns a blocking reader into an asynchronous reader, by running each
+/// read on a blocking pool.
+pub(crate) struct BlockingReader {
+    /// The reader, while no read is running.
+    read: Option<Box<dyn Read + Send>>,
+
+    /// The result of the running read.
+    pending: Option<oneshot::Receiver<ReadResult>>,
+
+    /// Data that was read, but didn't fit into the buffer it was read for.
+    buffered: Bytes,
+
+    spawner: Spawner,
+}
+
+impl BlockingReader {
+    pub(crate) fn new(read: Box<dyn Read + Send>, spawner: Spawner) -> BlockingReader {
+        BlockingReader {
+            read: Some(read),
+            pending: None,
+            buffered: Bytes::new(),
+            spawner,
+        }
+    }
+}
+
+impl AsyncRead for BlockingReader {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        ctx: &mut Context,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        if self.buffered.is_empty() && self.pending.is_none() {
+            let mut read = self
+                .read
+                .take()
+                .ok_or_else(|| io::Error::other("blocking read was cancelled"))?;
+            let len = buf.len();
+            let (tx, rx) = oneshot::channel();
+
+            (self.spawner.0)(Box::new(move || {
+                let mut data = vec![0; len];
+                let res = loop {
+                    match read.read(&mut data) {
+                        Err(ref e) if e.kind() == io::ErrorKind::Interrupted => (),
+                        res => break res,
+                    }
+                };
+                let res = res.map(|n| {
+                    data.truncate(n);
+                    data
+                });
+
+                let _ = tx.send((read, res));
+            }));
+
+            self.pending = Some(rx);
+        }
+
+        if let Some(ref mut rx) = self.pending {
+            let res = futures::ready!(Pin::new(rx).poll(ctx));
+
+            self.pending = None;
+
+            let (read, res) = res.map_err(|_| io::Error::other("blocking read was cancelled"))?;
+
+            self.read = Some(read);
+            self.buffered = Bytes::from(res?);
+        }
+
+        // The buffer can be smaller than the one the read was started for,
+        // if it was polled again with a different buffer.
+        //
+        let n = buf.len().min(self.buffered.len());
+
+        buf[..n].copy_from_slice(&self.buffered[..n]);
+        self.buffered.advance(n);
+
+        Poll::Ready(Ok(n))
+    }
+}
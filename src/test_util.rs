@@ -0,0 +1,115 @@
+// Copyright 2017 rust-hyper-multipart-rfc7578 Developers
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+//
+
+use bytes::Bytes;
+use http::{header::CONTENT_TYPE, Request};
+use http_body_util::Full;
+
+use crate::{
+    client_::Form,
+    error::ParseError,
+    server_::{Multipart, Part},
+};
+
+/// Decodes an encoded multipart body back into its parts, reading the
+/// boundary from the value of the Content-Type header.
+///
+/// # Examples
+///
+/// ```
+/// use hyper_multipart_rfc7578::{client::multipart, test_util};
+///
+/// let mut form = multipart::Form::default();
+///
+/// form.add_text("text", "Hello World!");
+///
+/// let (content_type, body) = form.into_bytes().unwrap();
+/// let parts = test_util::decode(&content_type, body).unwrap();
+///
+/// assert_eq!(parts[0].name(), Some("text"));
+/// assert_eq!(&parts[0].data()[..], b"Hello World!");
+/// ```
+pub fn decode<B>(content_type: &str, body: B) -> Result<Vec<Part>, ParseError>
+where
+    B: Into<Bytes>,
+{
+    let req = Request::post("/")
+        .header(CONTENT_TYPE, content_type)
+        .body(Full::new(body.into()))
+        .map_err(|_| ParseError::InvalidContentType)?;
+    let mut multipart = Multipart::from_request(req)?;
+
+    futures::executor::block_on(async {
+        let mut parts = vec![];
+
+        while let Some(part) = multipart.next_part().await? {
+            parts.push(part);
+        }
+
+        Ok(parts)
+    })
+}
+
+/// Encodes a form, and decodes the body back into its parts.
+///
+/// # Panics
+///
+/// Panics if the form can't be encoded, or if the body can't be decoded.
+pub fn decode_form(form: Form) -> Vec<Part> {
+    let (content_type, body) = form.into_bytes().expect("form to be encoded");
+
+    decode(&content_type, body).expect("body to be decoded")
+}
+
+/// Asserts that a form encodes to the expected parts, given as tuples of
+/// the name, filename, Content-Type, and content of each part, in order.
+///
+/// # Panics
+///
+/// Panics if the parts of the encoded form don't match.
+///
+/// # Examples
+///
+/// ```
+/// use hyper_multipart_rfc7578::{client::multipart, test_util};
+///
+/// let mut form = multipart::Form::default();
+///
+/// form.add_text("text", "Hello World!");
+/// form.add_bytes_file("file", &b"a,b,c"[..], "data.csv");
+///
+/// test_util::assert_form(
+///     form,
+///     &[
+///         ("text", None, "text/plain", &b"Hello World!"[..]),
+///         ("file", Some("data.csv"), "application/octet-stream", &b"a,b,c"[..]),
+///     ],
+/// );
+/// ```
+pub fn assert_form(form: Form, expected: &[(&str, Option<&str>, &str, &[u8])]) {
+    let parts = decode_form(form);
+    let actual: Vec<_> = parts
+        .iter()
+        .map(|part| {
+            (
+                part.name().unwrap_or_default(),
+                part.filename(),
+                part.content_type().to_string(),
+                &part.data()[..],
+            )
+        })
+        .collect();
+    let expected: Vec<_> = expected
+        .iter()
+        .map(|&(name, filename, content_type, data)| {
+            (name, filename, content_type.to_string(), data)
+        })
+        .collect();
+
+    assert_eq!(actual, expected, "form parts don't match");
+}
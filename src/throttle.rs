@@ -0,0 +1,86 @@
+// Copyright 2017 rust-hyper-multipart-rfc7578 Developers
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+//
+
+use std::{
+    future::Future,
+    num::NonZeroU64,
+    pin::Pin,
+    task::{Context, Poll},
+    time::Duration,
+};
+
+use tokio::time::{self, Instant, Sleep};
+
+/// A token bucket limiting the number of bytes written per second.
+///
+/// The bucket holds at most one second worth of bytes. Frames are allowed
+/// to overdraw the bucket, after which the body waits until the debt is
+/// paid back.
+pub(crate) struct RateLimit {
+    bytes_per_second: u64,
+
+    /// The number of bytes that can be written without waiting. This is
+    /// negative if more bytes were written than allowed.
+    tokens: f64,
+
+    /// When the bucket was last refilled.
+    last: Instant,
+
+    /// The timer registered while waiting for tokens.
+    sleep: Option<Pin<Box<Sleep>>>,
+}
+
+impl RateLimit {
+    pub(crate) fn new(bytes_per_second: NonZeroU64) -> RateLimit {
+        let bytes_per_second = bytes_per_second.get();
+
+        RateLimit {
+            bytes_per_second,
+            tokens: bytes_per_second as f64,
+            last: Instant::now(),
+            sleep: None,
+        }
+    }
+
+    /// Refills the bucket based on the time passed since the last refill.
+    fn refill(&mut self) {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.last).as_secs_f64();
+        let capacity = self.bytes_per_second as f64;
+
+        self.tokens = (self.tokens + elapsed * capacity).min(capacity);
+        self.last = now;
+    }
+
+    /// Waits until the bucket has tokens available. The waker is woken by a
+    /// timer, rather than by yielding repeatedly.
+    pub(crate) fn poll_ready(&mut self, cx: &mut Context) -> Poll<()> {
+        loop {
+            if let Some(ref mut sleep) = self.sleep {
+                futures::ready!(sleep.as_mut().poll(cx));
+
+                self.sleep = None;
+            }
+
+            self.refill();
+
+            if self.tokens > 0.0 {
+                return Poll::Ready(());
+            }
+
+            let wait = Duration::from_secs_f64(-self.tokens / self.bytes_per_second as f64);
+
+            self.sleep = Some(Box::pin(time::sleep(wait.max(Duration::from_millis(1)))));
+        }
+    }
+
+    /// Takes tokens for bytes that were written.
+    pub(crate) fn consume(&mut self, bytes: usize) {
+        self.tokens -= bytes as f64;
+    }
+}
@@ -0,0 +1,188 @@
+// Copyright 2017 rust-hyper-multipart-rfc7578 Developers
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+//
+
+use std::{
+    fmt,
+    io::{self, Read},
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use bytes::{Buf, Bytes, BytesMut};
+use futures::io::AsyncRead;
+
+/// The size of the chunks read from the content of a part before they are
+/// transformed.
+const CHUNK_SIZE: usize = 8 * 1024;
+
+/// A transform applied to the content of a part, one chunk at a time, while
+/// the part is streamed. This can be used to encrypt, compress, or redact
+/// content.
+///
+/// Any function taking a chunk and returning the transformed chunk is a
+/// transform.
+pub trait Transform: Send {
+    /// Transforms the next chunk of content. The returned chunk can be
+    /// empty, if the transform buffers its input.
+    fn transform(&mut self, chunk: Bytes) -> io::Result<Bytes>;
+
+    /// Returns any data left once the whole content was transformed, such
+    /// as buffered input or an authentication tag.
+    fn finish(&mut self) -> io::Result<Bytes> {
+        Ok(Bytes::new())
+    }
+}
+
+impl<F> Transform for F
+where
+    F: FnMut(Bytes) -> io::Result<Bytes> + Send,
+{
+    #[inline]
+    fn transform(&mut self, chunk: Bytes) -> io::Result<Bytes> {
+        self(chunk)
+    }
+}
+
+/// The transforms of a part, applied in order.
+#[derive(Default)]
+pub(crate) struct Transforms(Vec<Box<dyn Transform>>);
+
+impl Transforms {
+    #[inline]
+    pub(crate) fn push(&mut self, transform: Box<dyn Transform>) {
+        self.0.push(transform);
+    }
+
+    #[inline]
+    pub(crate) fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Passes a chunk through every transform.
+    fn apply(&mut self, chunk: Bytes) -> io::Result<Bytes> {
+        self.0
+            .iter_mut()
+            .try_fold(chunk, |chunk, transform| transform.transform(chunk))
+    }
+
+    /// Finishes every transform. What a transform returns when it finishes
+    /// is passed through the transforms after it, before they finish.
+    fn finish(&mut self) -> io::Result<Bytes> {
+        let mut tail = Bytes::new();
+
+        for transform in &mut self.0 {
+            let mut data = BytesMut::new();
+
+            if !tail.is_empty() {
+                data.extend_from_slice(&transform.transform(tail)?);
+            }
+
+            data.extend_from_slice(&transform.finish()?);
+            tail = data.freeze();
+        }
+
+        Ok(tail)
+    }
+}
+
+impl fmt::Debug for Transforms {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Transforms")
+            .field("len", &self.0.len())
+            .finish()
+    }
+}
+
+/// A reader applying transforms to the content read from another reader.
+pub(crate) struct Transformed<R> {
+    read: R,
+
+    transforms: Transforms,
+
+    /// The buffer content is read into before it is transformed.
+    buf: Vec<u8>,
+
+    /// Transformed content that wasn't read yet.
+    out: Bytes,
+
+    /// Whether the transforms were finished.
+    finished: bool,
+}
+
+impl<R> Transformed<R> {
+    pub(crate) fn new(read: R, transforms: Transforms) -> Transformed<R> {
+        Transformed {
+            read,
+            transforms,
+            buf: vec![0; CHUNK_SIZE],
+            out: Bytes::new(),
+            finished: false,
+        }
+    }
+
+    /// Transforms `n` bytes read into the buffer, or finishes the transforms
+    /// if the reader reached its end.
+    fn feed(&mut self, n: usize) -> io::Result<()> {
+        if n == 0 {
+            self.finished = true;
+            self.out = self.transforms.finish()?;
+        } else {
+            self.out = self
+                .transforms
+                .apply(Bytes::copy_from_slice(&self.buf[..n]))?;
+        }
+
+        Ok(())
+    }
+
+    /// Copies transformed content into `buf`.
+    fn drain(&mut self, buf: &mut [u8]) -> usize {
+        let n = buf.len().min(self.out.len());
+
+        buf[..n].copy_from_slice(&self.out[..n]);
+        self.out.advance(n);
+
+        n
+    }
+}
+
+impl<R> Read for Transformed<R>
+where
+    R: Read,
+{
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.out.is_empty() && !self.finished && !buf.is_empty() {
+            let n = self.read.read(&mut self.buf)?;
+
+            self.feed(n)?;
+        }
+
+        Ok(self.drain(buf))
+    }
+}
+
+impl<R> AsyncRead for Transformed<R>
+where
+    R: AsyncRead + Unpin,
+{
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        ctx: &mut Context,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = &mut *self;
+
+        while this.out.is_empty() && !this.finished && !buf.is_empty() {
+            let n = futures::ready!(Pin::new(&mut this.read).poll_read(ctx, &mut this.buf))?;
+
+            this.feed(n)?;
+        }
+
+        Poll::Ready(Ok(this.drain(buf)))
+    }
+}
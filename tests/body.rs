@@ -0,0 +1,1942 @@
+// Copyright 2017 rust-hyper-multipart-rfc7578 Developers
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+//
+
+use futures::TryStreamExt;
+use hyper_multipart_rfc7578::client::multipart::{self, BoundaryGenerator};
+
+struct TestGenerator;
+
+impl BoundaryGenerator for TestGenerator {
+    fn generate_boundary() -> String {
+        "test".to_string()
+    }
+}
+
+/// Collects a form into the bytes of its body.
+async fn collect(form: multipart::Form) -> Vec<u8> {
+    multipart::Body::from(form)
+        .collect_bytes()
+        .await
+        .unwrap()
+        .to_vec()
+}
+
+#[tokio::test]
+async fn many_empty_parts() {
+    const PARTS: usize = 100_000;
+
+    let mut form = multipart::Form::new::<TestGenerator>();
+
+    for _ in 0..PARTS {
+        form.add_text("empty", "");
+    }
+
+    let body = collect(form).await;
+    let part = "\r\n--test\r\nContent-Type: text/plain\r\nContent-Disposition: form-data; name=\"empty\"\r\n\r\n";
+
+    assert_eq!(body.len(), PARTS * part.len() + "\r\n--test--".len());
+    assert!(body.starts_with(part.as_bytes()));
+    assert!(body.ends_with(b"\r\n--test--"));
+}
+
+#[tokio::test]
+async fn small_parts_share_frames() {
+    let mut form = multipart::Form::new::<TestGenerator>();
+
+    form.add_text("a", "Hello");
+    form.add_text("b", "World");
+
+    let chunks: Vec<_> = multipart::Body::from(form)
+        .into_data_stream()
+        .try_collect()
+        .await
+        .unwrap();
+
+    assert_eq!(chunks.len(), 1);
+    assert_eq!(
+        chunks[0],
+        &b"\r\n--test\r\nContent-Type: text/plain\r\nContent-Disposition: form-data; name=\"a\"\r\n\r\nHello\r\n--test\r\nContent-Type: text/plain\r\nContent-Disposition: form-data; name=\"b\"\r\n\r\nWorld\r\n--test--"[..]
+    );
+}
+
+#[tokio::test]
+async fn small_bytes_share_frames_and_large_bytes_are_not_copied() {
+    let large = bytes::Bytes::from(vec![b'x'; 4096]);
+    let mut form = multipart::Form::new::<TestGenerator>();
+
+    form.add_bytes("a", &b"Hello"[..]);
+    form.add_bytes("b", &b"World"[..]);
+    form.add_bytes("c", large.clone());
+
+    let chunks: Vec<_> = multipart::Body::from(form)
+        .into_data_stream()
+        .try_collect()
+        .await
+        .unwrap();
+
+    assert_eq!(chunks.len(), 3);
+    assert!(chunks[0].ends_with(b"name=\"c\"\r\n\r\n"));
+    assert_eq!(chunks[1].as_ptr(), large.as_ptr());
+    assert_eq!(chunks[2], &b"\r\n--test--"[..]);
+}
+
+#[tokio::test]
+async fn slow_reader_sends_each_read() {
+    /// A reader returning at most two bytes per read, like a pipe.
+    struct Trickle(std::io::Cursor<&'static [u8]>);
+
+    impl std::io::Read for Trickle {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            let len = buf.len().min(2);
+
+            self.0.read(&mut buf[..len])
+        }
+    }
+
+    let mut form = multipart::Form::new::<TestGenerator>();
+
+    form.add_reader("input", Trickle(std::io::Cursor::new(b"Hello")));
+
+    let chunks: Vec<_> = multipart::Body::from(form)
+        .into_data_stream()
+        .try_collect()
+        .await
+        .unwrap();
+
+    assert_eq!(chunks.len(), 4);
+    assert!(chunks[0].ends_with(b"\r\n\r\nHe"));
+    assert_eq!(chunks[1], &b"ll"[..]);
+    assert_eq!(chunks[2], &b"o"[..]);
+    assert_eq!(chunks[3], &b"\r\n--test--"[..]);
+}
+
+#[tokio::test]
+async fn chunks_divide_the_body_in_order() {
+    let form = || {
+        let mut form = multipart::Form::new::<TestGenerator>();
+
+        form.add_text("text", "Hello World!");
+        form.add_reader("data", std::io::Cursor::new("a,b,c\n".repeat(1000)));
+        form
+    };
+
+    let expected = collect(form()).await;
+    let segments: Vec<_> = multipart::Body::from(form())
+        .into_chunks(1000)
+        .try_collect()
+        .await
+        .unwrap();
+
+    assert_eq!(segments.len(), expected.len().div_ceil(1000));
+    assert!(segments[..segments.len() - 1]
+        .iter()
+        .all(|segment| segment.len() == 1000));
+    assert_eq!(segments.concat(), expected);
+}
+
+#[tokio::test]
+async fn texts_are_sent_in_order() {
+    let mut form = multipart::Form::new::<TestGenerator>();
+
+    form.add_texts("tags", vec![String::from("a"), String::from("b")]);
+    form.add_texts("tags", ["c"]);
+
+    let body = String::from_utf8(collect(form).await).unwrap();
+    let values: Vec<_> = body
+        .split("--test")
+        .filter(|part| part.contains("name="))
+        .filter_map(|part| part.strip_suffix("\r\n"))
+        .filter_map(|part| part.rsplit("\r\n\r\n").next())
+        .collect();
+
+    assert_eq!(body.matches("name=\"tags\"").count(), 3);
+    assert_eq!(values, ["a", "b", "c"]);
+}
+
+#[cfg(feature = "urlencoded")]
+#[tokio::test]
+async fn urlencoded_fields_become_text_parts() {
+    let mut form = multipart::Form::new::<TestGenerator>();
+
+    form.add_urlencoded(b"title=Hello+World%21&tag=a&tag=b&empty=");
+
+    let parts: Vec<_> = form
+        .parts()
+        .iter()
+        .map(|part| part.name().to_string())
+        .collect();
+
+    assert_eq!(parts, ["title", "tag", "tag", "empty"]);
+
+    let body = String::from_utf8(collect(form).await).unwrap();
+
+    assert!(body.contains("name=\"title\"\r\n\r\nHello World!\r\n"));
+    assert!(body.contains("name=\"empty\"\r\n\r\n\r\n"));
+}
+
+#[tokio::test]
+async fn async_read_matches_body() {
+    use futures::AsyncReadExt;
+
+    fn form() -> multipart::Form {
+        let mut form = multipart::Form::new::<TestGenerator>();
+
+        form.body_config().chunk_size(16);
+        form.add_text("text", "Hello World!");
+        form.add_reader("input", std::io::Cursor::new(vec![7; 100]));
+        form
+    }
+
+    let expected = collect(form()).await;
+    let mut read = multipart::Body::from(form()).into_async_read();
+    let mut body = vec![];
+    let mut buf = [0; 7];
+
+    loop {
+        match read.read(&mut buf).await.unwrap() {
+            0 => break,
+            n => body.extend_from_slice(&buf[..n]),
+        }
+    }
+
+    assert_eq!(body, expected);
+
+    #[cfg(feature = "tokio")]
+    {
+        let mut read = multipart::Body::from(form()).into_async_read();
+        let mut body = vec![];
+
+        tokio::io::AsyncReadExt::read_to_end(&mut read, &mut body)
+            .await
+            .unwrap();
+
+        assert_eq!(body, expected);
+    }
+}
+
+#[tokio::test]
+async fn base64_content_length_matches_body() {
+    for len in [0, 1, 56, 57, 58, 114, 1000] {
+        let part = multipart::Part::bytes("data", vec![0xff; len], Some("data.bin"))
+            .transfer_encoding(multipart::TransferEncoding::Base64);
+        let content_length = part.content_length().unwrap();
+
+        let mut form = multipart::Form::new::<TestGenerator>();
+
+        form.leading_crlf(false);
+        form.add_part(part);
+
+        let body = collect(form).await;
+        let start = body.windows(4).position(|w| w == b"\r\n\r\n").unwrap() + 4;
+        let end = body.len() - "\r\n--test--".len();
+
+        assert_eq!(content_length, (end - start) as u64, "length {}", len);
+    }
+}
+
+#[tokio::test]
+async fn write_to_matches_body() {
+    let build = || {
+        let mut form = multipart::Form::new::<TestGenerator>();
+
+        form.add_text("text", "Hello World!");
+        form.add_bytes("bytes", vec![0; 5000]);
+        form.add_reader_file("input", std::io::Cursor::new(vec![1; 5000]), "input.bin");
+        form
+    };
+
+    let mut written = vec![];
+    let len = build().write_to(&mut written).unwrap();
+
+    assert_eq!(len, written.len() as u64);
+    assert_eq!(written, collect(build()).await);
+}
+
+#[test]
+fn write_to_applies_limits_and_rejects_async_parts() {
+    let mut form = multipart::Form::new::<TestGenerator>();
+
+    form.body_config().max_part_size(4);
+    form.add_reader("input", std::io::repeat(0));
+
+    let err = form.write_to(&mut std::io::sink()).unwrap_err();
+
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData, "{}", err);
+
+    let mut form = multipart::Form::new::<TestGenerator>();
+
+    form.add_async_reader("input", futures::io::Cursor::new("Hello World!"));
+
+    let mut written = vec![];
+    let err = form.write_to(&mut written).unwrap_err();
+
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+    assert!(written.is_empty());
+}
+
+/// An asynchronous reader that is only ready every other poll.
+struct SlowReader {
+    data: std::io::Cursor<Vec<u8>>,
+    ready: bool,
+}
+
+impl futures::io::AsyncRead for SlowReader {
+    fn poll_read(
+        mut self: std::pin::Pin<&mut Self>,
+        ctx: &mut std::task::Context,
+        buf: &mut [u8],
+    ) -> std::task::Poll<std::io::Result<usize>> {
+        self.ready = !self.ready;
+
+        if !self.ready {
+            ctx.waker().wake_by_ref();
+
+            return std::task::Poll::Pending;
+        }
+
+        std::task::Poll::Ready(std::io::Read::read(&mut self.data, buf))
+    }
+}
+
+#[tokio::test]
+async fn blocking_body_matches_body() {
+    use std::io::{Cursor, Read};
+
+    fn form() -> multipart::Form {
+        let mut form = multipart::Form::new::<TestGenerator>();
+
+        form.body_config().chunk_size(16);
+        form.add_text("text", "Hello World!");
+        form.add_reader("input", Cursor::new(vec![7; 100]));
+        form.add_async_reader("async", futures::io::Cursor::new("Hello Universe!"));
+        form
+    }
+
+    let expected = collect(form()).await;
+    let mut body = vec![];
+
+    multipart::blocking::Body::from(form())
+        .read_to_end(&mut body)
+        .unwrap();
+
+    assert_eq!(body, expected);
+
+    let chunks = multipart::blocking::Body::from(form())
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+
+    assert!(chunks.len() > 1);
+    assert_eq!(chunks.concat(), expected);
+
+    let mut form = multipart::blocking::Form::new::<TestGenerator>();
+
+    form.max_part_size(4);
+    form.add_text("text", "Hello World!");
+
+    let err = multipart::blocking::Body::from(form)
+        .read_to_end(&mut vec![])
+        .unwrap_err();
+
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+}
+
+#[cfg(feature = "curl")]
+#[tokio::test]
+async fn curl_reader_matches_body() {
+    use curl::easy::{Handler, ReadError};
+
+    fn form() -> multipart::Form {
+        let mut form = multipart::Form::new::<TestGenerator>();
+
+        form.add_text("text", "Hello World!");
+        form.add_reader("input", std::io::Cursor::new(vec![7; 100]));
+        form
+    }
+
+    let expected = collect(form()).await;
+    let mut reader = multipart::blocking::CurlReader::from(multipart::blocking::Body::from(form()));
+    let mut body = vec![];
+    let mut buf = [0; 7];
+
+    loop {
+        match reader.read(&mut buf).unwrap() {
+            0 => break,
+            n => body.extend_from_slice(&buf[..n]),
+        }
+    }
+
+    assert_eq!(body, expected);
+
+    let mut form = form();
+
+    form.max_part_size(4);
+
+    let mut reader = multipart::blocking::CurlReader::from(multipart::blocking::Body::from(form));
+
+    assert!(matches!(reader.read(&mut buf), Err(ReadError::Abort)));
+    assert!(reader.take_error().is_some());
+}
+
+#[tokio::test]
+async fn local_form_matches_form() {
+    use std::{io::Cursor, rc::Rc};
+
+    let content: Rc<[u8]> = Rc::from(vec![7; 100]);
+    let mut form = multipart::Form::new::<TestGenerator>();
+
+    form.body_config().chunk_size(16);
+    form.add_text("text", "Hello World!");
+    form.add_reader("input", Cursor::new(vec![7; 100]));
+    form.add_async_reader_file(
+        "async",
+        futures::io::Cursor::new("Hello Universe!"),
+        "a.txt",
+    );
+
+    let expected = collect(form).await;
+    let mut form = multipart::LocalForm::new::<TestGenerator>();
+
+    form.body_config().chunk_size(16);
+    form.add_text("text", "Hello World!");
+    form.add_local_reader("input", Cursor::new(content.clone()));
+    form.add_local_async_reader_file(
+        "async",
+        futures::io::Cursor::new(Rc::<[u8]>::from(&b"Hello Universe!"[..])),
+        "a.txt",
+    );
+
+    let body = multipart::LocalBody::from(form)
+        .collect_bytes()
+        .await
+        .unwrap();
+
+    assert_eq!(body, expected);
+
+    let mut form = multipart::LocalForm::new::<TestGenerator>();
+
+    form.add_local_reader("input", FailingReader(std::marker::PhantomData));
+
+    assert!(multipart::LocalBody::from(form)
+        .collect_bytes()
+        .await
+        .is_err());
+}
+
+/// A reader that isn't `Send`, and always fails.
+struct FailingReader(std::marker::PhantomData<std::rc::Rc<()>>);
+
+impl std::io::Read for FailingReader {
+    fn read(&mut self, _: &mut [u8]) -> std::io::Result<usize> {
+        Err(std::io::Error::other("failed"))
+    }
+}
+
+#[tokio::test]
+async fn async_reader_matches_reader() {
+    let data: Vec<u8> = (0..10_000).map(|i| i as u8).collect();
+
+    let mut form = multipart::Form::new::<TestGenerator>();
+
+    form.add_reader_file("input", std::io::Cursor::new(data.clone()), "input.bin");
+
+    let expected = collect(form).await;
+
+    let mut form = multipart::Form::new::<TestGenerator>();
+
+    form.add_async_reader_file(
+        "input",
+        SlowReader {
+            data: std::io::Cursor::new(data),
+            ready: false,
+        },
+        "input.bin",
+    );
+
+    assert_eq!(collect(form).await, expected);
+}
+
+#[tokio::test]
+async fn blocking_spawner_matches_reader() {
+    let build = || {
+        let data: Vec<u8> = (0..10_000).map(|i| i as u8).collect();
+        let mut form = multipart::Form::new::<TestGenerator>();
+
+        form.add_text("text", "Hello World!");
+        form.add_reader_file("input", std::io::Cursor::new(data), "input.bin");
+        form
+    };
+
+    let expected = collect(build()).await;
+
+    let mut form = build();
+
+    form.body_config().blocking_spawner(|task| {
+        std::thread::spawn(task);
+    });
+
+    assert_eq!(collect(form).await, expected);
+}
+
+#[cfg(feature = "fs")]
+#[tokio::test]
+async fn lazy_file_open_error_is_returned_by_body() {
+    let mut form = multipart::Form::new::<TestGenerator>();
+
+    form.add_file_lazy("file", "does/not/exist.txt");
+
+    let res = multipart::Body::from(form).collect_bytes().await;
+
+    assert!(matches!(
+        res,
+        Err(hyper_multipart_rfc7578::client::Error::ContentRead { .. })
+    ));
+}
+
+#[cfg(feature = "fs")]
+#[tokio::test]
+async fn file_filename_overrides_the_path() {
+    let mut form = multipart::Form::new::<TestGenerator>();
+
+    form.add_file_with_filename_and_mime("data", "Cargo.toml", "manifest.toml", mime::TEXT_PLAIN)
+        .unwrap();
+
+    let body = String::from_utf8(collect(form).await).unwrap();
+
+    assert!(body.contains(
+        "Content-Type: text/plain\r\nContent-Disposition: form-data; name=\"data\"; filename=\"manifest.toml\"\r\n"
+    ));
+}
+
+#[cfg(all(feature = "fs", unix))]
+#[tokio::test]
+async fn directory_parts_keep_relative_paths() {
+    use multipart::DirectoryOptions;
+    use std::{fs, os::unix::fs::symlink};
+
+    let dir = tempfile::tempdir().unwrap();
+
+    fs::create_dir_all(dir.path().join("b/c")).unwrap();
+    fs::create_dir(dir.path().join(".git")).unwrap();
+    fs::write(dir.path().join("a.txt"), "a").unwrap();
+    fs::write(dir.path().join("b/c/d.txt"), "d").unwrap();
+    fs::write(dir.path().join(".hidden"), "h").unwrap();
+    fs::write(dir.path().join(".git/config"), "g").unwrap();
+    symlink(dir.path().join("a.txt"), dir.path().join("link.txt")).unwrap();
+    symlink(dir.path(), dir.path().join("b/loop")).unwrap();
+
+    let filenames = |options| {
+        let mut form = multipart::Form::new::<TestGenerator>();
+
+        form.add_directory_with_options("files", dir.path(), options)
+            .unwrap();
+        form.parts()
+            .iter()
+            .map(|part| part.filename().unwrap().to_string())
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(filenames(DirectoryOptions::new()), ["a.txt", "b/c/d.txt"]);
+    assert_eq!(
+        filenames(DirectoryOptions::new().hidden(true).follow_symlinks(true)),
+        [".git/config", ".hidden", "a.txt", "b/c/d.txt", "link.txt"]
+    );
+
+    let mut form = multipart::Form::new::<TestGenerator>();
+
+    form.add_directory("files", dir.path()).unwrap();
+
+    let body = String::from_utf8(collect(form).await).unwrap();
+
+    assert!(body.contains("filename=\"b/c/d.txt\""));
+    assert!(body.contains("\r\n\r\nd\r\n--test--"));
+
+    let mut form = multipart::Form::new::<TestGenerator>();
+
+    assert!(form
+        .add_directory("files", dir.path().join("a.txt"))
+        .is_err());
+    assert!(form
+        .add_directory("files", dir.path().join("missing"))
+        .is_err());
+    assert!(form.is_empty());
+}
+
+#[cfg(all(feature = "fs", unix))]
+#[test]
+fn directory_errors_name_every_path() {
+    use multipart::DirectoryOptions;
+    use std::{fs, os::unix::fs::symlink};
+
+    let dir = tempfile::tempdir().unwrap();
+
+    fs::create_dir(dir.path().join("b")).unwrap();
+    fs::write(dir.path().join("a.txt"), "a").unwrap();
+    symlink(dir.path().join("missing"), dir.path().join("broken")).unwrap();
+    symlink(dir.path().join("missing"), dir.path().join("b/broken")).unwrap();
+
+    let mut form = multipart::Form::new::<TestGenerator>();
+    let options = DirectoryOptions::new().follow_symlinks(true);
+    let err = form
+        .add_directory_with_options("files", dir.path(), options)
+        .unwrap_err()
+        .to_string();
+
+    assert!(err.starts_with("2 paths can't be read"), "{}", err);
+    assert!(err.contains(&dir.path().join("broken").display().to_string()));
+    assert!(err.contains(&dir.path().join("b/broken").display().to_string()));
+    assert!(form.is_empty());
+}
+
+#[cfg(feature = "fs")]
+#[tokio::test]
+async fn files_mixed_are_opened_lazily() {
+    use std::fs;
+
+    let dir = tempfile::tempdir().unwrap();
+    let csv = dir.path().join("data \"1\".csv");
+    let missing = dir.path().join("missing.txt");
+
+    fs::write(&csv, "a,b,c").unwrap();
+
+    let mut form = multipart::Form::new::<TestGenerator>();
+
+    form.add_files_mixed("files", [&csv]).unwrap();
+
+    let body = String::from_utf8(collect(form).await).unwrap();
+    let head = format!(
+        "Content-Type: text/csv\r\nContent-Disposition: attachment; filename=\"{}/data %221%22.csv\"\r\n\r\na,b,c\r\n--",
+        dir.path().display()
+    );
+
+    assert!(body.contains(&head), "{}", body);
+
+    let mut form = multipart::Form::new::<TestGenerator>();
+
+    form.add_files_mixed("files", [&csv, &missing]).unwrap();
+
+    let err = multipart::Body::from(form)
+        .collect_bytes()
+        .await
+        .unwrap_err()
+        .to_string();
+
+    assert!(err.contains(&missing.display().to_string()), "{}", err);
+}
+
+#[cfg(feature = "fs")]
+#[test]
+fn file_mime_is_guessed_from_extension() {
+    use std::fs;
+
+    let dir = tempfile::tempdir().unwrap();
+
+    for file in ["data.csv", "photo.PNG", "notes.unknown"] {
+        fs::write(dir.path().join(file), "data").unwrap();
+    }
+
+    let mut form = multipart::Form::new::<TestGenerator>();
+
+    form.add_file("file", dir.path().join("data.csv")).unwrap();
+    form.add_file_lazy("file", dir.path().join("photo.PNG"));
+    form.add_directory("files", dir.path()).unwrap();
+
+    let mimes = form
+        .parts()
+        .iter()
+        .map(|part| part.content_type().to_string())
+        .collect::<Vec<_>>();
+
+    assert_eq!(
+        mimes,
+        [
+            "text/csv",
+            "image/png",
+            "text/csv",
+            "application/octet-stream",
+            "image/png"
+        ]
+    );
+}
+
+#[cfg(feature = "tar")]
+#[tokio::test]
+async fn directory_tar_contains_every_file() {
+    use std::{fs, io::Read};
+
+    let dir = tempfile::tempdir().unwrap();
+    let long = "d".repeat(120);
+
+    fs::create_dir_all(dir.path().join("b").join(&long)).unwrap();
+    fs::write(dir.path().join("a.txt"), "Hello World!").unwrap();
+    fs::write(
+        dir.path().join("b").join(&long).join("c.bin"),
+        vec![7; 1000],
+    )
+    .unwrap();
+    fs::write(dir.path().join(".hidden"), "hidden").unwrap();
+
+    let mut form = multipart::Form::new::<TestGenerator>();
+
+    form.add_directory_as_tar("archive", dir.path()).unwrap();
+
+    let body = collect(form).await;
+    let start = body.windows(4).position(|w| w == b"\r\n\r\n").unwrap() + 4;
+    let archive = &body[start..body.len() - b"\r\n--test--".len()];
+
+    assert_eq!(archive.len() % 512, 0);
+
+    let mut entries = vec![];
+
+    for entry in tar::Archive::new(archive).entries().unwrap() {
+        let mut entry = entry.unwrap();
+        let mut content = vec![];
+
+        entry.read_to_end(&mut content).unwrap();
+        entries.push((entry.path().unwrap().display().to_string(), content));
+    }
+
+    assert_eq!(
+        entries,
+        [
+            (String::from("a.txt"), b"Hello World!".to_vec()),
+            (format!("b/{}/c.bin", long), vec![7; 1000]),
+        ]
+    );
+}
+
+#[cfg(feature = "tar")]
+#[tokio::test]
+async fn directory_tar_fails_if_a_file_grows() {
+    use futures::StreamExt;
+    use std::{fs, io::Write};
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("a.bin");
+
+    fs::write(&path, vec![7; 8192]).unwrap();
+
+    let mut form = multipart::Form::new::<TestGenerator>();
+
+    form.body_config().chunk_size(1024);
+    form.add_directory_as_tar("archive", dir.path()).unwrap();
+
+    let mut stream = multipart::Body::from(form).into_data_stream();
+    let mut len = 0;
+
+    // Once part of the content was read, the file is open.
+    while len < 2048 {
+        len += stream.next().await.unwrap().unwrap().len();
+    }
+
+    fs::OpenOptions::new()
+        .append(true)
+        .open(&path)
+        .unwrap()
+        .write_all(b"more")
+        .unwrap();
+
+    let mut res = Ok(());
+
+    while let Some(item) = stream.next().await {
+        res = item.map(drop);
+    }
+
+    assert!(res.unwrap_err().to_string().contains("grew"));
+}
+
+#[cfg(feature = "fs")]
+#[tokio::test]
+async fn file_range_sends_only_the_range() {
+    let contents = std::fs::read(file!()).unwrap();
+
+    let mut form = multipart::Form::new::<TestGenerator>();
+
+    form.add_file_range("file", file!(), 100, 50).unwrap();
+
+    assert_eq!(form.parts()[0].content_length(), Some(50));
+
+    let body = collect(form).await;
+    let start = body.windows(4).position(|w| w == b"\r\n\r\n").unwrap() + 4;
+
+    assert_eq!(&body[start..start + 50], &contents[100..150]);
+    assert_eq!(&body[start + 50..], b"\r\n--test--");
+}
+
+#[cfg(feature = "fs")]
+#[tokio::test]
+async fn spooled_body_can_be_sent_twice() {
+    use std::io::Cursor;
+
+    let form = || {
+        let mut form = multipart::Form::new::<TestGenerator>();
+
+        form.add_text("text", "Hello World!");
+        form.add_reader_file("data", Cursor::new("a,b,c\n".repeat(1000)), "data.csv");
+        form
+    };
+
+    let expected = collect(form()).await;
+    let spooled = form().spool().unwrap();
+
+    assert_eq!(spooled.len(), expected.len() as u64);
+
+    for _ in 0..2 {
+        let body = spooled.body().collect_bytes().await.unwrap();
+
+        assert_eq!(body, expected);
+    }
+}
+
+#[cfg(feature = "fs")]
+#[tokio::test]
+async fn buffered_body_matches_below_and_above_memory_limit() {
+    use std::io::Cursor;
+
+    let form = || {
+        let mut form = multipart::Form::new::<TestGenerator>();
+
+        form.add_reader_file("data", Cursor::new("a,b,c\n".repeat(1000)), "data.csv");
+        form
+    };
+
+    let expected = collect(form()).await;
+
+    for max_memory in [16, 1024 * 1024] {
+        let buffered = form().buffered(max_memory).unwrap();
+
+        assert_eq!(buffered.body().collect_bytes().await.unwrap(), expected);
+    }
+}
+
+#[tokio::test]
+async fn sized_reader_sends_exactly_its_length() {
+    use std::io::Cursor;
+
+    let mut form = multipart::Form::new::<TestGenerator>();
+
+    form.add_reader_sized("data", Cursor::new("Hello World!"), 5);
+
+    let len = form.content_length().unwrap();
+    let body = collect(form).await;
+
+    assert_eq!(len, body.len() as u64);
+    assert!(body.ends_with(b"\r\n\r\nHello\r\n--test--"));
+
+    let mut form = multipart::Form::new::<TestGenerator>();
+
+    form.add_reader_sized("data", Cursor::new("Hello"), 12);
+
+    let res = multipart::Body::from(form).collect_bytes().await;
+
+    assert!(matches!(
+        res,
+        Err(hyper_multipart_rfc7578::client::Error::ContentRead { offset: 5, .. })
+    ));
+}
+
+#[tokio::test]
+async fn body_reports_known_length() {
+    use futures::{Stream, StreamExt};
+    use std::io::Cursor;
+
+    let mut form = multipart::Form::new::<TestGenerator>();
+
+    form.add_text("text", "Hello World!");
+    form.add_bytes("bytes", vec![0; 5000]);
+
+    let len = form.content_length().unwrap();
+    let body = multipart::Body::from(form);
+
+    #[cfg(feature = "http")]
+    assert_eq!(http_body::Body::size_hint(&body).exact(), Some(len));
+
+    let mut stream = body.into_data_stream();
+
+    assert_eq!(stream.size_hint(), (1, Some(len as usize)));
+
+    let mut read = 0;
+
+    while let Some(chunk) = stream.next().await {
+        read += chunk.unwrap().len() as u64;
+
+        let (_, upper) = stream.size_hint();
+
+        assert_eq!(upper, Some((len - read) as usize));
+    }
+
+    assert_eq!(read, len);
+    assert_eq!(stream.size_hint(), (0, Some(0)));
+
+    let mut form = multipart::Form::new::<TestGenerator>();
+
+    form.add_reader("input", Cursor::new("Hello World!"));
+
+    let body = multipart::Body::from(form);
+
+    #[cfg(feature = "http")]
+    assert_eq!(http_body::Body::size_hint(&body).exact(), None);
+    assert_eq!(body.into_data_stream().size_hint(), (0, None));
+}
+
+#[tokio::test]
+async fn transforms_apply_in_order_and_finish() {
+    use bytes::Bytes;
+    use std::io::{self, Cursor};
+
+    /// Counts the bytes of the content, and appends the count at the end.
+    struct Count(usize);
+
+    impl multipart::Transform for Count {
+        fn transform(&mut self, chunk: Bytes) -> io::Result<Bytes> {
+            self.0 += chunk.len();
+            Ok(chunk)
+        }
+
+        fn finish(&mut self) -> io::Result<Bytes> {
+            Ok(Bytes::from(format!(" ({})", self.0)))
+        }
+    }
+
+    let upper = |chunk: Bytes| Ok(Bytes::from(chunk.to_ascii_uppercase()));
+    let mut form = multipart::Form::new::<TestGenerator>();
+
+    form.add_part(
+        multipart::Part::text("text", "Hello World!")
+            .transform(Count(0))
+            .transform(upper),
+    );
+    form.add_part(
+        multipart::Part::async_reader(
+            "input",
+            futures::io::Cursor::new("Hello Universe!"),
+            None::<String>,
+        )
+        .transform(upper),
+    );
+
+    assert_eq!(form.content_length(), None);
+    assert!(form.try_clone().is_none());
+
+    let body = String::from_utf8(collect(form).await).unwrap();
+
+    assert!(body.contains("\r\n\r\nHELLO WORLD! (12)\r\n--test"));
+    assert!(body.contains("\r\n\r\nHELLO UNIVERSE!\r\n--test--"));
+
+    let mut form = multipart::Form::new::<TestGenerator>();
+
+    form.add_part(
+        multipart::Part::reader("data", Cursor::new("abc"), None::<String>)
+            .transform(|_: Bytes| Err(io::Error::other("redacted"))),
+    );
+
+    assert!(multipart::Body::from(form).collect_bytes().await.is_err());
+}
+
+#[tokio::test]
+async fn hooks_fire_on_complete_or_abort() {
+    use futures::StreamExt;
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+
+    let completed = Arc::new(AtomicUsize::new(0));
+    let aborted = Arc::new(AtomicUsize::new(0));
+    let form = || {
+        let mut form = multipart::Form::new::<TestGenerator>();
+        let (completed, aborted) = (completed.clone(), aborted.clone());
+
+        form.body_config()
+            .chunk_size(4)
+            .on_complete(move || {
+                completed.fetch_add(1, Ordering::SeqCst);
+            })
+            .on_abort(move || {
+                aborted.fetch_add(1, Ordering::SeqCst);
+            });
+        form.add_text("text", "Hello World!");
+        form
+    };
+    let counts = || {
+        (
+            completed.load(Ordering::SeqCst),
+            aborted.load(Ordering::SeqCst),
+        )
+    };
+
+    collect(form()).await;
+
+    assert_eq!(counts(), (1, 0));
+
+    let mut stream = multipart::Body::from(form()).into_data_stream();
+
+    stream.next().await.unwrap().unwrap();
+    drop(stream);
+
+    assert_eq!(counts(), (1, 1));
+
+    let mut too_large = form();
+
+    too_large.max_part_size(4);
+
+    let mut stream = multipart::Body::from(too_large).into_data_stream();
+
+    while let Some(Ok(_)) = stream.next().await {}
+
+    // The body ended with the error, before it was dropped.
+    assert_eq!(counts(), (1, 2));
+    assert!(stream.next().await.is_none());
+
+    drop(stream);
+
+    assert_eq!(counts(), (1, 2));
+
+    let mut invalid = form();
+
+    invalid.name_encoding(multipart::NameEncoding::Rfc7578Strict);
+    invalid.add_bytes_file("file", &b"a,b,c"[..], "data\r\n.csv");
+
+    let mut stream = multipart::Body::from(invalid).into_data_stream();
+
+    assert!(stream.next().await.unwrap().is_err());
+    assert_eq!(counts(), (1, 3));
+    assert!(stream.next().await.is_none());
+
+    drop(stream);
+
+    assert_eq!(counts(), (1, 3));
+}
+
+#[tokio::test]
+async fn endless_reader_exceeds_part_size() {
+    let mut form = multipart::Form::new::<TestGenerator>();
+
+    form.max_part_size(1024 * 1024);
+    form.add_reader("input", std::io::repeat(0));
+
+    let res = multipart::Body::from(form).collect_bytes().await;
+
+    assert!(matches!(
+        res,
+        Err(hyper_multipart_rfc7578::client::Error::PartTooLarge { .. })
+    ));
+}
+
+#[tokio::test]
+async fn errors_end_the_body() {
+    use futures::StreamExt;
+    use hyper_multipart_rfc7578::client::Error;
+
+    let errors = |form: multipart::Form| async move {
+        let items: Vec<_> = multipart::Body::from(form)
+            .into_data_stream()
+            .collect()
+            .await;
+
+        items
+            .into_iter()
+            .filter_map(Result::err)
+            .collect::<Vec<_>>()
+    };
+
+    let mut form = multipart::Form::new::<TestGenerator>();
+
+    form.max_part_size(4);
+    form.add_reader("input", std::io::repeat(0));
+    form.add_text("text", "Hello World!");
+
+    assert!(matches!(
+        errors(form).await[..],
+        [Error::PartTooLarge { .. }]
+    ));
+
+    let mut form = multipart::Form::new::<TestGenerator>();
+
+    form.max_part_size(4);
+    form.add_bytes("bytes", &b"Hello World!"[..]);
+    form.add_text("text", "Hello World!");
+
+    assert!(matches!(
+        errors(form).await[..],
+        [Error::PartTooLarge { .. }]
+    ));
+
+    let mut form = multipart::Form::new::<TestGenerator>();
+
+    form.max_body_size(16);
+    form.add_text("text", "Hello World!");
+
+    assert!(matches!(
+        errors(form).await[..],
+        [Error::BodyTooLarge { .. }]
+    ));
+}
+
+#[cfg(feature = "deadline")]
+#[tokio::test]
+async fn deadline_ends_a_stalled_body() {
+    use futures::{io::AsyncRead, StreamExt};
+    use hyper_multipart_rfc7578::client::Error;
+    use std::{
+        io,
+        pin::Pin,
+        task::{Context, Poll},
+        time::Duration,
+    };
+
+    struct Stalled;
+
+    impl AsyncRead for Stalled {
+        fn poll_read(
+            self: Pin<&mut Self>,
+            _: &mut Context,
+            _: &mut [u8],
+        ) -> Poll<io::Result<usize>> {
+            Poll::Pending
+        }
+    }
+
+    let mut form = multipart::Form::new::<TestGenerator>();
+
+    form.add_text("text", "Hello World!");
+    form.add_async_reader("stalled", Stalled);
+
+    let mut stream = multipart::Body::from(form)
+        .with_deadline(Duration::from_millis(20))
+        .into_data_stream();
+    let err = loop {
+        if let Err(e) = stream.next().await.unwrap() {
+            break e;
+        }
+    };
+
+    assert!(matches!(err, Error::DeadlineExceeded { after } if after.as_millis() == 20));
+    assert!(stream.next().await.is_none());
+}
+
+#[tokio::test]
+async fn body_size_limit_includes_headers() {
+    let mut form = multipart::Form::new::<TestGenerator>();
+
+    form.max_body_size(20);
+    form.add_text("text", "Hello");
+
+    let res = multipart::Body::from(form).collect_bytes().await;
+
+    assert!(matches!(
+        res,
+        Err(hyper_multipart_rfc7578::client::Error::BodyTooLarge { max: 20 })
+    ));
+}
+
+#[cfg(feature = "checksum")]
+#[tokio::test]
+async fn checksum_covers_every_chunk() {
+    use multipart::ChecksumAlgorithm;
+
+    let mut form = multipart::Form::new::<TestGenerator>();
+
+    form.body_config().chunk_size(4);
+
+    let checksum = form.add_part_with_checksum(
+        multipart::Part::reader("input", std::io::Cursor::new("Hello World!"), None::<&str>),
+        ChecksumAlgorithm::Md5,
+    );
+
+    collect(form).await;
+
+    assert_eq!(checksum.hex().unwrap(), "ed076287532e86365e841e92bfc50d8c");
+}
+
+#[cfg(feature = "checksum")]
+#[tokio::test]
+async fn content_md5_covers_the_whole_body() {
+    use base64::{engine::general_purpose::STANDARD, Engine};
+    use md5::{Digest, Md5};
+    use std::io::Cursor;
+
+    let mut form = multipart::Form::new::<TestGenerator>();
+
+    form.add_text("text", "Hello World!");
+    form.add_seekable_reader("input", Cursor::new(vec![7; 5000]));
+
+    let md5 = form.compute_content_md5().unwrap();
+    let body = collect(form).await;
+
+    assert_eq!(md5, STANDARD.encode(Md5::digest(&body)));
+
+    let mut form = multipart::Form::new::<TestGenerator>();
+
+    form.add_reader("input", Cursor::new("Hello World!"));
+
+    assert_eq!(
+        form.compute_content_md5().unwrap_err().kind(),
+        std::io::ErrorKind::InvalidInput
+    );
+}
+
+#[cfg(all(feature = "checksum", feature = "http"))]
+#[test]
+fn changing_the_form_drops_content_md5() {
+    let mut form = multipart::Form::new::<TestGenerator>();
+
+    form.add_text("text", "Hello World!");
+    form.compute_content_md5().unwrap();
+    form.add_text("more", "Hello again!");
+
+    let req = form
+        .set_body(http::Request::post("http://localhost/upload"))
+        .unwrap();
+
+    assert!(!req.headers().contains_key("content-md5"));
+
+    let mut form = multipart::Form::new::<TestGenerator>();
+
+    form.add_text("text", "Hello World!");
+    form.compute_content_md5().unwrap();
+    form.part_content_length(true);
+
+    let req = form
+        .set_body(http::Request::post("http://localhost/upload"))
+        .unwrap();
+
+    assert!(!req.headers().contains_key("content-md5"));
+}
+
+#[cfg(all(feature = "checksum", feature = "http"))]
+#[tokio::test]
+async fn spooled_body_sets_content_md5() {
+    use base64::{engine::general_purpose::STANDARD, Engine};
+    use md5::{Digest, Md5};
+    use std::io::Cursor;
+
+    let mut form = multipart::Form::new::<TestGenerator>();
+
+    form.add_reader("input", Cursor::new("Hello World!"));
+
+    let spooled = form.spool_to(Cursor::new(vec![])).unwrap();
+    let req = spooled
+        .set_body(http::Request::post("http://localhost/upload"))
+        .unwrap();
+    let body = spooled.body().collect_bytes().await.unwrap();
+
+    assert_eq!(spooled.content_md5(), STANDARD.encode(Md5::digest(&body)));
+    assert_eq!(req.headers()["content-md5"], spooled.content_md5());
+}
+
+#[cfg(all(feature = "checksum", feature = "http"))]
+#[test]
+fn local_form_sets_content_md5() {
+    let mut form = multipart::LocalForm::new::<TestGenerator>();
+
+    form.add_text("text", "Hello World!");
+
+    let md5 = form.compute_content_md5().unwrap();
+    let req = form
+        .set_body(http::Request::post("http://localhost/upload"))
+        .unwrap();
+
+    assert_eq!(req.headers()["content-md5"], md5.as_str());
+}
+
+#[cfg(feature = "http")]
+#[tokio::test]
+async fn batch_parts_are_http_requests() {
+    use http::Request;
+
+    let mut batch = multipart::BatchForm::new::<TestGenerator>();
+
+    batch.add_request(Request::get("/v1/items/1").body("").unwrap());
+    batch.add_request_with_id(
+        "update",
+        Request::patch("/v1/items/2")
+            .header("content-type", "application/json")
+            .body("{}")
+            .unwrap(),
+    );
+
+    assert_eq!(batch.content_type(), "multipart/mixed; boundary=\"test\"");
+
+    let body = multipart::Body::from(batch).collect_bytes().await.unwrap();
+
+    assert_eq!(
+        std::str::from_utf8(&body).unwrap(),
+        "\r\n\
+         --test\r\n\
+         Content-Type: application/http\r\n\
+         Content-ID: <1>\r\n\
+         \r\n\
+         GET /v1/items/1 HTTP/1.1\r\n\
+         \r\n\
+         \r\n\
+         --test\r\n\
+         Content-Type: application/http\r\n\
+         Content-ID: <update>\r\n\
+         \r\n\
+         PATCH /v1/items/2 HTTP/1.1\r\n\
+         content-type: application/json\r\n\
+         content-length: 2\r\n\
+         \r\n\
+         {}\r\n\
+         --test--"
+    );
+}
+
+#[cfg(feature = "http")]
+#[tokio::test]
+async fn trailers_follow_the_final_boundary() {
+    use http::header::{HeaderName, HeaderValue};
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+
+    let written = Arc::new(AtomicUsize::new(0));
+    let mut form = multipart::Form::new::<TestGenerator>();
+
+    form.add_text("text", "Hello World!");
+    form.body_config()
+        .trailer(HeaderName::from_static("x-written"), {
+            let written = written.clone();
+
+            move || HeaderValue::from(written.load(Ordering::SeqCst))
+        });
+
+    let frames: Vec<_> = multipart::Body::from(form)
+        .inspect_ok(|frame| {
+            if let Some(data) = frame.data_ref() {
+                written.fetch_add(data.len(), Ordering::SeqCst);
+            }
+        })
+        .try_collect()
+        .await
+        .unwrap();
+    let (last, data) = frames.split_last().unwrap();
+
+    assert!(data.iter().all(|frame| frame.is_data()));
+    assert!(data
+        .last()
+        .unwrap()
+        .data_ref()
+        .unwrap()
+        .ends_with(b"--test--"));
+    assert_eq!(
+        last.trailers_ref().unwrap()["x-written"],
+        written.load(Ordering::SeqCst).to_string()
+    );
+}
+
+#[tokio::test]
+async fn random_boundary_draws_from_its_entropy() {
+    use multipart::{Entropy, RandomBoundary, StdEntropy};
+
+    struct Zeroes;
+
+    impl Entropy for Zeroes {
+        fn fill_bytes(buf: &mut [u8]) {
+            buf.fill(0);
+        }
+    }
+
+    assert_eq!(
+        RandomBoundary::<4, Zeroes>::generate_boundary(),
+        "----HyperMultipartAAAA"
+    );
+
+    let first = RandomBoundary::<16, StdEntropy>::generate_boundary();
+    let second = RandomBoundary::<16, StdEntropy>::generate_boundary();
+
+    assert_eq!(first.len(), 34);
+    assert!(first[18..].chars().all(|c| c.is_ascii_alphanumeric()));
+    assert_ne!(first, second);
+}
+
+#[tokio::test]
+async fn colliding_boundary_is_regenerated() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    static GENERATED: AtomicUsize = AtomicUsize::new(0);
+
+    struct CountingGenerator;
+
+    impl BoundaryGenerator for CountingGenerator {
+        fn generate_boundary() -> String {
+            format!("boundary{}", GENERATED.fetch_add(1, Ordering::SeqCst))
+        }
+    }
+
+    let mut form = multipart::Form::new::<CountingGenerator>();
+
+    form.add_text("text", "--boundary0");
+
+    let (content_type, _) = form.into_bytes().unwrap();
+
+    assert_eq!(content_type, "multipart/form-data; boundary=\"boundary1\"");
+}
+
+#[tokio::test]
+async fn colliding_boundary_fails_the_body() {
+    let mut form = multipart::Form::with_generator(&|| "test".to_string());
+
+    form.add_bytes("bytes", &b"\r\n--test--"[..]);
+
+    let res = multipart::Body::from(form).collect_bytes().await;
+
+    assert!(matches!(
+        res,
+        Err(hyper_multipart_rfc7578::client::Error::BoundaryCollision { index: 0, .. })
+    ));
+}
+
+#[tokio::test]
+async fn content_read_error_has_part_and_offset() {
+    use std::io::{self, Read};
+
+    struct FailingReader(usize);
+
+    impl Read for FailingReader {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            if self.0 == 0 {
+                return Err(io::Error::other("disk on fire"));
+            }
+
+            let n = self.0.min(buf.len());
+
+            buf[..n].fill(b'a');
+            self.0 -= n;
+
+            Ok(n)
+        }
+    }
+
+    let mut form = multipart::Form::new::<TestGenerator>();
+
+    form.body_config().chunk_size(4);
+    form.add_text("text", "Hello World!");
+    form.add_reader("input", FailingReader(10));
+
+    let res = multipart::Body::from(form).collect_bytes().await;
+
+    match res {
+        Err(hyper_multipart_rfc7578::client::Error::ContentRead {
+            index,
+            name,
+            offset,
+            ..
+        }) => assert_eq!((index, &name[..], offset), (1, "input", 10)),
+        res => panic!("unexpected result {:?}", res),
+    }
+}
+
+#[tokio::test]
+async fn content_read_error_chains_its_source() {
+    use std::{error::Error, io};
+
+    let mut form = multipart::Form::new::<TestGenerator>();
+
+    form.add_part(
+        multipart::Part::async_reader("encoded", futures::io::empty(), None::<&str>)
+            .transfer_encoding(multipart::TransferEncoding::Base64),
+    );
+
+    let err = multipart::Body::from(form)
+        .collect_bytes()
+        .await
+        .unwrap_err();
+
+    assert_eq!(
+        err.source().unwrap().to_string(),
+        "encodings are not supported for async readers"
+    );
+    assert_eq!(io::Error::from(err).kind(), io::ErrorKind::InvalidInput);
+}
+
+#[tokio::test]
+async fn invalid_filename_fails_the_body() {
+    let mut form = multipart::Form::new::<TestGenerator>();
+
+    form.name_encoding(multipart::NameEncoding::Rfc7578Strict);
+    form.add_bytes_file("file", &b"a,b,c"[..], "data\r\n.csv");
+
+    let res = multipart::Body::from(form).collect_bytes().await;
+
+    assert!(matches!(
+        res,
+        Err(hyper_multipart_rfc7578::client::Error::InvalidFilename { index: 0, .. })
+    ));
+}
+
+#[tokio::test]
+async fn validate_reports_every_problem() {
+    use hyper_multipart_rfc7578::client::Error;
+
+    let mut form = multipart::Form::with_boundary("test");
+
+    assert!(matches!(
+        form.validate().unwrap_err()[..],
+        [Error::EmptyForm]
+    ));
+
+    form.duplicate_names(multipart::DuplicateNames::Reject);
+    form.name_encoding(multipart::NameEncoding::Rfc7578Strict);
+    form.add_text("text", "Hello World!");
+    form.add_bytes_file("file", &b"a,b,c"[..], "data\r\n.csv");
+    form.add_text("text", "\r\n--test--");
+
+    let problems = form.validate().unwrap_err();
+
+    assert!(matches!(
+        problems[..],
+        [
+            Error::InvalidFilename { index: 1, .. },
+            Error::DuplicateName { index: 2, .. },
+            Error::BoundaryCollision { index: 2, .. },
+        ]
+    ));
+
+    let res = multipart::Body::from(form).collect_bytes().await;
+
+    assert!(matches!(res, Err(Error::InvalidFilename { index: 1, .. })));
+}
+
+#[cfg(feature = "fs")]
+#[tokio::test]
+async fn validate_reports_unreadable_files() {
+    use hyper_multipart_rfc7578::client::Error;
+
+    let mut form = multipart::Form::new::<TestGenerator>();
+
+    form.add_file_lazy("missing", "does/not/exist.txt");
+    form.add_file_lazy("directory", "src");
+
+    let problems = form.validate().unwrap_err();
+
+    assert!(matches!(
+        problems[..],
+        [
+            Error::UnreadableFile { index: 0, .. },
+            Error::UnreadableFile { index: 1, .. },
+        ]
+    ));
+}
+
+#[cfg(feature = "fs")]
+#[tokio::test]
+async fn windows_paths_are_normalized() {
+    use multipart::FilenameNormalization;
+
+    let filename = |normalization, path: &str| {
+        let mut form = multipart::Form::new::<TestGenerator>();
+
+        form.normalize_filenames(normalization);
+        form.add_file_lazy("file", path);
+        form.parts()[0].filename().map(String::from)
+    };
+    let windows = FilenameNormalization::windows();
+
+    for (path, expected) in [
+        (r"C:\Users\ferris\data.csv", "Users/ferris/data.csv"),
+        (r"c:data.csv", "data.csv"),
+        (r"\\server\share\dir\data.csv", "dir/data.csv"),
+        (r"\\?\C:\dir\data.csv", "dir/data.csv"),
+        (r"\\?\UNC\server\share\data.csv", "data.csv"),
+        (r"\\.\pipe\data", "pipe/data"),
+        (r"\dir\data.csv", "dir/data.csv"),
+        ("/tmp/data.csv", "tmp/data.csv"),
+        (r"dir\data.csv", "dir/data.csv"),
+    ] {
+        assert_eq!(filename(windows, path).unwrap(), expected, "{}", path);
+    }
+
+    assert_eq!(
+        filename(FilenameNormalization::default(), r"C:\data.csv").unwrap(),
+        r"C:\data.csv"
+    );
+    assert_eq!(
+        filename(
+            FilenameNormalization::new().forward_slashes(true),
+            r"C:\data.csv"
+        )
+        .unwrap(),
+        "C:/data.csv"
+    );
+}
+
+#[tokio::test]
+async fn name_encoding_controls_special_characters() {
+    use multipart::NameEncoding;
+
+    let disposition = |encoding: Option<NameEncoding>| async move {
+        let mut form = multipart::Form::new::<TestGenerator>();
+
+        if let Some(encoding) = encoding {
+            form.name_encoding(encoding);
+        }
+        form.add_bytes_file("a\"b", &b"a,b,c"[..], "data\r\n.csv");
+
+        let body = collect(form).await;
+        let body = String::from_utf8(body).unwrap();
+
+        body.lines()
+            .find(|line| line.starts_with("Content-Disposition"))
+            .map(String::from)
+    };
+
+    // Browsers percent-encode special characters, so it is the default.
+    //
+    assert_eq!(
+        disposition(None).await.unwrap(),
+        "Content-Disposition: form-data; name=\"a%22b\"; filename=\"data%0D%0A.csv\""
+    );
+    assert_eq!(
+        disposition(Some(NameEncoding::Html5)).await.unwrap(),
+        "Content-Disposition: form-data; name=\"a%22b\"; filename=\"data%0D%0A.csv\""
+    );
+    assert_eq!(
+        disposition(Some(NameEncoding::Raw)).await.unwrap(),
+        "Content-Disposition: form-data; name=\"a\"b\"; filename=\"data"
+    );
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn non_utf8_filename_strategies() {
+    use multipart::NonUtf8Filename;
+    use std::{ffi::OsStr, os::unix::ffi::OsStrExt};
+
+    let disposition = |strategy| async move {
+        let mut form = multipart::Form::new::<TestGenerator>();
+        let part = multipart::Part::bytes::<_, String, _>("file", &b"a,b,c"[..], None)
+            .filename_os(OsStr::from_bytes(b"caf\xe9 100%.csv"));
+
+        form.non_utf8_filenames(strategy);
+        form.add_part(part);
+
+        let body = multipart::Body::from(form).collect_bytes().await?;
+        let body = String::from_utf8(body.to_vec()).unwrap();
+
+        Ok::<_, hyper_multipart_rfc7578::client::Error>(
+            body.lines()
+                .find(|line| line.starts_with("Content-Disposition"))
+                .map(String::from)
+                .unwrap(),
+        )
+    };
+
+    assert_eq!(
+        disposition(NonUtf8Filename::Lossy).await.unwrap(),
+        "Content-Disposition: form-data; name=\"file\"; filename=\"caf\u{fffd} 100%.csv\""
+    );
+    assert_eq!(
+        disposition(NonUtf8Filename::PercentEncode).await.unwrap(),
+        "Content-Disposition: form-data; name=\"file\"; filename=\"caf%E9 100%25.csv\""
+    );
+    assert_eq!(
+        disposition(NonUtf8Filename::Extended).await.unwrap(),
+        "Content-Disposition: form-data; name=\"file\"; filename=\"caf\u{fffd} 100%.csv\"; \
+         filename*=UTF-8''caf%E9%20100%25.csv"
+    );
+    assert!(matches!(
+        disposition(NonUtf8Filename::Reject).await,
+        Err(hyper_multipart_rfc7578::client::Error::InvalidFilename { index: 0, .. })
+    ));
+}
+
+#[tokio::test]
+async fn part_serializer_controls_the_layout() {
+    use multipart::{DefaultSerializer, HeaderOptions, Part, PartSerializer};
+    use std::io::{self, Write};
+
+    struct Marked;
+
+    impl PartSerializer for Marked {
+        fn write_headers(
+            &self,
+            write: &mut dyn Write,
+            part: &Part,
+            options: &HeaderOptions,
+        ) -> io::Result<()> {
+            write!(write, "\r\nX-Part: {}", options.index)?;
+            DefaultSerializer.write_headers(write, part, options)
+        }
+
+        fn write_final_boundary(&self, write: &mut dyn Write, boundary: &str) -> io::Result<()> {
+            DefaultSerializer.write_final_boundary(write, boundary)?;
+            write.write_all(b"\r\n")
+        }
+    }
+
+    let mut form = multipart::Form::new::<TestGenerator>();
+
+    form.part_serializer(Marked);
+    form.add_text("a", "1");
+    form.add_text("b", "2");
+
+    let len = form.content_length().unwrap();
+    let body = collect(form).await;
+
+    assert_eq!(body.len() as u64, len);
+    assert_eq!(
+        std::str::from_utf8(&body).unwrap(),
+        "\r\n\
+         --test\r\n\
+         X-Part: 0\r\n\
+         Content-Type: text/plain\r\n\
+         Content-Disposition: form-data; name=\"a\"\r\n\
+         \r\n\
+         1\r\n\
+         --test\r\n\
+         X-Part: 1\r\n\
+         Content-Type: text/plain\r\n\
+         Content-Disposition: form-data; name=\"b\"\r\n\
+         \r\n\
+         2\r\n\
+         --test--\r\n"
+    );
+}
+
+#[tokio::test]
+async fn disposition_params_follow_the_filename() {
+    let mut form = multipart::Form::new::<TestGenerator>();
+
+    form.add_part(
+        multipart::Part::bytes("file", &b"a,b,c"[..], Some("data.csv"))
+            .disposition_param("modification-date", "Wed, 12 Feb 1997 16:29:51 -0500")
+            .disposition_param("size", "5"),
+    );
+
+    let body = collect(form).await;
+    let header = "Content-Disposition: form-data; name=\"file\"; filename=\"data.csv\"; \
+        modification-date=\"Wed, 12 Feb 1997 16:29:51 -0500\"; size=5\r\n";
+
+    assert!(String::from_utf8(body).unwrap().contains(header));
+}
+
+#[tokio::test]
+async fn channel_parts_match_form_parts() {
+    let mut form = multipart::Form::new::<TestGenerator>();
+
+    form.add_text("text", "Hello World!");
+    form.add_async_reader("input", futures::io::Cursor::new("Hello Universe!"));
+    form.add_async_reader("empty", futures::io::empty());
+
+    let expected = collect(form).await;
+
+    let mut form = multipart::Form::new::<TestGenerator>();
+
+    form.add_text("text", "Hello World!");
+
+    let (mut sender, body) = form.into_channel();
+    let send = tokio::spawn(async move {
+        sender
+            .add_async_reader("input", futures::io::Cursor::new("Hello Universe!"))
+            .await
+            .unwrap();
+        sender
+            .add_stream(
+                "empty",
+                futures::stream::iter(Vec::<std::io::Result<&[u8]>>::new()),
+            )
+            .await
+            .unwrap();
+    });
+
+    let actual = body.collect_bytes().await.unwrap();
+
+    send.await.unwrap();
+
+    assert_eq!(&actual[..], &expected[..]);
+}
+
+#[tokio::test]
+async fn channel_without_parts_is_empty() {
+    let (sender, body) = multipart::channel("test");
+
+    sender.close();
+
+    assert!(body.collect_bytes().await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn part_writer_matches_reader() {
+    use futures::io::AsyncWriteExt;
+
+    let content = "a,b,c\n".repeat(1000);
+    let mut form = multipart::Form::new::<TestGenerator>();
+
+    form.add_async_reader_file(
+        "data",
+        futures::io::Cursor::new(content.clone()),
+        "data.csv",
+    );
+
+    let expected = collect(form).await;
+
+    let mut form = multipart::Form::new::<TestGenerator>();
+    let mut writer = form.add_writer("data", Some("data.csv"), None);
+    let write = tokio::spawn(async move {
+        for line in content.lines() {
+            writer.write_all(line.as_bytes()).await.unwrap();
+            writer.write_all(b"\n").await.unwrap();
+        }
+    });
+
+    let actual = collect(form).await;
+
+    write.await.unwrap();
+
+    assert_eq!(actual, expected);
+}
+
+#[tokio::test]
+async fn lazy_part_is_opened_when_reached() {
+    use std::{
+        io::{self, Cursor},
+        sync::{
+            atomic::{AtomicBool, Ordering},
+            Arc,
+        },
+    };
+
+    let opened = Arc::new(AtomicBool::new(false));
+    let mut form = multipart::Form::new::<TestGenerator>();
+
+    form.add_lazy("first", || -> io::Result<Cursor<&[u8]>> {
+        Err(io::Error::other("unavailable"))
+    });
+    form.add_lazy("second", {
+        let opened = opened.clone();
+
+        move || {
+            opened.store(true, Ordering::SeqCst);
+
+            Ok(Cursor::new("Hello World!"))
+        }
+    });
+
+    assert!(!opened.load(Ordering::SeqCst));
+    assert!(multipart::Body::from(form).collect_bytes().await.is_err());
+    assert!(!opened.load(Ordering::SeqCst));
+}
+
+#[cfg(feature = "tower")]
+#[tokio::test]
+async fn upload_service_posts_the_form() {
+    use http::{header::CONTENT_TYPE, Method, Request, Uri};
+    use http_body_util::{BodyExt, StreamBody};
+    use tower::{Layer, ServiceExt};
+
+    let mut form = multipart::Form::new::<TestGenerator>();
+
+    form.add_text("text", "Hello World!");
+
+    let content_type = form.content_type();
+    let expected = collect(form.try_clone().unwrap()).await;
+    let service = multipart::UploadLayer::new().layer(tower::service_fn(
+        |req: Request<StreamBody<multipart::Body>>| async move {
+            let (parts, body) = req.into_parts();
+            let body = body.collect().await?.to_bytes();
+
+            Ok::<_, multipart::BoxError>((parts, body))
+        },
+    ));
+    let uri = Uri::from_static("http://localhost/upload");
+    let (parts, body) = service.oneshot((uri, form)).await.unwrap();
+
+    assert_eq!(parts.method, Method::POST);
+    assert_eq!(parts.uri, "http://localhost/upload");
+    assert_eq!(parts.headers[CONTENT_TYPE], content_type);
+    assert_eq!(body, expected);
+}
+
+#[test]
+fn body_yields_after_a_number_of_frames() {
+    use futures::{
+        task::{self, ArcWake},
+        Stream,
+    };
+    use std::{
+        pin::Pin,
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+        task::{Context, Poll},
+    };
+
+    struct CountingWaker(AtomicUsize);
+
+    impl ArcWake for CountingWaker {
+        fn wake_by_ref(arc_self: &Arc<Self>) {
+            arc_self.0.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    let mut form = multipart::Form::new::<TestGenerator>();
+
+    form.body_config().chunk_size(16).yield_after(4);
+    form.add_reader("data", std::io::Cursor::new(vec![0; 1024]));
+
+    let wakes = Arc::new(CountingWaker(AtomicUsize::new(0)));
+    let waker = task::waker(wakes.clone());
+    let mut ctx = Context::from_waker(&waker);
+    let mut stream = multipart::Body::from(form).into_data_stream();
+
+    for _ in 0..4 {
+        assert!(matches!(
+            Pin::new(&mut stream).poll_next(&mut ctx),
+            Poll::Ready(Some(Ok(_)))
+        ));
+    }
+
+    assert!(Pin::new(&mut stream).poll_next(&mut ctx).is_pending());
+    assert_eq!(wakes.0.load(Ordering::SeqCst), 1);
+    assert!(matches!(
+        Pin::new(&mut stream).poll_next(&mut ctx),
+        Poll::Ready(Some(Ok(_)))
+    ));
+}
+
+#[cfg(all(feature = "tokio-uring", target_os = "linux"))]
+#[test]
+fn uring_file_matches_file() {
+    // Nothing is spawned until the body is polled, so the form can be built
+    // outside of the runtime.
+    let mut missing = multipart::Form::new::<TestGenerator>();
+
+    missing.add_uring_file("file", "missing.csv");
+
+    assert_eq!(missing.parts()[0].content_type(), "text/csv");
+
+    tokio_uring::start(async {
+        assert!(multipart::Body::from(missing)
+            .collect_bytes()
+            .await
+            .is_err());
+
+        let mut form = multipart::Form::new::<TestGenerator>();
+
+        form.add_file("file", "Cargo.toml").unwrap();
+
+        let expected = collect(form).await;
+        let mut form = multipart::Form::new::<TestGenerator>();
+
+        // The chunk size is read once the part starts.
+        form.add_uring_file("file", "Cargo.toml");
+        form.body_config().chunk_size(64);
+
+        let chunks: Vec<_> = multipart::Body::from(form)
+            .into_data_stream()
+            .try_collect()
+            .await
+            .unwrap();
+
+        assert_eq!(chunks[1].len(), 64);
+        assert_eq!(chunks.concat(), expected);
+    });
+}
@@ -0,0 +1,194 @@
+// Copyright 2017 rust-hyper-multipart-rfc7578 Developers
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+//
+
+#![cfg(feature = "http")]
+
+use bytes::Bytes;
+use futures::stream::{self, Iter};
+use http_body::Frame;
+use http_body_util::StreamBody;
+use hyper_multipart_rfc7578::server::{self, multipart::Multipart};
+use std::{convert::Infallible, vec::IntoIter};
+
+type Frames = StreamBody<Iter<IntoIter<Result<Frame<Bytes>, Infallible>>>>;
+
+/// Creates a parser over a body that is split into the given frames.
+fn parser(frames: &[&str]) -> Multipart<Frames> {
+    let frames = frames
+        .iter()
+        .map(|frame| Ok(Frame::data(Bytes::copy_from_slice(frame.as_bytes()))))
+        .collect::<Vec<_>>();
+
+    Multipart::new(StreamBody::new(stream::iter(frames)), "abc")
+}
+
+#[tokio::test]
+async fn part_size_is_limited() {
+    let mut multipart = parser(&[
+        "--abc\r\n\r\nHello\r\n",
+        "--abc\r\n\r\nHello ",
+        "World!\r\n--abc--",
+    ]);
+
+    multipart.max_part_size(5);
+
+    let part = multipart.next_part().await.unwrap().unwrap();
+
+    assert_eq!(&part.data()[..], b"Hello");
+    assert!(matches!(
+        multipart.next_part().await,
+        Err(server::Error::PartTooLarge { max: 5 })
+    ));
+}
+
+#[tokio::test]
+async fn body_errors_are_the_source() {
+    use std::error::Error;
+
+    let frames = vec![
+        Ok(Frame::data(Bytes::from_static(b"--abc\r\n"))),
+        Err(std::io::Error::other("connection reset")),
+    ];
+    let mut multipart = Multipart::new(StreamBody::new(stream::iter(frames)), "abc");
+    let err = multipart.next_part().await.unwrap_err();
+
+    assert_eq!(err.source().unwrap().to_string(), "connection reset");
+}
+
+#[tokio::test]
+async fn delimiter_split_across_frames() {
+    let mut multipart = parser(&[
+        "--abc\r\nContent-Disposition: form-data; name=\"a\"\r\n\r\nHello\r",
+        "\n--a",
+        "bc\r\nContent-Disposition: form-data; name=\"b\"\r\n\r\nWorld!\r\n-",
+        "-abc-",
+        "-",
+    ]);
+
+    let part = multipart.next_part().await.unwrap().unwrap();
+
+    assert_eq!(part.name(), Some("a"));
+    assert_eq!(&part.data()[..], b"Hello");
+
+    let part = multipart.next_part().await.unwrap().unwrap();
+
+    assert_eq!(part.name(), Some("b"));
+    assert_eq!(&part.data()[..], b"World!");
+    assert!(multipart.next_part().await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn preamble_is_skipped() {
+    let mut multipart = parser(&[
+        "This is a preamble, with --ab in it",
+        "\r\n--abc\r\n\r\nHello World!\r\n--abc--",
+    ]);
+
+    let part = multipart.next_part().await.unwrap().unwrap();
+
+    assert_eq!(&part.data()[..], b"Hello World!");
+    assert!(multipart.next_part().await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn transport_padding_after_boundary() {
+    let mut multipart = parser(&[
+        "--abc \t\r\n\r\nHello\r\n--abc\t",
+        " \r\n\r\nWorld!\r\n--abc--",
+    ]);
+
+    let part = multipart.next_part().await.unwrap().unwrap();
+
+    assert_eq!(&part.data()[..], b"Hello");
+
+    let part = multipart.next_part().await.unwrap().unwrap();
+
+    assert_eq!(&part.data()[..], b"World!");
+    assert!(multipart.next_part().await.unwrap().is_none());
+
+    let mut multipart = parser(&["--abc garbage\r\n\r\nHello\r\n--abc--"]);
+
+    assert!(matches!(
+        multipart.next_part().await,
+        Err(server::Error::InvalidBoundary)
+    ));
+}
+
+#[tokio::test]
+async fn headers_too_large() {
+    let header = format!("X-Padding: {}\r\n", "a".repeat(1024));
+    let mut frames = vec!["--abc\r\n".to_string()];
+
+    frames.extend(std::iter::repeat_n(header, 16));
+    frames.push("\r\nHello World!\r\n--abc--".to_string());
+
+    let frames = frames.iter().map(String::as_str).collect::<Vec<_>>();
+    let mut multipart = parser(&frames);
+
+    assert!(matches!(
+        multipart.next_part().await,
+        Err(server::Error::HeadersTooLarge)
+    ));
+}
+
+#[tokio::test]
+async fn unexpected_eof() {
+    for frames in [
+        &["--abc\r\n\r\nHello ", "World!"][..],
+        &[
+            "--abc\r\nContent-Disposition: form-data;",
+            " name=\"a\"\r\n",
+        ][..],
+        &["Only a preamble"][..],
+    ] {
+        let mut multipart = parser(frames);
+
+        assert!(
+            matches!(
+                multipart.next_part().await,
+                Err(server::Error::UnexpectedEof)
+            ),
+            "{:?}",
+            frames
+        );
+    }
+}
+
+#[tokio::test]
+async fn dropped_field_is_skipped() {
+    use futures::TryStreamExt;
+
+    let mut multipart = parser(&[
+        "--abc\r\nContent-Disposition: form-data; name=\"a\"\r\n\r\nHello",
+        " World, this content",
+        " is never read in full\r\n--abc\r\n",
+        "Content-Disposition: form-data; name=\"b\"\r\n\r\nSecond\r\n--abc--",
+    ]);
+
+    let mut field = multipart.next_field().await.unwrap().unwrap();
+
+    assert_eq!(field.name(), Some("a"));
+    assert!(field.try_next().await.unwrap().is_some());
+
+    drop(field);
+
+    let mut field = multipart.next_field().await.unwrap().unwrap();
+    let mut data = vec![];
+
+    assert_eq!(field.name(), Some("b"));
+
+    while let Some(chunk) = field.try_next().await.unwrap() {
+        data.extend_from_slice(&chunk);
+    }
+
+    assert_eq!(data, b"Second");
+
+    drop(field);
+
+    assert!(multipart.next_field().await.unwrap().is_none());
+}